@@ -4,8 +4,15 @@
 //! 本模块提供了数据块的定义和量化/反量化的实现。
 //! 包括对不同数据类型的量化支持，以及并行处理的扩展。
 
-use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
-use std::slice::{from_raw_parts, from_raw_parts_mut};
+use rayon::{
+    iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+use std::{
+    array::from_fn,
+    iter::zip,
+    slice::{from_raw_parts, from_raw_parts_mut, from_ref},
+};
 
 /// 数据块定义
 pub trait DataBlock: Sized + 'static {
@@ -56,6 +63,16 @@ pub trait Quantize<T, const N: usize>: DataBlock {
 
     /// 将当前类型的数据反量化为原始数据
     fn dequantize(&self) -> [T; N];
+
+    /// 批量反量化数据块，`src` 与 `dst` 等长
+    ///
+    /// 默认逐块调用 [`Quantize::dequantize`]，热点类型覆盖此方法以使用 SIMD 加速。
+    #[inline]
+    fn dequantize_blocks(src: &[Self], dst: &mut [[T; N]]) {
+        for (src, dst) in zip(src, dst) {
+            *dst = src.dequantize()
+        }
+    }
 }
 
 /// 为支持 `f16` 的数据块实现量化和反量化
@@ -71,6 +88,15 @@ where
     fn dequantize(&self) -> [f16; N] {
         self.dequantize().map(f16::from_f32)
     }
+
+    #[inline]
+    fn dequantize_blocks(src: &[Self], dst: &mut [[f16; N]]) {
+        for (src, dst) in zip(src, dst) {
+            let mut buf = [[0.; N]];
+            Blk::dequantize_blocks(from_ref(src), &mut buf);
+            *dst = buf[0].map(f16::from_f32)
+        }
+    }
 }
 
 /// 为支持 `bf16` 的数据块实现量化和反量化
@@ -87,6 +113,72 @@ where
     fn dequantize(&self) -> [bf16; N] {
         self.dequantize().map(bf16::from_f32)
     }
+
+    #[inline]
+    fn dequantize_blocks(src: &[Self], dst: &mut [[bf16; N]]) {
+        for (src, dst) in zip(src, dst) {
+            let mut buf = [[0.; N]];
+            Blk::dequantize_blocks(from_ref(src), &mut buf);
+            *dst = buf[0].map(bf16::from_f32)
+        }
+    }
+}
+
+/// 带重要性权重的量化特性
+///
+/// 重要性权重（imatrix）为每个元素给出一个非负的权重，量化时优先降低权重较大的元素的误差。
+///
+/// # 类型参数
+/// - `T`: 数据类型
+/// - `N`: 数据块大小
+pub trait QuantizeWeighted<T, const N: usize>: Quantize<T, N> {
+    /// 按重要性权重将数据量化为当前类型
+    fn quantize_weighted(data: &[T; N], weights: &[f32; N]) -> Self;
+}
+
+/// 为支持 `f16` 的数据块实现带权重的量化
+impl<Blk, const N: usize> QuantizeWeighted<f16, N> for Blk
+where
+    Blk: QuantizeWeighted<f32, N>,
+{
+    #[inline]
+    fn quantize_weighted(data: &[f16; N], weights: &[f32; N]) -> Self {
+        Self::quantize_weighted(&data.map(f16::to_f32), weights)
+    }
+}
+
+/// 为支持 `bf16` 的数据块实现带权重的量化
+impl<Blk, const N: usize> QuantizeWeighted<bf16, N> for Blk
+where
+    Blk: QuantizeWeighted<f32, N>,
+{
+    #[inline]
+    fn quantize_weighted(data: &[bf16; N], weights: &[f32; N]) -> Self {
+        Self::quantize_weighted(&data.map(bf16::to_f32), weights)
+    }
+}
+
+/// 切片操作使用的并行方式
+#[derive(Clone, Copy, Default, Debug)]
+pub enum Parallelism<'a> {
+    /// 使用 rayon 的全局线程池
+    #[default]
+    Global,
+    /// 使用指定的线程池
+    Pool(&'a rayon::ThreadPool),
+    /// 在当前线程上顺序执行
+    Sequential,
+}
+
+/// 按指定的并行方式执行 `for_each`，`$seq` 和 `$par` 分别是顺序和并行的迭代器
+macro_rules! for_each {
+    ($parallelism:expr; $seq:expr, $par:expr, $f:expr) => {
+        match $parallelism {
+            Parallelism::Sequential => $seq.for_each($f),
+            Parallelism::Global => $par.for_each($f),
+            Parallelism::Pool(pool) => pool.install(|| $par.for_each($f)),
+        }
+    };
 }
 
 /// 并行量化和反量化的扩展特性
@@ -97,10 +189,30 @@ where
 /// - `N`: 数据块大小
 pub trait QuantExt<T, const N: usize>: Sized {
     /// 将数据切片量化为目标类型
-    fn quantize_slice(dst: &mut [Self], src: &[T]) -> Result<(), QuantizeError>;
+    #[inline]
+    fn quantize_slice(dst: &mut [Self], src: &[T]) -> Result<(), QuantizeError> {
+        Self::quantize_slice_with(dst, src, Parallelism::Global)
+    }
 
     /// 将目标类型的数据切片反量化为原始数据
-    fn dequantize_slice(dst: &mut [T], src: &[Self]) -> Result<(), QuantizeError>;
+    #[inline]
+    fn dequantize_slice(dst: &mut [T], src: &[Self]) -> Result<(), QuantizeError> {
+        Self::dequantize_slice_with(dst, src, Parallelism::Global)
+    }
+
+    /// 以指定的并行方式将数据切片量化为目标类型
+    fn quantize_slice_with(
+        dst: &mut [Self],
+        src: &[T],
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError>;
+
+    /// 以指定的并行方式将目标类型的数据切片反量化为原始数据
+    fn dequantize_slice_with(
+        dst: &mut [T],
+        src: &[Self],
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError>;
 }
 
 /// 量化错误类型
@@ -112,54 +224,263 @@ pub enum QuantizeError {
     LengthMismatch,
 }
 
+/// 并行反量化时每个任务处理的数据块数量
+const DEQUANTIZE_BATCH: usize = 64;
+
 /// 为实现 `Quantize` 的数据块提供并行量化和反量化支持
 impl<Blk, T, const N: usize> QuantExt<T, N> for Blk
 where
     Blk: Quantize<T, N> + Send + Sync,
     T: Send + Sync,
 {
-    fn quantize_slice(dst: &mut [Self], src: &[T]) -> Result<(), QuantizeError> {
-        if src.len() % N != 0 {
+    fn quantize_slice_with(
+        dst: &mut [Self],
+        src: &[T],
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError> {
+        if !src.len().is_multiple_of(N) {
             return Err(QuantizeError::Indivisible);
         }
         if dst.len() != src.len() / N {
             return Err(QuantizeError::LengthMismatch);
         }
         let src = unsafe { from_raw_parts(src.as_ptr().cast::<[T; N]>(), dst.len()) };
-        dst.into_par_iter()
-            .zip(src)
-            .for_each(|(dst, src)| *dst = Blk::quantize(src));
+        for_each!(parallelism;
+            zip(dst.iter_mut(), src),
+            dst.into_par_iter().zip(src),
+            |(dst, src)| *dst = Blk::quantize(src)
+        );
         Ok(())
     }
 
-    fn dequantize_slice(dst: &mut [T], src: &[Self]) -> Result<(), QuantizeError> {
-        if dst.len() % N != 0 {
+    fn dequantize_slice_with(
+        dst: &mut [T],
+        src: &[Self],
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError> {
+        if !dst.len().is_multiple_of(N) {
             return Err(QuantizeError::Indivisible);
         }
         if src.len() != dst.len() / N {
             return Err(QuantizeError::LengthMismatch);
         }
         let dst = unsafe { from_raw_parts_mut(dst.as_mut_ptr().cast::<[T; N]>(), src.len()) };
-        src.into_par_iter()
-            .zip(dst)
-            .for_each(|(src, dst)| *dst = Blk::dequantize(src));
+        for_each!(parallelism;
+            zip(src.chunks(DEQUANTIZE_BATCH), dst.chunks_mut(DEQUANTIZE_BATCH)),
+            src.par_chunks(DEQUANTIZE_BATCH).zip(dst.par_chunks_mut(DEQUANTIZE_BATCH)),
+            |(src, dst)| Blk::dequantize_blocks(src, dst)
+        );
         Ok(())
     }
 }
 
+/// 并行带权重量化的扩展特性
+///
+/// # 类型参数
+///
+/// - `T`: 数据类型
+/// - `N`: 数据块大小
+pub trait QuantWeightedExt<T, const N: usize>: Sized {
+    /// 将数据切片按行量化为目标类型，`weights` 是每列的重要性权重，其长度即为行长
+    #[inline]
+    fn quantize_slice_weighted(
+        dst: &mut [Self],
+        src: &[T],
+        weights: &[f32],
+    ) -> Result<(), QuantizeError> {
+        Self::quantize_slice_weighted_with(dst, src, weights, Parallelism::Global)
+    }
+
+    /// 以指定的并行方式将数据切片按行量化为目标类型
+    fn quantize_slice_weighted_with(
+        dst: &mut [Self],
+        src: &[T],
+        weights: &[f32],
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError>;
+}
+
+/// 为实现 `QuantizeWeighted` 的数据块提供并行带权重量化支持
+impl<Blk, T, const N: usize> QuantWeightedExt<T, N> for Blk
+where
+    Blk: QuantizeWeighted<T, N> + Send + Sync,
+    T: Send + Sync,
+{
+    fn quantize_slice_weighted_with(
+        dst: &mut [Self],
+        src: &[T],
+        weights: &[f32],
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError> {
+        if weights.is_empty()
+            || !weights.len().is_multiple_of(N)
+            || !src.len().is_multiple_of(weights.len())
+        {
+            return Err(QuantizeError::Indivisible);
+        }
+        if dst.len() != src.len() / N {
+            return Err(QuantizeError::LengthMismatch);
+        }
+        let row = weights.len() / N;
+        let src = unsafe { from_raw_parts(src.as_ptr().cast::<[T; N]>(), dst.len()) };
+        let weights = unsafe { from_raw_parts(weights.as_ptr().cast::<[f32; N]>(), row) };
+        for_each!(parallelism;
+            zip(dst.iter_mut(), src).enumerate(),
+            dst.into_par_iter().zip(src).enumerate(),
+            |(i, (dst, src))| *dst = Blk::quantize_weighted(src, &weights[i % row])
+        );
+        Ok(())
+    }
+}
+
+/// 量化数据块与量化激活值的点积，对应 ggml 的 `vec_dot`
+///
+/// 激活值量化的类型 [`VecDot::Rhs`] 与 ggml 的 `vec_dot_type` 一致，
+/// 矩阵乘可以直接在量化权重上计算而无需先反量化。
+pub trait VecDot: DataBlock {
+    /// 激活值量化的数据块类型
+    type Rhs: DataBlock;
+
+    /// 计算一对数据块的点积
+    fn dot(&self, rhs: &Self::Rhs) -> f32;
+
+    /// 计算等长的数据块切片的点积
+    fn vec_dot(lhs: &[Self], rhs: &[Self::Rhs]) -> Result<f32, QuantizeError> {
+        if lhs.len() != rhs.len() {
+            return Err(QuantizeError::LengthMismatch);
+        }
+        Ok(zip(lhs, rhs).fold(0., |acc, (x, y)| acc + x.dot(y)))
+    }
+}
+
+/// 多行交错存储的数据块
+///
+/// 交错数据块由 `R` 行中同一列的 `B` 数据块重排而成，可以与 `B` 无损地相互转换。
+///
+/// # 类型参数
+///
+/// - `B`: 被交错的数据块类型
+/// - `R`: 交错的行数
+pub trait Interleave<B, const R: usize>: DataBlock {
+    /// 将 `R` 行中同一列的数据块交错为当前类型
+    fn interleave(blocks: [&B; R]) -> Self;
+
+    /// 将当前类型拆分为 `R` 行中同一列的数据块
+    fn deinterleave(&self) -> [B; R];
+}
+
+/// 并行交错和拆分的扩展特性
+///
+/// # 类型参数
+///
+/// - `B`: 被交错的数据块类型
+/// - `R`: 交错的行数
+pub trait InterleaveExt<B, const R: usize>: Sized {
+    /// 将每行 `row` 个数据块的数据切片交错为目标类型
+    #[inline]
+    fn interleave_slice(dst: &mut [Self], src: &[B], row: usize) -> Result<(), QuantizeError> {
+        Self::interleave_slice_with(dst, src, row, Parallelism::Global)
+    }
+
+    /// 将目标类型的数据切片拆分为每行 `row` 个数据块的数据
+    #[inline]
+    fn deinterleave_slice(dst: &mut [B], src: &[Self], row: usize) -> Result<(), QuantizeError> {
+        Self::deinterleave_slice_with(dst, src, row, Parallelism::Global)
+    }
+
+    /// 以指定的并行方式将每行 `row` 个数据块的数据切片交错为目标类型
+    fn interleave_slice_with(
+        dst: &mut [Self],
+        src: &[B],
+        row: usize,
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError>;
+
+    /// 以指定的并行方式将目标类型的数据切片拆分为每行 `row` 个数据块的数据
+    fn deinterleave_slice_with(
+        dst: &mut [B],
+        src: &[Self],
+        row: usize,
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError>;
+}
+
+/// 为实现 `Interleave` 的数据块提供并行交错和拆分支持
+impl<Blk, B, const R: usize> InterleaveExt<B, R> for Blk
+where
+    Blk: Interleave<B, R> + Send + Sync,
+    B: Send + Sync,
+{
+    fn interleave_slice_with(
+        dst: &mut [Self],
+        src: &[B],
+        row: usize,
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError> {
+        if row == 0 || !src.len().is_multiple_of(row * R) {
+            return Err(QuantizeError::Indivisible);
+        }
+        if dst.len() != src.len() / R {
+            return Err(QuantizeError::LengthMismatch);
+        }
+        for_each!(parallelism;
+            zip(dst.chunks_mut(row), src.chunks(row * R)),
+            dst.par_chunks_mut(row).zip(src.par_chunks(row * R)),
+            |(dst, src): (&mut [Self], &[B])| {
+                for (x, dst) in dst.iter_mut().enumerate() {
+                    *dst = Blk::interleave(from_fn(|i| &src[i * row + x]))
+                }
+            }
+        );
+        Ok(())
+    }
+
+    fn deinterleave_slice_with(
+        dst: &mut [B],
+        src: &[Self],
+        row: usize,
+        parallelism: Parallelism,
+    ) -> Result<(), QuantizeError> {
+        if row == 0 || !dst.len().is_multiple_of(row * R) {
+            return Err(QuantizeError::Indivisible);
+        }
+        if src.len() != dst.len() / R {
+            return Err(QuantizeError::LengthMismatch);
+        }
+        for_each!(parallelism;
+            zip(dst.chunks_mut(row * R), src.chunks(row)),
+            dst.par_chunks_mut(row * R).zip(src.par_chunks(row)),
+            |(dst, src): (&mut [B], &[Self])| {
+                for (x, src) in src.iter().enumerate() {
+                    for (i, blk) in src.deinterleave().into_iter().enumerate() {
+                        dst[i * row + x] = blk
+                    }
+                }
+            }
+        );
+        Ok(())
+    }
+}
+
+mod report;
+mod simd;
 mod structs;
+pub use report::QuantErrorStats;
+pub use simd::force_scalar;
 pub use structs::*;
 
 #[cfg(feature = "types")]
 pub extern crate digit_layout;
 
+pub extern crate rayon;
+
 /// 类型定义模块
 #[cfg(feature = "types")]
 pub mod types;
 
 #[cfg(test)]
 pub(crate) mod test_utils {
-    use crate::Quantize;
+    use crate::{DataBlock, Interleave, InterleaveExt, Q4_0, Quantize, QuantizeWeighted, VecDot};
     use std::fmt;
 
     /// 测试量化和反量化的工具函数
@@ -172,16 +493,111 @@ pub(crate) mod test_utils {
     /// - `rel`: 相对误差阈值
     pub fn test<const N: usize, T: Quantize<f32, N>>(abs: f32, rel: f32) {
         use rand::Rng;
-        use std::iter::zip;
 
         let mut data = [0.0f32; N];
         rand::rng().fill(&mut data[..]);
 
         let quant = T::quantize(&data);
-        let dequant = T::dequantize(&quant);
+        check(&data, &T::dequantize(&quant), abs, rel)
+    }
+
+    /// 测试带权重量化和反量化的工具函数
+    ///
+    /// # 参数
+    ///
+    /// - `N`: 数据块大小
+    /// - `T`: 数据类型
+    /// - `abs`: 绝对误差阈值
+    /// - `rel`: 相对误差阈值
+    pub fn test_weighted<const N: usize, T: QuantizeWeighted<f32, N>>(abs: f32, rel: f32) {
+        use rand::Rng;
+
+        let mut rng = rand::rng();
+        let mut data = [0.0f32; N];
+        rng.fill(&mut data[..]);
+        let weights: [f32; N] = std::array::from_fn(|_| rng.random_range(0.5..1.5));
+
+        let quant = T::quantize_weighted(&data, &weights);
+        check(&data, &T::dequantize(&quant), abs, rel)
+    }
+
+    /// 测试点积的工具函数，与反量化后的点积比较
+    ///
+    /// # 参数
+    ///
+    /// - `N`: 数据块大小
+    /// - `T`: 数据类型
+    /// - `err`: 允许的相对误差
+    pub fn test_vec_dot<const N: usize, T>(err: f32)
+    where
+        T: VecDot + Quantize<f32, N>,
+        T::Rhs: Quantize<f32, N>,
+    {
+        use rand::Rng;
+        use std::iter::zip;
+
+        const LEN: usize = 4;
+        let mut rng = rand::rng();
+        let mut x = vec![0.0f32; LEN * N];
+        let mut y = vec![0.0f32; LEN * N];
+        x.fill_with(|| rng.random_range(-1.0..1.0));
+        y.fill_with(|| rng.random_range(-1.0..1.0));
+
+        let x = x
+            .chunks_exact(N)
+            .map(|x| T::quantize(x.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        let y = y
+            .chunks_exact(N)
+            .map(|y| T::Rhs::quantize(y.try_into().unwrap()))
+            .collect::<Vec<_>>();
+
+        let expected = zip(&x, &y)
+            .flat_map(|(x, y)| zip(x.dequantize(), y.dequantize()))
+            .fold(0., |acc, (x, y)| acc + x as f64 * y as f64) as f32;
+        let actual = T::vec_dot(&x, &y).unwrap();
+        assert!(
+            (expected - actual).abs() <= err * (1. + expected.abs()),
+            "{expected} vs {actual}"
+        );
+
+        assert!(T::vec_dot(&x, &y[1..]).is_err())
+    }
+
+    /// 与 llama.cpp 参考实现的量化结果比较
+    ///
+    /// 输入由固定种子的线性同余生成器产生，`expected` 为参考实现对同一输入的量化结果，
+    /// `sum` 为参考实现反量化结果之和。
+    ///
+    /// # 参数
+    ///
+    /// - `N`: 数据块大小
+    /// - `T`: 数据类型
+    pub fn test_ref<const N: usize, T: Quantize<f32, N>>(expected: &[u8], sum: f32) {
+        let mut seed = 1u32;
+        let data = std::array::from_fn(|_| {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            (seed >> 8) as f32 / 16777216. * 2. - 1.
+        });
+
+        let quant = T::quantize(&data);
+        let bytes =
+            unsafe { std::slice::from_raw_parts((&raw const quant).cast::<u8>(), size_of::<T>()) };
+        assert_eq!(bytes, expected);
+
+        let actual = T::dequantize(&quant).iter().sum::<f32>();
+        assert!(
+            (actual - sum).abs() <= 1e-5 * (1. + sum.abs()),
+            "{actual} vs {sum}"
+        );
+    }
+
+    /// 检查反量化结果与原始数据的误差
+    fn check<const N: usize>(data: &[f32; N], dequant: &[f32; N], abs: f32, rel: f32) {
+        use std::iter::zip;
 
         let mut ec = ErrorCollector::new(abs, rel);
-        for (a, b) in zip(data, dequant) {
+        for (&a, &b) in zip(data, dequant) {
             ec.push(Diff::new(a, b))
         }
         println!("{ec}");
@@ -193,6 +609,39 @@ pub(crate) mod test_utils {
         assert!(ec.outliers().is_empty());
     }
 
+    /// 测试 [`Q4_0`] 交错和拆分的工具函数
+    ///
+    /// # 参数
+    ///
+    /// - `R`: 交错的行数
+    /// - `T`: 交错数据块类型
+    pub fn test_interleave<const R: usize, T: Interleave<Q4_0, R> + Send + Sync>() {
+        use rand::Rng;
+
+        const ROW: usize = 3;
+        let mut data = vec![0.0f32; 2 * R * ROW * 32];
+        rand::rng().fill(&mut data[..]);
+
+        let src = data
+            .chunks_exact(32)
+            .map(|x| Q4_0::quantize(x.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        let mut interleaved = (0..src.len() / R).map(|_| T::ZEROS).collect::<Vec<_>>();
+        T::interleave_slice(&mut interleaved, &src, ROW).unwrap();
+        let mut dst = (0..src.len()).map(|_| Q4_0::ZEROS).collect::<Vec<_>>();
+        T::deinterleave_slice(&mut dst, &interleaved, ROW).unwrap();
+
+        for (a, b) in std::iter::zip(&src, &dst) {
+            assert_eq!(a.delta.to_bits(), b.delta.to_bits());
+            assert_eq!(a.quants, b.quants);
+        }
+
+        // 交错块的布局与其元素数和大小一致
+        assert_eq!(T::COUNT, R * Q4_0::COUNT);
+        #[cfg(feature = "types")]
+        assert_eq!(T::ID.nbytes(), size_of::<T>());
+    }
+
     /// 差异计算
     struct Diff {
         /// 绝对误差
@@ -367,4 +816,55 @@ mod tests {
 
     // 以 Q8_0 为例生成测试
     generate_tests!(Q8_0, 4.5e-3);
+
+    #[test]
+    fn test_quant_slice_weighted() {
+        use crate::{IQ4NL, QuantWeightedExt, QuantizeWeighted};
+
+        let mut rng = rand::rng();
+        let input: Vec<f32> = (0..(4 * N)).map(|_| rng.random_range(-1.0..1.0)).collect();
+        let weights: Vec<f32> = (0..(2 * N)).map(|_| rng.random_range(0.5..1.5)).collect();
+        let mut quantized: Vec<IQ4NL> = (0..4).map(|_| IQ4NL::ZEROS).collect();
+
+        // 每行 2 个数据块，权重按列重复使用
+        IQ4NL::quantize_slice_weighted(&mut quantized, &input, &weights).unwrap();
+        for (i, (blk, x)) in quantized.iter().zip(input.chunks_exact(N)).enumerate() {
+            let w = &weights[i % 2 * N..][..N];
+            let ans = IQ4NL::quantize_weighted(x.try_into().unwrap(), w.try_into().unwrap());
+            assert_eq!(blk.delta.to_bits(), ans.delta.to_bits());
+            assert_eq!(blk.qs, ans.qs);
+        }
+
+        let err = IQ4NL::quantize_slice_weighted(&mut quantized, &input, &weights[..N + 1]);
+        assert_eq!(err, Err(crate::QuantizeError::Indivisible));
+        let err = IQ4NL::quantize_slice_weighted(&mut quantized[..3], &input, &weights);
+        assert_eq!(err, Err(crate::QuantizeError::LengthMismatch));
+    }
+
+    #[test]
+    fn test_parallelism() {
+        let mut rng = rand::rng();
+        let input: Vec<f32> = (0..(8 * N)).map(|_| rng.random_range(-1.0..1.0)).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+
+        let mut ans = (0..8).map(|_| Q8_0::ZEROS).collect::<Vec<_>>();
+        Q8_0::quantize_slice(&mut ans, &input).unwrap();
+        for parallelism in [Parallelism::Sequential, Parallelism::Pool(&pool)] {
+            let mut quantized = (0..8).map(|_| Q8_0::ZEROS).collect::<Vec<_>>();
+            Q8_0::quantize_slice_with(&mut quantized, &input, parallelism).unwrap();
+            for (a, b) in quantized.iter().zip(&ans) {
+                assert_eq!(a.delta.to_bits(), b.delta.to_bits());
+                assert_eq!(a.quants, b.quants);
+            }
+
+            let mut output = vec![0f32; input.len()];
+            let mut output_ = vec![0f32; input.len()];
+            Q8_0::dequantize_slice(&mut output, &ans).unwrap();
+            Q8_0::dequantize_slice_with(&mut output_, &quantized, parallelism).unwrap();
+            assert_eq!(output, output_);
+        }
+    }
 }
@@ -0,0 +1,107 @@
+//! 量化误差统计。
+
+use crate::{DataBlock, QuantExt, QuantizeError};
+use std::iter::zip;
+
+/// 一段数据量化再反量化后的误差统计
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct QuantErrorStats {
+    /// 均方根误差
+    pub rmse: f64,
+    /// 最大绝对误差
+    pub max_abs: f64,
+    /// 原始数据与还原数据的余弦相似度
+    pub cosine: f64,
+    /// 信噪比，单位为分贝，无误差时为正无穷
+    pub snr: f64,
+}
+
+impl QuantErrorStats {
+    /// 比较原始数据与还原数据，两者长度必须相同
+    pub fn new(original: &[f32], restored: &[f32]) -> Result<Self, QuantizeError> {
+        if original.len() != restored.len() {
+            return Err(QuantizeError::LengthMismatch);
+        }
+
+        let mut signal = 0.;
+        let mut noise = 0.;
+        let mut dot = 0.;
+        let mut restored_ = 0.;
+        let mut max_abs = 0f64;
+        for (&x, &y) in zip(original, restored) {
+            let (x, y) = (x as f64, y as f64);
+            let e = x - y;
+            signal += x * x;
+            noise += e * e;
+            dot += x * y;
+            restored_ += y * y;
+            max_abs = max_abs.max(e.abs());
+        }
+
+        let len = original.len().max(1) as f64;
+        let norm = (signal * restored_).sqrt();
+        Ok(Self {
+            rmse: (noise / len).sqrt(),
+            max_abs,
+            // 两者都为零向量时视为完全相同
+            cosine: if norm > 0. {
+                dot / norm
+            } else if signal == restored_ {
+                1.
+            } else {
+                0.
+            },
+            snr: if noise > 0. {
+                10. * (signal / noise).log10()
+            } else {
+                f64::INFINITY
+            },
+        })
+    }
+
+    /// 将数据量化为 `Blk` 再反量化，统计两者的误差
+    pub fn measure<Blk, const N: usize>(data: &[f32]) -> Result<Self, QuantizeError>
+    where
+        Blk: DataBlock + QuantExt<f32, N>,
+    {
+        if !data.len().is_multiple_of(N) {
+            return Err(QuantizeError::Indivisible);
+        }
+        let mut blocks = (0..data.len() / N).map(|_| Blk::ZEROS).collect::<Vec<_>>();
+        Blk::quantize_slice(&mut blocks, data)?;
+        let mut restored = vec![0.; data.len()];
+        Blk::dequantize_slice(&mut restored, &blocks)?;
+        Self::new(data, &restored)
+    }
+}
+
+#[test]
+fn test_stats() {
+    let stats = QuantErrorStats::new(&[1., 2., 3., 4.], &[1., 2., 3., 4.]).unwrap();
+    assert_eq!(stats.rmse, 0.);
+    assert_eq!(stats.max_abs, 0.);
+    assert!((stats.cosine - 1.).abs() < 1e-12);
+    assert_eq!(stats.snr, f64::INFINITY);
+
+    let stats = QuantErrorStats::new(&[1., -1., 1., -1.], &[1., -1., 1., 1.]).unwrap();
+    assert_eq!(stats.rmse, 1.);
+    assert_eq!(stats.max_abs, 2.);
+    assert_eq!(stats.cosine, 0.5);
+    assert!((stats.snr - 0.).abs() < 1e-12);
+
+    assert_eq!(
+        QuantErrorStats::new(&[1.], &[]),
+        Err(QuantizeError::LengthMismatch)
+    );
+
+    let data = (0..256).map(|i| (i as f32 / 16.).sin()).collect::<Vec<_>>();
+    let q8_0 = QuantErrorStats::measure::<crate::Q8_0, 32>(&data).unwrap();
+    let q4_0 = QuantErrorStats::measure::<crate::Q4_0, 32>(&data).unwrap();
+    assert!(q8_0.rmse < q4_0.rmse);
+    assert!(q8_0.snr > q4_0.snr);
+    assert!(q4_0.cosine > 0.99);
+    assert_eq!(
+        QuantErrorStats::measure::<crate::Q8_0, 32>(&data[..31]),
+        Err(QuantizeError::Indivisible)
+    );
+}
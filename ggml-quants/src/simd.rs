@@ -0,0 +1,547 @@
+//! SIMD 加速的反量化内核。
+//!
+//! 内核在运行时根据 CPU 特性选择，与标量实现逐位一致：
+//! 每个元素的浮点运算及其顺序都与标量实现相同，且不使用融合乘加。
+
+use crate::{Q4_0, Q4K, Q6K, Q8_0, Quantize};
+use std::{
+    iter::zip,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static FORCE_SCALAR: AtomicBool = AtomicBool::new(false);
+
+/// 强制反量化使用标量实现，用于测试或对比 SIMD 内核
+pub fn force_scalar(force: bool) {
+    FORCE_SCALAR.store(force, Ordering::Relaxed)
+}
+
+/// 可用的指令集
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Isa {
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "x86_64")]
+    Avx512,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+
+fn isa() -> Isa {
+    if FORCE_SCALAR.load(Ordering::Relaxed) {
+        return Isa::Scalar;
+    }
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx512f") {
+            return Isa::Avx512;
+        }
+        if is_x86_feature_detected!("avx2") {
+            return Isa::Avx2;
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        if std::arch::is_aarch64_feature_detected!("neon") {
+            return Isa::Neon;
+        }
+    }
+    Isa::Scalar
+}
+
+fn scalar<B: Quantize<f32, N>, const N: usize>(src: &[B], dst: &mut [[f32; N]]) {
+    for (src, dst) in zip(src, dst) {
+        *dst = src.dequantize()
+    }
+}
+
+macro_rules! dispatch {
+    ($( $(#[$attr:meta])* $name:ident: $ty:ty, $n:expr; )+) => {
+        $(
+            $(#[$attr])*
+            pub(crate) fn $name(src: &[$ty], dst: &mut [[f32; $n]]) {
+                assert_eq!(src.len(), dst.len());
+                match isa() {
+                    Isa::Scalar => scalar(src, dst),
+                    #[cfg(target_arch = "x86_64")]
+                    Isa::Avx2 => unsafe { x86::avx2::$name(src, dst) },
+                    #[cfg(target_arch = "x86_64")]
+                    Isa::Avx512 => unsafe { x86::avx512::$name(src, dst) },
+                    #[cfg(target_arch = "aarch64")]
+                    Isa::Neon => unsafe { neon::$name(src, dst) },
+                }
+            }
+        )+
+    };
+}
+
+dispatch! {
+    /// 批量反量化 [`Q4_0`]
+    dequantize_q4_0: Q4_0, 32;
+    /// 批量反量化 [`Q8_0`]
+    dequantize_q8_0: Q8_0, 32;
+    /// 批量反量化 [`Q4K`]
+    dequantize_q4k: Q4K, 256;
+    /// 批量反量化 [`Q6K`]
+    dequantize_q6k: Q6K, 256;
+}
+
+/// Q4K 第 `i` 个 64 元素分组中低、高半组的缩放因子和最小值，计算方式与标量实现相同
+#[inline(always)]
+fn q4k_group(blk: &Q4K, i: usize) -> [(f32, f32); 2] {
+    use crate::structs::scale_min_k4;
+    let (delta, min) = blk.delta_min.to_f32();
+    [2 * i, 2 * i + 1].map(|j| {
+        let (sc, m) = scale_min_k4(j, &blk.scales);
+        (delta * sc as f32, min * m as f32)
+    })
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    pub(super) mod avx2 {
+        use super::super::q4k_group;
+        use crate::{Q4_0, Q4K, Q6K, Q8_0};
+        use std::arch::x86_64::*;
+
+        /// 将 8 个 u8 扩展为 i32
+        #[inline]
+        #[target_feature(enable = "avx2")]
+        unsafe fn cvt_u8(ptr: *const u8) -> __m256i {
+            _mm256_cvtepu8_epi32(unsafe { _mm_loadl_epi64(ptr.cast()) })
+        }
+
+        /// 取出 Q6K 高位量化值中的 2 位并移到第 4、5 位
+        #[inline]
+        #[target_feature(enable = "avx2")]
+        fn q6k_high(h: __m256i, shift: i32) -> __m256i {
+            let h = _mm256_srlv_epi32(h, _mm256_set1_epi32(shift));
+            _mm256_slli_epi32(_mm256_and_si256(h, _mm256_set1_epi32(3)), 4)
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub(crate) unsafe fn dequantize_q4_0(src: &[Q4_0], dst: &mut [[f32; 32]]) {
+            let mask = _mm_set1_epi8(0xf);
+            let eight = _mm256_set1_epi32(8);
+            for (blk, y) in src.iter().zip(dst) {
+                let d = _mm256_set1_ps(blk.delta.to_f32());
+                let q = unsafe { _mm_loadu_si128(blk.quants.as_ptr().cast()) };
+                let l = _mm_and_si128(q, mask);
+                let h = _mm_and_si128(_mm_srli_epi16(q, 4), mask);
+                for (k, q) in [l, _mm_srli_si128(l, 8), h, _mm_srli_si128(h, 8)]
+                    .into_iter()
+                    .enumerate()
+                {
+                    let q = _mm256_sub_epi32(_mm256_cvtepu8_epi32(q), eight);
+                    let y_ = _mm256_mul_ps(_mm256_cvtepi32_ps(q), d);
+                    unsafe { _mm256_storeu_ps(y[8 * k..].as_mut_ptr(), y_) }
+                }
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub(crate) unsafe fn dequantize_q8_0(src: &[Q8_0], dst: &mut [[f32; 32]]) {
+            for (blk, y) in src.iter().zip(dst) {
+                let d = _mm256_set1_ps(blk.delta.to_f32());
+                for k in 0..4 {
+                    let q = unsafe { _mm_loadl_epi64(blk.quants[8 * k..].as_ptr().cast()) };
+                    let q = _mm256_cvtepi8_epi32(q);
+                    let y_ = _mm256_mul_ps(_mm256_cvtepi32_ps(q), d);
+                    unsafe { _mm256_storeu_ps(y[8 * k..].as_mut_ptr(), y_) }
+                }
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub(crate) unsafe fn dequantize_q4k(src: &[Q4K], dst: &mut [[f32; 256]]) {
+            let mask = _mm256_set1_epi32(0xf);
+            for (blk, y) in src.iter().zip(dst) {
+                for (i, (y, q)) in y
+                    .chunks_exact_mut(64)
+                    .zip(blk.qs.chunks_exact(32))
+                    .enumerate()
+                {
+                    let [(d1, m1), (d2, m2)] = q4k_group(blk, i);
+                    let (d1, m1) = (_mm256_set1_ps(d1), _mm256_set1_ps(m1));
+                    let (d2, m2) = (_mm256_set1_ps(d2), _mm256_set1_ps(m2));
+                    for j in (0..32).step_by(8) {
+                        let q = unsafe { cvt_u8(q[j..].as_ptr()) };
+                        let l = _mm256_cvtepi32_ps(_mm256_and_si256(q, mask));
+                        let h = _mm256_cvtepi32_ps(_mm256_srli_epi32(q, 4));
+                        let l = _mm256_sub_ps(_mm256_mul_ps(d1, l), m1);
+                        let h = _mm256_sub_ps(_mm256_mul_ps(d2, h), m2);
+                        unsafe { _mm256_storeu_ps(y[j..].as_mut_ptr(), l) }
+                        unsafe { _mm256_storeu_ps(y[32 + j..].as_mut_ptr(), h) }
+                    }
+                }
+            }
+        }
+
+        #[target_feature(enable = "avx2")]
+        pub(crate) unsafe fn dequantize_q6k(src: &[Q6K], dst: &mut [[f32; 256]]) {
+            let mask = _mm256_set1_epi32(0xf);
+            let bias = _mm256_set1_epi32(32);
+            for (blk, y) in src.iter().zip(dst) {
+                let delta = blk.delta.to_f32();
+                for ((y, ql), (qh, sc)) in y
+                    .chunks_exact_mut(128)
+                    .zip(blk.ql.chunks_exact(64))
+                    .zip(blk.qh.chunks_exact(32).zip(blk.scales.chunks_exact(8)))
+                {
+                    for i in (0..32).step_by(8) {
+                        let is = i / 16;
+                        let a = unsafe { cvt_u8(ql[i..].as_ptr()) };
+                        let b = unsafe { cvt_u8(ql[i + 32..].as_ptr()) };
+                        let h = unsafe { cvt_u8(qh[i..].as_ptr()) };
+                        let q = [
+                            _mm256_or_si256(_mm256_and_si256(a, mask), q6k_high(h, 0)),
+                            _mm256_or_si256(_mm256_and_si256(b, mask), q6k_high(h, 2)),
+                            _mm256_or_si256(_mm256_srli_epi32(a, 4), q6k_high(h, 4)),
+                            _mm256_or_si256(_mm256_srli_epi32(b, 4), q6k_high(h, 6)),
+                        ];
+                        for (k, q) in q.into_iter().enumerate() {
+                            let d = _mm256_set1_ps(delta * sc[is + 2 * k] as f32);
+                            let q = _mm256_cvtepi32_ps(_mm256_sub_epi32(q, bias));
+                            let y_ = _mm256_mul_ps(d, q);
+                            unsafe { _mm256_storeu_ps(y[i + k * 32..].as_mut_ptr(), y_) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub(super) mod avx512 {
+        use super::super::q4k_group;
+        use crate::{Q4_0, Q4K, Q6K, Q8_0};
+        use std::arch::x86_64::*;
+
+        /// 将 16 个 u8 扩展为 i32
+        #[inline]
+        #[target_feature(enable = "avx512f")]
+        unsafe fn cvt_u8(ptr: *const u8) -> __m512i {
+            _mm512_cvtepu8_epi32(unsafe { _mm_loadu_si128(ptr.cast()) })
+        }
+
+        /// 取出 Q6K 高位量化值中的 2 位并移到第 4、5 位
+        #[inline]
+        #[target_feature(enable = "avx512f")]
+        fn q6k_high(h: __m512i, shift: i32) -> __m512i {
+            let h = _mm512_srlv_epi32(h, _mm512_set1_epi32(shift));
+            _mm512_slli_epi32(_mm512_and_si512(h, _mm512_set1_epi32(3)), 4)
+        }
+
+        #[target_feature(enable = "avx512f")]
+        pub(crate) unsafe fn dequantize_q4_0(src: &[Q4_0], dst: &mut [[f32; 32]]) {
+            let mask = _mm512_set1_epi32(0xf);
+            let eight = _mm512_set1_epi32(8);
+            for (blk, y) in src.iter().zip(dst) {
+                let d = _mm512_set1_ps(blk.delta.to_f32());
+                let q = unsafe { cvt_u8(blk.quants.as_ptr()) };
+                let l = _mm512_and_si512(q, mask);
+                let h = _mm512_srli_epi32(q, 4);
+                for (k, q) in [l, h].into_iter().enumerate() {
+                    let q = _mm512_sub_epi32(q, eight);
+                    let y_ = _mm512_mul_ps(_mm512_cvtepi32_ps(q), d);
+                    unsafe { _mm512_storeu_ps(y[16 * k..].as_mut_ptr(), y_) }
+                }
+            }
+        }
+
+        #[target_feature(enable = "avx512f")]
+        pub(crate) unsafe fn dequantize_q8_0(src: &[Q8_0], dst: &mut [[f32; 32]]) {
+            for (blk, y) in src.iter().zip(dst) {
+                let d = _mm512_set1_ps(blk.delta.to_f32());
+                for k in 0..2 {
+                    let q = unsafe { _mm_loadu_si128(blk.quants[16 * k..].as_ptr().cast()) };
+                    let q = _mm512_cvtepi8_epi32(q);
+                    let y_ = _mm512_mul_ps(_mm512_cvtepi32_ps(q), d);
+                    unsafe { _mm512_storeu_ps(y[16 * k..].as_mut_ptr(), y_) }
+                }
+            }
+        }
+
+        #[target_feature(enable = "avx512f")]
+        pub(crate) unsafe fn dequantize_q4k(src: &[Q4K], dst: &mut [[f32; 256]]) {
+            let mask = _mm512_set1_epi32(0xf);
+            for (blk, y) in src.iter().zip(dst) {
+                for (i, (y, q)) in y
+                    .chunks_exact_mut(64)
+                    .zip(blk.qs.chunks_exact(32))
+                    .enumerate()
+                {
+                    let [(d1, m1), (d2, m2)] = q4k_group(blk, i);
+                    let (d1, m1) = (_mm512_set1_ps(d1), _mm512_set1_ps(m1));
+                    let (d2, m2) = (_mm512_set1_ps(d2), _mm512_set1_ps(m2));
+                    for j in (0..32).step_by(16) {
+                        let q = unsafe { cvt_u8(q[j..].as_ptr()) };
+                        let l = _mm512_cvtepi32_ps(_mm512_and_si512(q, mask));
+                        let h = _mm512_cvtepi32_ps(_mm512_srli_epi32(q, 4));
+                        let l = _mm512_sub_ps(_mm512_mul_ps(d1, l), m1);
+                        let h = _mm512_sub_ps(_mm512_mul_ps(d2, h), m2);
+                        unsafe { _mm512_storeu_ps(y[j..].as_mut_ptr(), l) }
+                        unsafe { _mm512_storeu_ps(y[32 + j..].as_mut_ptr(), h) }
+                    }
+                }
+            }
+        }
+
+        #[target_feature(enable = "avx512f")]
+        pub(crate) unsafe fn dequantize_q6k(src: &[Q6K], dst: &mut [[f32; 256]]) {
+            let mask = _mm512_set1_epi32(0xf);
+            let bias = _mm512_set1_epi32(32);
+            for (blk, y) in src.iter().zip(dst) {
+                let delta = blk.delta.to_f32();
+                for ((y, ql), (qh, sc)) in y
+                    .chunks_exact_mut(128)
+                    .zip(blk.ql.chunks_exact(64))
+                    .zip(blk.qh.chunks_exact(32).zip(blk.scales.chunks_exact(8)))
+                {
+                    for i in (0..32).step_by(16) {
+                        let is = i / 16;
+                        let a = unsafe { cvt_u8(ql[i..].as_ptr()) };
+                        let b = unsafe { cvt_u8(ql[i + 32..].as_ptr()) };
+                        let h = unsafe { cvt_u8(qh[i..].as_ptr()) };
+                        let q = [
+                            _mm512_or_si512(_mm512_and_si512(a, mask), q6k_high(h, 0)),
+                            _mm512_or_si512(_mm512_and_si512(b, mask), q6k_high(h, 2)),
+                            _mm512_or_si512(_mm512_srli_epi32(a, 4), q6k_high(h, 4)),
+                            _mm512_or_si512(_mm512_srli_epi32(b, 4), q6k_high(h, 6)),
+                        ];
+                        for (k, q) in q.into_iter().enumerate() {
+                            let d = _mm512_set1_ps(delta * sc[is + 2 * k] as f32);
+                            let q = _mm512_cvtepi32_ps(_mm512_sub_epi32(q, bias));
+                            let y_ = _mm512_mul_ps(d, q);
+                            unsafe { _mm512_storeu_ps(y[i + k * 32..].as_mut_ptr(), y_) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use super::q4k_group;
+    use crate::{Q4_0, Q4K, Q6K, Q8_0};
+    use std::arch::aarch64::*;
+
+    /// 将 16 个 u8 扩展为 4 组 i32
+    #[inline]
+    #[target_feature(enable = "neon")]
+    fn widen_u8(q: uint8x16_t) -> [int32x4_t; 4] {
+        let l = vmovl_u8(vget_low_u8(q));
+        let h = vmovl_high_u8(q);
+        [
+            vreinterpretq_s32_u32(vmovl_u16(vget_low_u16(l))),
+            vreinterpretq_s32_u32(vmovl_high_u16(l)),
+            vreinterpretq_s32_u32(vmovl_u16(vget_low_u16(h))),
+            vreinterpretq_s32_u32(vmovl_high_u16(h)),
+        ]
+    }
+
+    /// 将 16 个 i8 扩展为 4 组 i32
+    #[inline]
+    #[target_feature(enable = "neon")]
+    fn widen_i8(q: int8x16_t) -> [int32x4_t; 4] {
+        let l = vmovl_s8(vget_low_s8(q));
+        let h = vmovl_high_s8(q);
+        [
+            vmovl_s16(vget_low_s16(l)),
+            vmovl_high_s16(l),
+            vmovl_s16(vget_low_s16(h)),
+            vmovl_high_s16(h),
+        ]
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(crate) unsafe fn dequantize_q4_0(src: &[Q4_0], dst: &mut [[f32; 32]]) {
+        for (blk, y) in src.iter().zip(dst) {
+            let d = vdupq_n_f32(blk.delta.to_f32());
+            let eight = vdupq_n_s32(8);
+            let q = unsafe { vld1q_u8(blk.quants.as_ptr()) };
+            let l = vandq_u8(q, vdupq_n_u8(0xf));
+            let h = vshrq_n_u8(q, 4);
+            for (k, q) in [l, h].into_iter().enumerate() {
+                for (j, q) in widen_u8(q).into_iter().enumerate() {
+                    let y_ = vmulq_f32(vcvtq_f32_s32(vsubq_s32(q, eight)), d);
+                    unsafe { vst1q_f32(y[16 * k + 4 * j..].as_mut_ptr(), y_) }
+                }
+            }
+        }
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(crate) unsafe fn dequantize_q8_0(src: &[Q8_0], dst: &mut [[f32; 32]]) {
+        for (blk, y) in src.iter().zip(dst) {
+            let d = vdupq_n_f32(blk.delta.to_f32());
+            for k in 0..2 {
+                let q = unsafe { vld1q_s8(blk.quants[16 * k..].as_ptr()) };
+                for (j, q) in widen_i8(q).into_iter().enumerate() {
+                    let y_ = vmulq_f32(vcvtq_f32_s32(q), d);
+                    unsafe { vst1q_f32(y[16 * k + 4 * j..].as_mut_ptr(), y_) }
+                }
+            }
+        }
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(crate) unsafe fn dequantize_q4k(src: &[Q4K], dst: &mut [[f32; 256]]) {
+        for (blk, y) in src.iter().zip(dst) {
+            for (i, (y, q)) in y
+                .chunks_exact_mut(64)
+                .zip(blk.qs.chunks_exact(32))
+                .enumerate()
+            {
+                let [(d1, m1), (d2, m2)] = q4k_group(blk, i);
+                let (d1, m1) = (vdupq_n_f32(d1), vdupq_n_f32(m1));
+                let (d2, m2) = (vdupq_n_f32(d2), vdupq_n_f32(m2));
+                for j in (0..32).step_by(16) {
+                    let q = unsafe { vld1q_u8(q[j..].as_ptr()) };
+                    let l = widen_u8(vandq_u8(q, vdupq_n_u8(0xf)));
+                    let h = widen_u8(vshrq_n_u8(q, 4));
+                    for k in 0..4 {
+                        let l = vsubq_f32(vmulq_f32(d1, vcvtq_f32_s32(l[k])), m1);
+                        let h = vsubq_f32(vmulq_f32(d2, vcvtq_f32_s32(h[k])), m2);
+                        unsafe { vst1q_f32(y[j + 4 * k..].as_mut_ptr(), l) }
+                        unsafe { vst1q_f32(y[32 + j + 4 * k..].as_mut_ptr(), h) }
+                    }
+                }
+            }
+        }
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(crate) unsafe fn dequantize_q6k(src: &[Q6K], dst: &mut [[f32; 256]]) {
+        let mask = vdupq_n_u8(0xf);
+        let three = vdupq_n_u8(3);
+        for (blk, y) in src.iter().zip(dst) {
+            let delta = blk.delta.to_f32();
+            for ((y, ql), (qh, sc)) in y
+                .chunks_exact_mut(128)
+                .zip(blk.ql.chunks_exact(64))
+                .zip(blk.qh.chunks_exact(32).zip(blk.scales.chunks_exact(8)))
+            {
+                for i in (0..32).step_by(16) {
+                    let is = i / 16;
+                    let a = unsafe { vld1q_u8(ql[i..].as_ptr()) };
+                    let b = unsafe { vld1q_u8(ql[i + 32..].as_ptr()) };
+                    let h = unsafe { vld1q_u8(qh[i..].as_ptr()) };
+                    let q = [
+                        vorrq_u8(vandq_u8(a, mask), vshlq_n_u8(vandq_u8(h, three), 4)),
+                        vorrq_u8(
+                            vandq_u8(b, mask),
+                            vshlq_n_u8(vandq_u8(vshrq_n_u8(h, 2), three), 4),
+                        ),
+                        vorrq_u8(
+                            vshrq_n_u8(a, 4),
+                            vshlq_n_u8(vandq_u8(vshrq_n_u8(h, 4), three), 4),
+                        ),
+                        vorrq_u8(vshrq_n_u8(b, 4), vshlq_n_u8(vshrq_n_u8(h, 6), 4)),
+                    ];
+                    for (k, q) in q.into_iter().enumerate() {
+                        let d = vdupq_n_f32(delta * sc[is + 2 * k] as f32);
+                        let q = vsubq_s8(vreinterpretq_s8_u8(q), vdupq_n_s8(32));
+                        for (j, q) in widen_i8(q).into_iter().enumerate() {
+                            let y_ = vmulq_f32(d, vcvtq_f32_s32(q));
+                            unsafe { vst1q_f32(y[i + k * 32 + 4 * j..].as_mut_ptr(), y_) }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DataBlock;
+    use half::f16;
+    use rand::Rng;
+    use std::slice::from_raw_parts_mut;
+
+    /// 生成随机的数据块，缩放因子为有限值
+    fn random<B: DataBlock>(len: usize, fix: impl Fn(&mut B, f16, f16)) -> Vec<B> {
+        let mut rng = rand::rng();
+        let mut ans = (0..len).map(|_| B::ZEROS).collect::<Vec<_>>();
+        let bytes =
+            unsafe { from_raw_parts_mut(ans.as_mut_ptr().cast::<u8>(), size_of_val(&*ans)) };
+        rng.fill(bytes);
+        for blk in &mut ans {
+            let d = f16::from_f32(rng.random_range(-1e-2..1e-2));
+            let m = f16::from_f32(rng.random_range(-1e-2..1e-2));
+            fix(blk, d, m)
+        }
+        ans
+    }
+
+    type Kernel<B, const N: usize> = unsafe fn(&[B], &mut [[f32; N]]);
+
+    /// 检查所有可用的内核都与标量实现逐位一致
+    fn check<B: Quantize<f32, N>, const N: usize>(src: &[B], kernels: &[(bool, Kernel<B, N>)]) {
+        let mut expected = vec![[0.; N]; src.len()];
+        scalar(src, &mut expected);
+        for &(available, kernel) in kernels {
+            if !available {
+                continue;
+            }
+            let mut actual = vec![[f32::NAN; N]; src.len()];
+            unsafe { kernel(src, &mut actual) };
+            for (e, a) in zip(expected.as_flattened(), actual.as_flattened()) {
+                assert_eq!(e.to_bits(), a.to_bits())
+            }
+        }
+    }
+
+    #[test]
+    fn test_bit_identical() {
+        let q4_0 = random::<Q4_0>(64, |b, d, _| b.delta = d);
+        let q8_0 = random::<Q8_0>(64, |b, d, _| b.delta = d);
+        let q4k = random::<Q4K>(16, |b, d, m| {
+            b.delta_min.delta = d;
+            b.delta_min.min = m
+        });
+        let q6k = random::<Q6K>(16, |b, d, _| b.delta = d);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let avx2 = is_x86_feature_detected!("avx2");
+            let avx512 = is_x86_feature_detected!("avx512f");
+            use x86::{avx2 as a, avx512 as b};
+            check(
+                &q4_0,
+                &[(avx2, a::dequantize_q4_0), (avx512, b::dequantize_q4_0)],
+            );
+            check(
+                &q8_0,
+                &[(avx2, a::dequantize_q8_0), (avx512, b::dequantize_q8_0)],
+            );
+            check(
+                &q4k,
+                &[(avx2, a::dequantize_q4k), (avx512, b::dequantize_q4k)],
+            );
+            check(
+                &q6k,
+                &[(avx2, a::dequantize_q6k), (avx512, b::dequantize_q6k)],
+            );
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            let available = std::arch::is_aarch64_feature_detected!("neon");
+            check(&q4_0, &[(available, neon::dequantize_q4_0)]);
+            check(&q8_0, &[(available, neon::dequantize_q8_0)]);
+            check(&q4k, &[(available, neon::dequantize_q4k)]);
+            check(&q6k, &[(available, neon::dequantize_q6k)]);
+        }
+
+        // 按运行时特性分派的结果与标量实现一致，不修改全局的强制标量开关以免影响并行的测试
+        let mut dispatched = vec![[0.; 256]; q6k.len()];
+        let mut expected = vec![[0.; 256]; q6k.len()];
+        dequantize_q6k(&q6k, &mut dispatched);
+        scalar(&q6k, &mut expected);
+        assert_eq!(dispatched, expected)
+    }
+}
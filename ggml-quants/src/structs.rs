@@ -1,4 +1,5 @@
-﻿mod half;
+﻿mod grids;
+mod half;
 mod iq1m;
 mod iq1s;
 mod iq2s;
@@ -8,6 +9,7 @@ mod iq3s;
 mod iq3xxs;
 mod iq4nl;
 mod iq4xs;
+mod mxfp4;
 mod q2_k;
 mod q3_k;
 mod q4_0;
@@ -23,6 +25,8 @@ mod q6_k;
 mod q8_0;
 mod q8_1;
 mod q8_k;
+mod tq1_0;
+mod tq2_0;
 
 pub use ::half::{bf16, f16};
 pub use iq1m::IQ1M;
@@ -34,6 +38,7 @@ pub use iq3s::IQ3S;
 pub use iq3xxs::IQ3XXS;
 pub use iq4nl::IQ4NL;
 pub use iq4xs::IQ4XS;
+pub use mxfp4::MXFP4;
 pub use q2_k::Q2K;
 pub use q3_k::Q3K;
 pub use q4_0::Q4_0;
@@ -49,6 +54,10 @@ pub use q6_k::Q6K;
 pub use q8_0::Q8_0;
 pub use q8_1::Q8_1;
 pub use q8_k::Q8K;
+pub use tq1_0::TQ1_0;
+pub use tq2_0::TQ2_0;
+
+use std::{array::from_fn, iter::zip};
 
 /// 全局缩放因子和最小值
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
@@ -83,7 +92,7 @@ impl DeltaMin {
     }
 
     #[inline]
-    fn to_f32(self) -> (f32, f32) {
+    pub(crate) fn to_f32(self) -> (f32, f32) {
         (self.delta.to_f32(), self.min.to_f32())
     }
 }
@@ -99,6 +108,12 @@ fn max_by_abs(data: &[f32]) -> f32 {
         .fold(0., |acc, &x| if x.abs() > acc.abs() { x } else { acc })
 }
 
+#[inline]
+fn max_positive(data: &[f32]) -> f32 {
+    data.iter()
+        .fold(0., |acc, &x| if x > acc { x } else { acc })
+}
+
 #[inline]
 fn min_max(data: &[f32]) -> (f32, f32) {
     data.iter().fold((f32::MAX, f32::MIN), |(min, max), &x| {
@@ -106,6 +121,662 @@ fn min_max(data: &[f32]) -> (f32, f32) {
     })
 }
 
+/// 四舍六入五成双地取整，与 ggml 的 `nearest_int` 一致
+#[inline]
+fn nearest_int(x: f32) -> i32 {
+    x.round_ties_even() as _
+}
+
+/// 对称量化，搜索误差最小的缩放因子，量化值加上 `nmax` 偏移后写入 `l`
+///
+/// 与 ggml 的 `make_qx_quants` 逐位一致。
+fn make_qx_quants(nmax: i32, x: &[f32], l: &mut [i8], rmse_type: i32, qw: Option<&[f32]>) -> f32 {
+    let n = x.len();
+    let max = max_by_abs(x);
+    if max.abs() < GROUP_MAX_EPS {
+        l[..n].fill(0);
+        return 0.;
+    }
+
+    let quant = |iscale: f32, x: f32| nearest_int(iscale * x).clamp(-nmax, nmax - 1);
+    let mut iscale = -nmax as f32 / max;
+    if rmse_type == 0 {
+        for i in 0..n {
+            l[i] = (nmax + quant(iscale, x[i])) as _;
+        }
+        return iscale.recip();
+    }
+
+    let return_early = rmse_type < 0;
+    let rmse_type = rmse_type.abs();
+    let weight = |i: usize| match qw {
+        Some(qw) => qw[i],
+        None => match rmse_type {
+            1 => x[i] * x[i],
+            2 => 1.,
+            3 => x[i].abs(),
+            _ => x[i].abs().sqrt(),
+        },
+    };
+
+    let mut sumlx = 0.;
+    let mut suml2 = 0.;
+    for i in 0..n {
+        let q = quant(iscale, x[i]);
+        l[i] = (q + nmax) as _;
+        let w = weight(i);
+        let q = q as f32;
+        sumlx += w * x[i] * q;
+        suml2 += w * q * q;
+    }
+    let mut scale = if suml2 != 0. { sumlx / suml2 } else { 0. };
+    if return_early {
+        return if suml2 > 0. {
+            0.5 * (scale + iscale.recip())
+        } else {
+            iscale.recip()
+        };
+    }
+
+    let mut best = scale * sumlx;
+    for is in -9..=9 {
+        if is == 0 {
+            continue;
+        }
+        iscale = -(nmax as f32 + 0.1 * is as f32) / max;
+        sumlx = 0.;
+        suml2 = 0.;
+        for (i, &x) in x.iter().enumerate() {
+            let w = weight(i);
+            let q = quant(iscale, x) as f32;
+            sumlx += w * x * q;
+            suml2 += w * q * q;
+        }
+        if suml2 > 0. && sumlx * sumlx > best * suml2 {
+            for i in 0..n {
+                l[i] = (nmax + quant(iscale, x[i])) as _;
+            }
+            scale = sumlx / suml2;
+            best = scale * sumlx;
+        }
+    }
+    scale
+}
+
+/// 对称量化，以 `x²` 为权重迭代调整每个量化值，量化值加上 `nmax` 偏移后写入 `l`
+///
+/// 与 ggml 的 `make_q3_quants`（`do_rmse = true`）逐位一致。
+fn make_q3_quants(nmax: i32, x: &[f32], l: &mut [i8]) -> f32 {
+    let n = x.len();
+    let max = max_by_abs(x);
+    if max.abs() < GROUP_MAX_EPS {
+        l[..n].fill(0);
+        return 0.;
+    }
+
+    let iscale = -nmax as f32 / max;
+    let mut sumlx = 0.;
+    let mut suml2 = 0.;
+    for i in 0..n {
+        let q = nearest_int(iscale * x[i]).clamp(-nmax, nmax - 1);
+        l[i] = q as _;
+        let w = x[i] * x[i];
+        let q = q as f32;
+        sumlx += w * x[i] * q;
+        suml2 += w * q * q;
+    }
+    for _ in 0..5 {
+        let mut changed = false;
+        for i in 0..n {
+            let w = x[i] * x[i];
+            let li = l[i] as f32;
+            let mut slx = sumlx - w * x[i] * li;
+            if slx > 0. {
+                let mut sl2 = suml2 - w * li * li;
+                let new_l = nearest_int(x[i] * sl2 / slx).clamp(-nmax, nmax - 1);
+                if new_l != l[i] as i32 {
+                    let q = new_l as f32;
+                    slx += w * x[i] * q;
+                    sl2 += w * q * q;
+                    if sl2 > 0. && slx * slx * suml2 > sumlx * sumlx * sl2 {
+                        l[i] = new_l as _;
+                        sumlx = slx;
+                        suml2 = sl2;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    for l in &mut l[..n] {
+        *l += nmax as i8;
+    }
+    if suml2 > 0. { sumlx / suml2 } else { 0. }
+}
+
+/// 带最小值的加权量化，搜索误差最小的缩放因子和最小值，返回 `(scale, -min)`
+///
+/// 与 ggml 的 `make_qkx2_quants` 逐位一致。
+#[allow(clippy::too_many_arguments)]
+fn make_qkx2_quants(
+    nmax: i32,
+    x: &[f32],
+    weights: &[f32],
+    l: &mut [u8],
+    rmin: f32,
+    rdelta: f32,
+    nstep: usize,
+    use_mad: bool,
+) -> (f32, f32) {
+    let n = x.len();
+    let error = |diff: f32| if use_mad { diff.abs() } else { diff * diff };
+
+    let mut min = x[0];
+    let mut max = x[0];
+    let mut sum_w = weights[0];
+    let mut sum_x = sum_w * x[0];
+    for i in 1..n {
+        if x[i] < min {
+            min = x[i]
+        }
+        if x[i] > max {
+            max = x[i]
+        }
+        sum_w += weights[i];
+        sum_x += weights[i] * x[i];
+    }
+    if min > 0. {
+        min = 0.
+    }
+    if max == min {
+        l[..n].fill(0);
+        return (0., -min);
+    }
+
+    let mut iscale = nmax as f32 / (max - min);
+    let mut scale = iscale.recip();
+    let mut best_error = 0.;
+    for i in 0..n {
+        l[i] = nearest_int(iscale * (x[i] - min)).clamp(0, nmax) as _;
+        best_error += weights[i] * error(scale * l[i] as f32 + min - x[i]);
+    }
+    if nstep < 1 {
+        return (scale, -min);
+    }
+
+    let mut laux = [0u8; _32];
+    for is in 0..=nstep {
+        iscale = (rmin + rdelta * is as f32 + nmax as f32) / (max - min);
+        let mut sum_l = 0.;
+        let mut sum_l2 = 0.;
+        let mut sum_xl = 0.;
+        for i in 0..n {
+            let l = nearest_int(iscale * (x[i] - min)).clamp(0, nmax);
+            laux[i] = l as _;
+            let w = weights[i];
+            let l = l as f32;
+            sum_l += w * l;
+            sum_l2 += w * l * l;
+            sum_xl += w * l * x[i];
+        }
+        let d = sum_w * sum_l2 - sum_l * sum_l;
+        if d > 0. {
+            let mut this_scale = (sum_w * sum_xl - sum_x * sum_l) / d;
+            let mut this_min = (sum_l2 * sum_x - sum_l * sum_xl) / d;
+            if this_min > 0. {
+                this_min = 0.;
+                this_scale = sum_xl / sum_l2;
+            }
+            let mut cur_error = 0.;
+            for i in 0..n {
+                cur_error += weights[i] * error(this_scale * laux[i] as f32 + this_min - x[i]);
+            }
+            if cur_error < best_error {
+                l[..n].copy_from_slice(&laux[..n]);
+                best_error = cur_error;
+                scale = this_scale;
+                min = this_min;
+            }
+        }
+    }
+    (scale, -min)
+}
+
+/// 数据块的 `2Σx² / n`，ggml 以此修正 i-quant 系列和部分 K 系列的重要性权重
+#[inline]
+fn sigma2(x: &[f32]) -> f32 {
+    2. * x.iter().fold(0., |acc, x| acc + x * x) / x.len() as f32
+}
+
+/// 由重要性权重 `qw` 计算加权量化使用的元素权重 `qw * sqrt(sigma2 + x²)`，与 ggml 一致
+fn imatrix_weight<const N: usize>(x: &[f32], qw: &[f32], sigma2: f32) -> [f32; N] {
+    from_fn(|i| qw[i] * (sigma2 + x[i] * x[i]).sqrt())
+}
+
+/// 非负数据的加权量化，先搜索误差最小的缩放因子，再逐个调整量化值，量化值写入 `l`
+///
+/// 与 ggml 的 `make_qp_quants` 逐位一致。
+fn make_qp_quants(nmax: i32, x: &[f32], l: &mut [u8], qw: &[f32]) -> f32 {
+    let n = x.len();
+    let mut max = 0.;
+    for &x in x {
+        if x > max {
+            max = x
+        }
+    }
+    if max < GROUP_MAX_EPS {
+        l[..n].fill(0);
+        return 0.;
+    }
+
+    let mut iscale = nmax as f32 / max;
+    for i in 0..n {
+        l[i] = nearest_int(iscale * x[i]) as _;
+    }
+    let scale = iscale.recip();
+    let mut best_mse = 0.;
+    for i in 0..n {
+        let diff = x[i] - scale * l[i] as f32;
+        best_mse += qw[i] * diff * diff;
+    }
+    for is in -4..=4 {
+        if is == 0 {
+            continue;
+        }
+        let iscale_is = (0.1 * is as f32 + nmax as f32) / max;
+        let scale_is = iscale_is.recip();
+        let mut mse = 0.;
+        for i in 0..n {
+            let l = nearest_int(iscale_is * x[i]).min(nmax);
+            let diff = x[i] - scale_is * l as f32;
+            mse += qw[i] * diff * diff;
+        }
+        if mse < best_mse {
+            best_mse = mse;
+            iscale = iscale_is;
+        }
+    }
+
+    let mut sumlx = 0.;
+    let mut suml2 = 0.;
+    for i in 0..n {
+        l[i] = nearest_int(iscale * x[i]).min(nmax) as _;
+        let li = l[i] as f32;
+        sumlx += qw[i] * x[i] * li;
+        suml2 += qw[i] * li * li;
+    }
+    for _ in 0..5 {
+        let mut changed = false;
+        for i in 0..n {
+            let w = qw[i];
+            let li = l[i] as f32;
+            let mut slx = sumlx - w * x[i] * li;
+            let mut sl2 = suml2 - w * li * li;
+            if slx > 0. && sl2 > 0. {
+                let new_l = nearest_int(x[i] * sl2 / slx).min(nmax);
+                if new_l != l[i] as i32 {
+                    let q = new_l as f32;
+                    slx += w * x[i] * q;
+                    sl2 += w * q * q;
+                    if slx * slx * suml2 > sumlx * sumlx * sl2 {
+                        l[i] = new_l as _;
+                        sumlx = slx;
+                        suml2 = sl2;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    if suml2 > 0. { sumlx / suml2 } else { 0. }
+}
+
+/// 从 K 系列量化的 12 字节局部缩放因子中取出第 `j` 组的 6 位缩放因子和最小值
+#[inline]
+pub(crate) fn scale_min_k4(j: usize, q: &[u8; 12]) -> (u8, u8) {
+    if j < 4 {
+        (q[j] & 63, q[j + 4] & 63)
+    } else {
+        (
+            (q[j + 4] & 0xf) | ((q[j - 4] >> 6) << 4),
+            (q[j + 4] >> 4) | ((q[j] >> 6) << 4),
+        )
+    }
+}
+
+/// 将 8 组 6 位缩放因子和最小值打包为 K 系列量化的 12 字节局部缩放因子
+fn pack_scale_min_k4(scales: [u8; 8], mins: [u8; 8]) -> [u8; 12] {
+    let mut q = [0; 12];
+    for (j, (ls, lm)) in zip(scales, mins).enumerate() {
+        if j < 4 {
+            q[j] = ls;
+            q[j + 4] = lm;
+        } else {
+            q[j + 4] = (ls & 0xf) | ((lm & 0xf) << 4);
+            q[j - 4] |= (ls >> 4) << 6;
+            q[j] |= (lm >> 4) << 6;
+        }
+    }
+    q
+}
+
+/// 将 256 个 2 位量化值打包，每 128 个元素中第 `32j + l` 个值存入第 `l` 字节的第 `2j` 位
+fn pack_2bits(l: &[u8; _256]) -> [u8; _256 / 4] {
+    from_fn(|i| {
+        let (j, i) = (i / _32 * 4 * _32, i % _32);
+        l[j + i] | (l[j + i + _32] << 2) | (l[j + i + 2 * _32] << 4) | (l[j + i + 3 * _32] << 6)
+    })
+}
+
+/// 从打包的 2 位量化值中取出第 `i` 个，是 [`pack_2bits`] 的逆过程
+#[inline]
+fn unpack_2bits(qs: &[u8; _256 / 4], i: usize) -> u8 {
+    let (n, j, l) = (i / (4 * _32), i % (4 * _32) / _32, i % _32);
+    (qs[n * _32 + l] >> (2 * j)) & 3
+}
+
+/// `Q4K` 和 `Q5K` 共用的量化过程
+///
+/// 每 32 个元素搜索一组局部缩放因子和最小值，以 6 位量化并打包，再用量化后的参数计算 `[0, nmax]` 范围的量化值。
+/// 提供重要性权重 `qw` 时按 ggml 的加权方式搜索，此时 `rmin` 和 `nstep` 不生效。
+fn quantize_k4(
+    data: &[f32; _256],
+    qw: Option<&[f32; _256]>,
+    nmax: i32,
+    rmin: f32,
+    nstep: usize,
+) -> (DeltaMin, [u8; 12], [u8; _256]) {
+    let mut l = [0u8; _256];
+    let mut scales = [0.; _256 / _32];
+    let mut mins = [0.; _256 / _32];
+    let mut sw = [0.; _256 / _32];
+    let sigma2 = sigma2(data);
+    for (j, (x, l)) in zip(data.chunks_exact(_32), l.chunks_exact_mut(_32)).enumerate() {
+        (scales[j], mins[j]) = match qw {
+            Some(qw) => {
+                let weights: [f32; _32] = imatrix_weight(x, &qw[_32 * j..], sigma2);
+                sw[j] = weights.iter().fold(0., |acc, w| acc + w);
+                make_qkx2_quants(nmax, x, &weights, l, -0.9, 0.05, 36, false)
+            }
+            None => {
+                let av_x = (x.iter().fold(0., |acc, x| acc + x * x) / _32 as f32).sqrt();
+                let weights: [f32; _32] = from_fn(|i| av_x + x[i].abs());
+                make_qkx2_quants(nmax, x, &weights, l, rmin, 0.1, nstep, false)
+            }
+        }
+    }
+
+    // 局部缩放因子和最小值用 6 位量化
+    let (scales, delta_min) = if qw.is_some() {
+        let mut ls = [0u8; _256 / _32];
+        let mut lm = [0u8; _256 / _32];
+        let delta = make_qp_quants(63, &scales, &mut ls, &sw);
+        let min = make_qp_quants(63, &mins, &mut lm, &sw);
+        (pack_scale_min_k4(ls, lm), DeltaMin::new(delta, min))
+    } else {
+        let max_scale = max_positive(&scales);
+        let max_min = max_positive(&mins);
+        let inv_scale = if max_scale > 0. { 63. / max_scale } else { 0. };
+        let inv_min = if max_min > 0. { 63. / max_min } else { 0. };
+        let f = |x: f32| nearest_int(x).min(63) as u8;
+        (
+            pack_scale_min_k4(
+                scales.map(|x| f(inv_scale * x)),
+                mins.map(|x| f(inv_min * x)),
+            ),
+            DeltaMin::new(max_scale / 63., max_min / 63.),
+        )
+    };
+
+    // 用量化后的局部缩放因子和最小值重新计算量化值
+    let (delta, min) = delta_min.to_f32();
+    for (j, (x, l)) in zip(data.chunks_exact(_32), l.chunks_exact_mut(_32)).enumerate() {
+        let (sc, m) = scale_min_k4(j, &scales);
+        let d = delta * sc as f32;
+        if d == 0. {
+            continue;
+        }
+        let dm = min * m as f32;
+        for (l, &x) in zip(l, x) {
+            *l = nearest_int((x + dm) / d).clamp(0, nmax) as _;
+        }
+    }
+
+    (delta_min, scales, l)
+}
+
+/// `IQ3XXS` 和 `IQ3S` 共用的 32 元素子块量化过程，`xval` 为取绝对值后的数据，`max` 为其最大值
+///
+/// 在 `[-nstep, nstep]` 范围内搜索缩放因子，每 4 个元素选取码本中最近的格点，坐标写入 `l`。
+/// `refine_all` 为真时用最优缩放因子重新量化全部 4 元组，否则只重新量化不在码本上的 4 元组。
+/// 返回最优缩放因子和 8 个码本项序号。
+fn quantize_iq3_sub_block(
+    index: &grids::Iq3Index,
+    xval: &[f32; _32],
+    weight: &[f32; _32],
+    max: f32,
+    l: &mut [i8; _32],
+    nstep: i32,
+    refine_all: bool,
+) -> (f32, [u16; 8]) {
+    const KMAXQ: i32 = 8;
+    let waux: [f32; _32] = from_fn(|i| weight[i].sqrt());
+    let sums = |l: &[i8; _32]| {
+        let mut sumqx = 0.;
+        let mut sumq2 = 0.;
+        for ((&w, &x), &l) in zip(zip(weight, xval), l) {
+            let q = (2 * l + 1) as f32;
+            sumqx += w * x * q;
+            sumq2 += w * q * q;
+        }
+        (sumqx, sumq2)
+    };
+    let quant = |id: f32, x: f32| nearest_int(0.5 * (id * x - 1.)).clamp(0, KMAXQ - 1) as i8;
+
+    let mut best = 0.;
+    let mut scale = max / (2 * KMAXQ - 1) as f32;
+    let mut is_on_grid = [!refine_all; 8];
+    let mut laux = [0i8; _32];
+    let mut is_on_grid_aux = [true; 8];
+    for is in -nstep..=nstep {
+        let id = ((2 * KMAXQ - 1) as f32 + is as f32 * 0.2) / max;
+        let this_scale = 1. / id;
+        for (k, on_grid) in is_on_grid_aux.iter_mut().enumerate() {
+            let (l, x, w) = (
+                &mut laux[4 * k..][..4],
+                &xval[4 * k..][..4],
+                &waux[4 * k..][..4],
+            );
+            for (l, &x) in zip(&mut *l, x) {
+                *l = quant(id, x)
+            }
+            *on_grid = index.find(l, x, w, this_scale).1
+        }
+        let (sumqx, sumq2) = sums(&laux);
+        if sumq2 > 0. && sumqx * sumqx > best * sumq2 {
+            scale = sumqx / sumq2;
+            best = scale * sumqx;
+            *l = laux;
+            is_on_grid = is_on_grid_aux;
+        }
+    }
+
+    if is_on_grid.contains(&false) && scale > 0. {
+        let id = 1. / scale;
+        for (k, &on_grid) in is_on_grid.iter().enumerate() {
+            if on_grid && !refine_all {
+                continue;
+            }
+            let (l, x, w) = (
+                &mut l[4 * k..][..4],
+                &xval[4 * k..][..4],
+                &waux[4 * k..][..4],
+            );
+            for (l, &x) in zip(&mut *l, x) {
+                *l = quant(id, x)
+            }
+            index.find(l, x, w, scale);
+        }
+        let (sumqx, sumq2) = sums(l);
+        if sumq2 > 0. {
+            scale = sumqx / sumq2
+        }
+    }
+
+    let grid = from_fn(|k| {
+        index
+            .get(&l[4 * k..][..4])
+            .expect("quantized point should be on the grid")
+    });
+    (scale, grid)
+}
+
+/// 在非线性量化值表中查找与 `x` 最接近的项，与 ggml 的 `best_index_int8` 一致
+fn best_index_int8(values: &[i8], x: f32) -> u8 {
+    let n = values.len();
+    if x <= values[0] as f32 {
+        return 0;
+    }
+    if x >= values[n - 1] as f32 {
+        return (n - 1) as _;
+    }
+    let (mut ml, mut mu) = (0, n - 1);
+    while mu - ml > 1 {
+        let mav = (ml + mu) / 2;
+        if x < values[mav] as f32 {
+            mu = mav
+        } else {
+            ml = mav
+        }
+    }
+    if x - (values[mu - 1] as f32) < values[mu] as f32 - x {
+        (mu - 1) as _
+    } else {
+        mu as _
+    }
+}
+
+/// `IQ4NL` 和 `IQ4XS` 共用的子块缩放因子搜索，量化值序号写入 `l`
+///
+/// 在 `[-ntry, ntry]` 范围内搜索使加权误差最小的缩放因子，与 ggml 的 `quantize_row_iq4_nl_impl` 一致。
+/// 子块数据全为 0 时返回 0 且不修改 `l`。
+fn quantize_iq4_sub_block(
+    values: &[i8],
+    xb: &[f32],
+    weight: &[f32],
+    l: &mut [u8],
+    ntry: i32,
+) -> f32 {
+    let max = max_by_abs(xb);
+    if max.abs() < GROUP_MAX_EPS {
+        return 0.;
+    }
+
+    let values0 = values[0] as f32;
+    let sums = |id: f32, mut l: Option<&mut [u8]>| {
+        let mut sumqx = 0.;
+        let mut sumq2 = 0.;
+        for (j, (&x, &w)) in zip(xb, weight).enumerate() {
+            let i = best_index_int8(values, id * x);
+            if let Some(l) = l.as_deref_mut() {
+                l[j] = i
+            }
+            let q = values[i as usize] as f32;
+            sumqx += w * q * x;
+            sumq2 += w * q * q;
+        }
+        (sumqx, sumq2)
+    };
+
+    let d = if ntry > 0 {
+        -max / values0
+    } else {
+        max / values0
+    };
+    let (sumqx, sumq2) = sums(1. / d, Some(l));
+    let mut d = sumqx / sumq2;
+    let mut best = d * sumqx;
+    for itry in -ntry..=ntry {
+        let (sumqx, sumq2) = sums((itry as f32 + values0) / max, None);
+        if sumq2 > 0. && sumqx * sumqx > best * sumq2 {
+            d = sumqx / sumq2;
+            best = d * sumqx
+        }
+    }
+    d
+}
+
+/// 将 4 位量化值打包，每 32 个元素中前 16 个存入低 4 位，后 16 个存入高 4 位
+fn pack_4bits(l: &[u8], q4: &mut [u8]) {
+    for (q4, l) in zip(q4.chunks_exact_mut(16), l.chunks_exact(_32)) {
+        let (lo, hi) = l.split_at(16);
+        for (q, (&lo, &hi)) in zip(q4, zip(lo, hi)) {
+            *q = lo | (hi << 4)
+        }
+    }
+}
+
+/// 将一个格点码本项展开为 8 个值，按符号掩码取负并乘以缩放因子写入 `y`
+#[inline]
+fn dequantize_grid8(y: &mut [f32], d: f32, grid: u64, signs: u8) {
+    for (j, (y, g)) in zip(y, grid.to_le_bytes()).enumerate() {
+        let sign = if signs & grids::KMASK_IQ2XS[j] != 0 {
+            -1.
+        } else {
+            1.
+        };
+        *y = d * g as f32 * sign
+    }
+}
+
+/// 将一个 `IQ1S` 码本项展开为 8 个值，加上偏移后乘以缩放因子写入 `y`
+#[inline]
+fn dequantize_iq1_grid8(y: &mut [f32], dl: f32, grid: u64, delta: f32) {
+    for (y, g) in zip(y, grid.to_le_bytes()) {
+        *y = dl * (g as i8 as f32 + delta)
+    }
+}
+
+/// 以 `chunk` 字节为单位交错 `R` 个 [`Q4_0`] 的量化值，同时将偏移量化值转为有符号的 4 位补码
+fn interleave_q4_0<const R: usize, const M: usize>(blocks: [&Q4_0; R], chunk: usize) -> [u8; M] {
+    let mut ans = [0; M];
+    for (i, dst) in ans.chunks_exact_mut(chunk).enumerate() {
+        let src = &blocks[i % R].quants[i / R * chunk..][..chunk];
+        for (dst, src) in zip(dst, src) {
+            *dst = src ^ 0x88
+        }
+    }
+    ans
+}
+
+/// [`interleave_q4_0`] 的逆变换
+fn deinterleave_q4_0<const R: usize, const M: usize>(
+    delta: &[f16; R],
+    quants: &[u8; M],
+    chunk: usize,
+) -> [Q4_0; R] {
+    let mut ans = delta.map(|delta| Q4_0 {
+        delta,
+        quants: [0; _32 / 2],
+    });
+    for (i, src) in quants.chunks_exact(chunk).enumerate() {
+        let dst = &mut ans[i % R].quants[i / R * chunk..][..chunk];
+        for (dst, src) in zip(dst, src) {
+            *dst = src ^ 0x88
+        }
+    }
+    ans
+}
+
+const GROUP_MAX_EPS: f32 = 1e-15;
+const IQ1S_DELTA: f32 = 0.125;
+
 const _1: usize = 1;
 const _32: usize = 32;
 const _256: usize = 256;
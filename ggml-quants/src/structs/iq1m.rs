@@ -1,14 +1,14 @@
-﻿use super::_256;
+﻿use super::{_32, _256, IQ1S_DELTA, dequantize_iq1_grid8, f16, grids::IQ1S_GRID};
 use crate::{DataBlock, Quantize};
 
 /// IQ1M 量化结构体
 #[repr(C)]
 pub struct IQ1M {
-    /// 低位量化值
+    /// 码本索引的低 8 位
     pub qs: [u8; _256 / 8],
-    /// 高位量化值
+    /// 每 8 个元素的码本索引高 3 位和偏移符号
     pub qh: [u8; _256 / 16],
-    /// 缩放因子
+    /// 3 位局部缩放因子，每 16 位的最高 4 位拼接为全局缩放因子
     pub scales: [u8; _256 / 32],
 }
 
@@ -21,11 +21,70 @@ impl_data_block! {
     }
 }
 
+impl IQ1M {
+    /// 取出分散存储在局部缩放因子中的全局缩放因子
+    pub fn delta(&self) -> f16 {
+        let sc = self.scales_u16();
+        f16::from_bits(
+            (sc[0] >> 12) | ((sc[1] >> 8) & 0x00f0) | ((sc[2] >> 4) & 0x0f00) | (sc[3] & 0xf000),
+        )
+    }
+
+    #[inline]
+    fn scales_u16(&self) -> [u16; 4] {
+        std::array::from_fn(|i| u16::from_le_bytes([self.scales[2 * i], self.scales[2 * i + 1]]))
+    }
+}
+
 impl Quantize<f32, _256> for IQ1M {
     fn quantize(_data: &[f32; _256]) -> Self {
         todo!()
     }
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let d = self.delta().to_f32();
+        let sc = self.scales_u16();
+
+        let mut ans = [0.; _256];
+        for (ib, ((y, qs), qh)) in ans
+            .chunks_exact_mut(_32)
+            .zip(self.qs.chunks_exact(4))
+            .zip(self.qh.chunks_exact(2))
+            .enumerate()
+        {
+            let sc = sc[ib / 2] >> (6 * (ib % 2));
+            let dl = [
+                d * (2 * (sc & 7) + 1) as f32,
+                d * (2 * ((sc >> 3) & 7) + 1) as f32,
+            ];
+            // qh 的每 4 位为一组 8 个元素的码本索引高 3 位和偏移符号
+            for (l, (y, &q)) in y.chunks_exact_mut(8).zip(qs).enumerate() {
+                let h = qh[l / 2] >> (4 * (l % 2));
+                let grid = IQ1S_GRID[q as usize | ((h as usize & 7) << 8)];
+                let delta = if h & 8 != 0 { -IQ1S_DELTA } else { IQ1S_DELTA };
+                dequantize_iq1_grid8(y, dl[l / 2], grid, delta)
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq1m() {
+    let mut block = IQ1M::ZEROS;
+    // 全局缩放因子 1.0 = 0x3c00，分散存储在 4 个 16 位局部缩放因子的最高 4 位
+    block.scales[5] = 0xc0;
+    block.scales[7] = 0x30;
+    assert_eq!(block.delta(), f16::ONE);
+    // 第一组前半使用局部缩放因子 1，第二个 8 元组使用负偏移
+    block.scales[0] = 1;
+    block.qh[0] = 0x80;
+
+    let grid = IQ1S_GRID[0].to_le_bytes();
+    let expect: [f32; _256] = std::array::from_fn(|i| match i {
+        0..8 => 3. * (grid[i % 8] as i8 as f32 + IQ1S_DELTA),
+        8..16 => 3. * (grid[i % 8] as i8 as f32 - IQ1S_DELTA),
+        _ => grid[i % 8] as i8 as f32 + IQ1S_DELTA,
+    });
+    let ans: [f32; _256] = block.dequantize();
+    assert_eq!(ans, expect)
+}
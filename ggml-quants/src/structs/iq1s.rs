@@ -1,4 +1,4 @@
-use super::{_256, f16};
+use super::{_32, _256, IQ1S_DELTA, dequantize_iq1_grid8, f16, grids::IQ1S_GRID};
 use crate::{DataBlock, Quantize};
 
 /// IQ1S 量化结构体
@@ -6,9 +6,9 @@ use crate::{DataBlock, Quantize};
 pub struct IQ1S {
     /// 缩放因子
     pub delta: f16,
-    /// 低位量化值
+    /// 码本索引的低 8 位
     pub qs: [u8; _256 / 8],
-    /// 高位量化值
+    /// 每 32 个元素的码本索引高 3 位、局部缩放因子和偏移符号
     pub qh: [u16; _256 / 32],
 }
 
@@ -26,6 +26,42 @@ impl Quantize<f32, _256> for IQ1S {
         todo!()
     }
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let d = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for ((y, qs), &qh) in ans
+            .chunks_exact_mut(_32)
+            .zip(self.qs.chunks_exact(4))
+            .zip(&self.qh)
+        {
+            // 低 12 位为 4 个码本索引的高 3 位，其后 3 位为局部缩放因子，最高位为偏移符号
+            let dl = d * (2 * ((qh >> 12) & 7) + 1) as f32;
+            let delta = if qh & 0x8000 != 0 {
+                -IQ1S_DELTA
+            } else {
+                IQ1S_DELTA
+            };
+            for (l, (y, &q)) in y.chunks_exact_mut(8).zip(qs).enumerate() {
+                let grid = IQ1S_GRID[q as usize | ((qh as usize >> (3 * l)) & 7) << 8];
+                dequantize_iq1_grid8(y, dl, grid, delta)
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq1s() {
+    let mut block = IQ1S::ZEROS;
+    block.delta = f16::ONE;
+    // 第一组使用局部缩放因子 1 和负偏移
+    block.qh[0] = 0x9000;
+
+    let grid = IQ1S_GRID[0].to_le_bytes();
+    let expect: [f32; _256] = std::array::from_fn(|i| match i {
+        0.._32 => 3. * (grid[i % 8] as i8 as f32 - IQ1S_DELTA),
+        _ => grid[i % 8] as i8 as f32 + IQ1S_DELTA,
+    });
+    let ans: [f32; _256] = block.dequantize();
+    assert_eq!(ans, expect)
+}
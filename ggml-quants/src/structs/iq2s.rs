@@ -1,4 +1,4 @@
-use super::{_256, f16};
+use super::{_32, _256, dequantize_grid8, f16, grids::IQ2S_GRID};
 use crate::{DataBlock, Quantize};
 
 /// IQ2S 量化结构体
@@ -29,6 +29,40 @@ impl Quantize<f32, _256> for IQ2S {
         todo!()
     }
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        // 前 32 字节为码本索引的低 8 位，后 32 字节为符号掩码
+        let (qs, signs) = self.qs.split_at(_256 / 8);
+
+        let mut ans = [0.; _256];
+        for (ib, y) in ans.chunks_exact_mut(_32).enumerate() {
+            let sc = self.scales[ib];
+            let db = [
+                delta * (0.5 + (sc & 0xf) as f32) * 0.25,
+                delta * (0.5 + (sc >> 4) as f32) * 0.25,
+            ];
+            for (l, y) in y.chunks_exact_mut(8).enumerate() {
+                let i = 4 * ib + l;
+                let h = ((self.qh[ib] as usize) << (8 - 2 * l)) & 0x300;
+                dequantize_grid8(y, db[l / 2], IQ2S_GRID[qs[i] as usize | h], signs[i])
+            }
+        }
+        ans
+    }
+}
+
+#[test]
+fn test_iq2s() {
+    let mut block = IQ2S::ZEROS;
+    block.delta = f16::ONE;
+    // 第一个量化值的第 0 位取负，第二个量化值使用码本第 256 项
+    block.qs[_256 / 8] = 1;
+    block.qh[0] = 0b0100;
+
+    let mut expect = [1.; _256];
+    expect[0] = -1.;
+    for (y, g) in expect[8..16].iter_mut().zip(IQ2S_GRID[256].to_le_bytes()) {
+        *y = g as f32 * 0.125
     }
+    let ans: [f32; _256] = block.dequantize();
+    assert_eq!(ans, expect)
 }
@@ -1,4 +1,7 @@
-use super::{_256, f16};
+use super::{
+    _32, _256, dequantize_grid8, f16,
+    grids::{IQ2XS_GRID, KSIGNS_IQ2XS},
+};
 use crate::{DataBlock, Quantize};
 
 /// IQ2XS 量化结构体
@@ -6,10 +9,10 @@ use crate::{DataBlock, Quantize};
 pub struct IQ2XS {
     /// 缩放因子
     pub delta: f16,
-    /// 低位量化值
+    /// 量化值
     pub qs: [u16; _256 / 8],
-    /// 高位量化值
-    pub qh: [u8; _256 / 32],
+    /// 局部缩放因子
+    pub scales: [u8; _256 / 32],
 }
 
 impl_data_block! {
@@ -17,7 +20,7 @@ impl_data_block! {
     Self {
         delta: f16::ZERO,
         qs: [0; _256 / 8],
-        qh: [0; _256 / 32],
+        scales: [0; _256 / 32],
     }
 }
 
@@ -26,6 +29,41 @@ impl Quantize<f32, _256> for IQ2XS {
         todo!()
     }
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for ((y, qs), &sc) in ans
+            .chunks_exact_mut(_32)
+            .zip(self.qs.chunks_exact(4))
+            .zip(&self.scales)
+        {
+            let db = [
+                delta * (0.5 + (sc & 0xf) as f32) * 0.25,
+                delta * (0.5 + (sc >> 4) as f32) * 0.25,
+            ];
+            // 每个量化值低 9 位为码本索引，高 7 位为符号索引
+            for (l, (y, &q)) in y.chunks_exact_mut(8).zip(qs).enumerate() {
+                let grid = IQ2XS_GRID[q as usize & 511];
+                let signs = KSIGNS_IQ2XS[q as usize >> 9];
+                dequantize_grid8(y, db[l / 2], grid, signs)
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq2xs() {
+    let mut block = IQ2XS::ZEROS;
+    block.delta = f16::ONE;
+    // 第一组的后半使用局部缩放因子 1，第一个量化值使用符号索引 1
+    block.scales[0] = 0x10;
+    block.qs[0] = 1 << 9;
+
+    let mut expect = [1.; _256];
+    expect[16.._32].fill(3.);
+    expect[0] = -1.;
+    expect[7] = -1.;
+    let ans: [f32; _256] = block.dequantize();
+    assert_eq!(ans, expect)
+}
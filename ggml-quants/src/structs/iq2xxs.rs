@@ -1,4 +1,7 @@
-use super::{_256, f16};
+use super::{
+    _32, _256, dequantize_grid8, f16,
+    grids::{IQ2XXS_GRID, KSIGNS_IQ2XS},
+};
 use crate::{DataBlock, Quantize};
 
 /// IQ2XXS 量化结构体
@@ -23,6 +26,36 @@ impl Quantize<f32, _256> for IQ2XXS {
         todo!()
     }
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for (y, qs) in ans.chunks_exact_mut(_32).zip(self.qs.chunks_exact(4)) {
+            // 前 32 位为 4 个码本索引，后 32 位为 4 组 7 位符号索引和 4 位局部缩放因子
+            let grid = qs[0] as u32 | (qs[1] as u32) << 16;
+            let signs = qs[2] as u32 | (qs[3] as u32) << 16;
+            let db = delta * (0.5 + (signs >> 28) as f32) * 0.25;
+            for (l, y) in y.chunks_exact_mut(8).enumerate() {
+                let grid = IQ2XXS_GRID[(grid >> (8 * l)) as usize & 0xff];
+                let signs = KSIGNS_IQ2XS[(signs >> (7 * l)) as usize & 127];
+                dequantize_grid8(y, db, grid, signs)
+            }
+        }
+        ans
     }
 }
+
+#[test]
+fn test_iq2xxs() {
+    let mut block = IQ2XXS::ZEROS;
+    block.delta = f16::ONE;
+    // 第一组使用符号索引 1 和局部缩放因子 1
+    block.qs[2] = 1;
+    block.qs[3] = 1 << 12;
+
+    let mut expect = [1.; _256];
+    expect[.._32].fill(3.);
+    expect[0] = -3.;
+    expect[7] = -3.;
+    let ans: [f32; _256] = block.dequantize();
+    assert_eq!(ans, expect)
+}
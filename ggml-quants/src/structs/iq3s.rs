@@ -1,5 +1,10 @@
-use super::{_256, f16};
-use crate::{DataBlock, Quantize};
+use super::{
+    _32, _256, dequantize_grid8, f16,
+    grids::{IQ3S_GRID, IQ3S_INDEX},
+    imatrix_weight, nearest_int, quantize_iq3_sub_block, sigma2,
+};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
+use std::iter::zip;
 
 /// IQ3S 量化结构体
 #[repr(C)]
@@ -27,11 +32,120 @@ impl_data_block! {
     }
 }
 
+impl IQ3S {
+    /// 量化一个数据块，`qw` 为可选的重要性权重
+    fn quantize_impl(data: &[f32; _256], qw: Option<&[f32; _256]>) -> Self {
+        let sigma2 = sigma2(data);
+        let mut ans = Self::ZEROS;
+        let mut scales = [0.; _256 / _32];
+        let mut l = [0i8; _32];
+        let mut max_scale = 0.;
+        for (ib, xb) in data.chunks_exact(_32).enumerate() {
+            let weight: [f32; _32] = match qw {
+                Some(qw) => imatrix_weight(xb, &qw[_32 * ib..], sigma2),
+                None => std::array::from_fn(|i| xb[i] * xb[i]),
+            };
+            let xval: [f32; _32] = std::array::from_fn(|i| xb[i].abs());
+            let signs = &mut ans.signs[4 * ib..][..4];
+            for (k, s) in signs.iter_mut().enumerate() {
+                for i in 0..8 {
+                    if xb[8 * k + i] < 0. {
+                        *s |= 1 << i
+                    }
+                }
+            }
+
+            let max = xval[1..]
+                .iter()
+                .fold(xval[0], |acc, &x| if acc > x { acc } else { x });
+            if max == 0. {
+                continue;
+            }
+
+            let (mut scale, grid) =
+                quantize_iq3_sub_block(&IQ3S_INDEX, &xval, &weight, max, &mut l, 9, true);
+            if scale < 0. {
+                scale = -scale;
+                for s in signs {
+                    *s = !*s
+                }
+            }
+            for (k, (q, g)) in zip(&mut ans.qs[8 * ib..][..8], grid).enumerate() {
+                *q = g as _;
+                ans.qh[ib] |= ((g >> 8) << k) as u8
+            }
+            scales[ib] = scale;
+            if scale > max_scale {
+                max_scale = scale
+            }
+        }
+
+        if max_scale == 0. {
+            return ans;
+        }
+
+        // 每两组的局部缩放因子用 4 位量化，存入同一字节
+        let d = max_scale / 31.;
+        ans.delta = f16::from_f32(d * 1.033);
+        let id = 1. / d;
+        let f = |x: f32| nearest_int(0.5 * (id * x - 1.)).clamp(0, 15) as u8;
+        for (y, x) in zip(&mut ans.scales, scales.chunks_exact(2)) {
+            *y = f(x[0]) | (f(x[1]) << 4)
+        }
+        ans
+    }
+}
+
 impl Quantize<f32, _256> for IQ3S {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        Self::quantize_impl(data, None)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for (ib, y) in ans.chunks_exact_mut(_32).enumerate() {
+            let sc = (self.scales[ib / 2] >> (4 * (ib % 2))) & 0xf;
+            let db = delta * (1 + 2 * sc as i32) as f32;
+            let qh = self.qh[ib] as usize;
+            for (l, y) in y.chunks_exact_mut(8).enumerate() {
+                let q1 = self.qs[8 * ib + 2 * l] as usize | ((qh << (8 - 2 * l)) & 256);
+                let q2 = self.qs[8 * ib + 2 * l + 1] as usize | ((qh << (7 - 2 * l)) & 256);
+                let grid = IQ3S_GRID[q1] as u64 | (IQ3S_GRID[q2] as u64) << 32;
+                dequantize_grid8(y, db, grid, self.signs[4 * ib + l])
+            }
+        }
+        ans
     }
 }
+
+impl QuantizeWeighted<f32, _256> for IQ3S {
+    #[inline]
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        Self::quantize_impl(data, Some(weights))
+    }
+}
+
+#[test]
+fn test_iq3s() {
+    crate::test_utils::test_ref::<256, IQ3S>(
+        &[
+            67, 25, 222, 61, 47, 209, 146, 11, 121, 80, 38, 74, 92, 98, 202, 210, 119, 168, 185,
+            122, 163, 10, 156, 7, 215, 196, 15, 192, 119, 211, 39, 34, 140, 226, 0, 84, 148, 66,
+            98, 185, 46, 165, 119, 205, 31, 147, 122, 118, 217, 54, 168, 229, 186, 31, 182, 13,
+            211, 214, 208, 138, 53, 52, 222, 226, 144, 96, 168, 122, 249, 212, 162, 242, 148, 195,
+            51, 77, 242, 153, 21, 236, 172, 111, 204, 56, 152, 242, 236, 30, 140, 178, 30, 36, 102,
+            239, 184, 168, 65, 93, 170, 47, 219, 84, 63, 81, 19, 160, 239, 223, 223, 207,
+        ],
+        1.2743225,
+    );
+    crate::test_utils::test::<256, IQ3S>(0.46, 0.);
+    crate::test_utils::test_weighted::<256, IQ3S>(0.47, 0.);
+}
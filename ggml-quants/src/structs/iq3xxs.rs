@@ -1,13 +1,18 @@
-use super::{_256, f16};
-use crate::{DataBlock, Quantize};
+use super::{
+    _32, _256, dequantize_grid8, f16,
+    grids::{IQ3XXS_GRID, IQ3XXS_INDEX, KSIGNS_IQ2XS},
+    imatrix_weight, nearest_int, quantize_iq3_sub_block, sigma2,
+};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
+use std::iter::zip;
 
 /// IQ3XXS 量化结构体
 #[repr(C)]
 pub struct IQ3XXS {
     /// 缩放因子
     pub delta: f16,
-    /// 量化值
-    pub qs: [u16; 3 * _256 / 8],
+    /// 量化值，前 64 字节为码本索引，后 32 字节为每 32 个元素的符号索引和局部缩放因子
+    pub qs: [u8; 3 * _256 / 8],
 }
 
 impl_data_block! {
@@ -18,11 +23,149 @@ impl_data_block! {
     }
 }
 
+impl IQ3XXS {
+    /// 量化一个数据块，`qw` 为可选的重要性权重
+    fn quantize_impl(data: &[f32; _256], qw: Option<&[f32; _256]>) -> Self {
+        let sigma2 = sigma2(data);
+        let mut ans = Self::ZEROS;
+        let mut scales = [0.; _256 / _32];
+        let mut scales_and_signs = [0u32; _256 / _32];
+        let mut l = [0i8; _32];
+        let mut max_scale = 0.;
+        for (ib, xb) in data.chunks_exact(_32).enumerate() {
+            let weight: [f32; _32] = match qw {
+                Some(qw) => imatrix_weight(xb, &qw[_32 * ib..], sigma2),
+                None => std::array::from_fn(|i| xb[i] * xb[i]),
+            };
+            // 每 8 个元素记录 7 位符号，负号个数为奇数时翻转权重最小的元素
+            let mut xval = [0.; _32];
+            let mut block_signs = [0u8; 4];
+            for (k, s) in block_signs.iter_mut().enumerate() {
+                let mut nflip = 0;
+                for i in 8 * k..8 * (k + 1) {
+                    if xb[i] >= 0. {
+                        xval[i] = xb[i]
+                    } else {
+                        xval[i] = -xb[i];
+                        nflip += 1;
+                        *s |= 1 << (i - 8 * k)
+                    }
+                }
+                if nflip % 2 == 1 {
+                    let mut imin = 8 * k;
+                    let mut min = weight[imin] * xb[imin] * xb[imin];
+                    for i in 8 * k + 1..8 * (k + 1) {
+                        let ax = weight[i] * xb[i] * xb[i];
+                        if ax < min {
+                            min = ax;
+                            imin = i
+                        }
+                    }
+                    xval[imin] = -xval[imin];
+                    *s ^= 1 << (imin - 8 * k)
+                }
+                *s &= 127
+            }
+
+            let max = xval[1..]
+                .iter()
+                .fold(xval[0], |acc, &x| if acc > x { acc } else { x });
+            if max < GROUP_MAX_EPS_IQ3_XXS {
+                l = [0; _32];
+                continue;
+            }
+
+            let (mut scale, grid) =
+                quantize_iq3_sub_block(&IQ3XXS_INDEX, &xval, &weight, max, &mut l, 15, false);
+            if scale < 0. {
+                scale = -scale;
+                for s in &mut block_signs {
+                    *s = !*s & 127
+                }
+            }
+            for (q, g) in zip(&mut ans.qs[8 * ib..][..8], grid) {
+                *q = g as _
+            }
+            scales_and_signs[ib] = zip(block_signs, [0, 7, 14, 21])
+                .fold(0, |acc, (s, shift)| acc | (s as u32) << shift);
+            scales[ib] = scale;
+            if scale > max_scale {
+                max_scale = scale
+            }
+        }
+
+        if max_scale == 0. {
+            return Self::ZEROS;
+        }
+
+        // 局部缩放因子用 4 位量化，存入符号索引的最高 4 位
+        let d = max_scale / 31.;
+        ans.delta = f16::from_f32(d * 1.0125);
+        let id = 1. / d;
+        for (ib, (sas, scale)) in zip(scales_and_signs, scales).enumerate() {
+            let l = nearest_int(0.5 * (id * scale - 1.)).clamp(0, 15) as u32;
+            ans.qs[_256 / 4 + 4 * ib..][..4].copy_from_slice(&(sas | (l << 28)).to_le_bytes())
+        }
+        ans
+    }
+}
+
 impl Quantize<f32, _256> for IQ3XXS {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        Self::quantize_impl(data, None)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        let (qs, scales_and_signs) = self.qs.split_at(_256 / 4);
+
+        let mut ans = [0.; _256];
+        for ((y, qs), sas) in ans
+            .chunks_exact_mut(_32)
+            .zip(qs.chunks_exact(8))
+            .zip(scales_and_signs.chunks_exact(4))
+        {
+            let sas = u32::from_le_bytes(sas.try_into().unwrap());
+            let db = delta * (0.5 + (sas >> 28) as f32) * 0.5;
+            for (l, y) in y.chunks_exact_mut(8).enumerate() {
+                let grid = IQ3XXS_GRID[qs[2 * l] as usize] as u64
+                    | (IQ3XXS_GRID[qs[2 * l + 1] as usize] as u64) << 32;
+                let signs = KSIGNS_IQ2XS[(sas >> (7 * l)) as usize & 127];
+                dequantize_grid8(y, db, grid, signs)
+            }
+        }
+        ans
+    }
+}
+
+impl QuantizeWeighted<f32, _256> for IQ3XXS {
+    #[inline]
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        Self::quantize_impl(data, Some(weights))
     }
 }
+
+const GROUP_MAX_EPS_IQ3_XXS: f32 = 1e-8;
+
+#[test]
+fn test_iq3xxs() {
+    crate::test_utils::test_ref::<256, IQ3XXS>(
+        &[
+            140, 25, 144, 42, 42, 251, 39, 171, 103, 218, 80, 193, 86, 178, 245, 228, 184, 91, 231,
+            17, 32, 134, 198, 90, 211, 201, 65, 139, 158, 66, 115, 31, 216, 221, 0, 170, 79, 39,
+            134, 231, 30, 211, 7, 207, 17, 121, 160, 182, 230, 161, 88, 113, 216, 103, 233, 9, 113,
+            254, 206, 169, 66, 20, 100, 78, 172, 103, 179, 166, 62, 179, 5, 22, 235, 205, 76, 24,
+            71, 252, 100, 15, 66, 198, 30, 146, 249, 236, 56, 80, 240, 219, 170, 215, 22, 202, 191,
+            232, 5, 196,
+        ],
+        2.596035,
+    );
+    crate::test_utils::test::<256, IQ3XXS>(0.52, 0.);
+    crate::test_utils::test_weighted::<256, IQ3XXS>(0.52, 0.);
+}
@@ -1,5 +1,9 @@
-use super::{_32, f16};
-use crate::{DataBlock, Quantize};
+use super::{
+    _32, best_index_int8, f16, grids::KVALUES_IQ4NL, imatrix_weight, pack_4bits,
+    quantize_iq4_sub_block, sigma2,
+};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
+use std::array::from_fn;
 
 /// IQ4NL 量化结构体
 #[repr(C)]
@@ -7,7 +11,7 @@ pub struct IQ4NL {
     /// 缩放因子
     pub delta: f16,
     /// 量化值
-    pub qs: [u16; _32 / 2],
+    pub qs: [u8; _32 / 2],
 }
 
 impl_data_block! {
@@ -18,11 +22,60 @@ impl_data_block! {
     }
 }
 
+impl IQ4NL {
+    /// 量化一个数据块，`qw` 为可选的重要性权重
+    fn quantize_impl(data: &[f32; _32], qw: Option<&[f32; _32]>) -> Self {
+        let weight: [f32; _32] = match qw {
+            Some(qw) => imatrix_weight(data, qw, sigma2(data)),
+            None => from_fn(|i| data[i] * data[i]),
+        };
+        let mut l = [0; _32];
+        let d = quantize_iq4_sub_block(&KVALUES_IQ4NL, data, &weight, &mut l, 7);
+
+        // 用最优缩放因子重新查表
+        let id = if d != 0. { 1. / d } else { 0. };
+        for (l, &x) in l.iter_mut().zip(data) {
+            *l = best_index_int8(&KVALUES_IQ4NL, id * x)
+        }
+
+        let mut ans = Self {
+            delta: f16::from_f32(d),
+            qs: [0; _32 / 2],
+        };
+        pack_4bits(&l, &mut ans.qs);
+        ans
+    }
+}
+
 impl Quantize<f32, _32> for IQ4NL {
-    fn quantize(_data: &[f32; _32]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _32]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _32)
+        }
+
+        Self::quantize_impl(data, None)
     }
+
     fn dequantize(&self) -> [f32; _32] {
-        todo!()
+        let d = self.delta.to_f32();
+        from_fn(|i| {
+            let q = self.qs[i % 16] >> (4 * (i / 16));
+            d * KVALUES_IQ4NL[(q & 0xf) as usize] as f32
+        })
+    }
+}
+
+impl QuantizeWeighted<f32, _32> for IQ4NL {
+    #[inline]
+    fn quantize_weighted(data: &[f32; _32], weights: &[f32; _32]) -> Self {
+        Self::quantize_impl(data, Some(weights))
     }
 }
+
+#[test]
+fn test_iq4nl() {
+    crate::test_utils::test::<32, IQ4NL>(0.1, 0.);
+    crate::test_utils::test_weighted::<32, IQ4NL>(0.12, 0.);
+}
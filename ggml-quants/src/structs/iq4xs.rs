@@ -1,5 +1,9 @@
-use super::{_256, f16};
-use crate::{DataBlock, Quantize};
+use super::{
+    _32, _256, best_index_int8, f16, grids::KVALUES_IQ4NL, imatrix_weight, max_by_abs, nearest_int,
+    pack_4bits, quantize_iq4_sub_block, sigma2,
+};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
+use std::array::from_fn;
 
 /// IQ4XS 量化结构体
 #[repr(C)]
@@ -11,7 +15,7 @@ pub struct IQ4XS {
     /// 低位缩放因子
     pub scales_l: [u8; _256 / 64],
     /// 量化值
-    pub qs: [u16; _256 / 2],
+    pub qs: [u8; _256 / 2],
 }
 
 impl_data_block! {
@@ -24,11 +28,83 @@ impl_data_block! {
     }
 }
 
+impl IQ4XS {
+    /// 取出第 `ib` 组的 6 位有符号局部缩放因子
+    #[inline]
+    fn scale(&self, ib: usize) -> i32 {
+        let l = (self.scales_l[ib / 2] >> (4 * (ib % 2))) & 0xf;
+        let h = (self.scales_h >> (2 * ib)) & 3;
+        (l as u16 | (h << 4)) as i32 - 32
+    }
+
+    /// 量化一个数据块，`qw` 为可选的重要性权重
+    fn quantize_impl(data: &[f32; _256], qw: Option<&[f32; _256]>) -> Self {
+        let sigma2 = sigma2(data);
+        let mut l = [0; _256];
+        let scales: [f32; _256 / _32] = from_fn(|ib| {
+            let xb = &data[ib * _32..][.._32];
+            let weight: [f32; _32] = match qw {
+                Some(qw) => imatrix_weight(xb, &qw[_32 * ib..], sigma2),
+                None => from_fn(|i| xb[i] * xb[i]),
+            };
+            quantize_iq4_sub_block(&KVALUES_IQ4NL, xb, &weight, &mut l[ib * _32..][.._32], 7)
+        });
+
+        // 局部缩放因子用 6 位量化，低 4 位存入 scales_l，高 2 位存入 scales_h
+        let mut ans = Self::ZEROS;
+        let d = -max_by_abs(&scales) / 32.;
+        ans.delta = f16::from_f32(d);
+        let id = if d != 0. { 1. / d } else { 0. };
+        for (ib, &scale) in scales.iter().enumerate() {
+            let ls = nearest_int(id * scale).clamp(-32, 31);
+            let dl = d * ls as f32;
+            let idl = if dl != 0. { 1. / dl } else { 0. };
+            for (l, &x) in l[ib * _32..][.._32]
+                .iter_mut()
+                .zip(&data[ib * _32..][.._32])
+            {
+                *l = best_index_int8(&KVALUES_IQ4NL, idl * x)
+            }
+            let ls = (ls + 32) as u8;
+            ans.scales_l[ib / 2] |= (ls & 0xf) << (4 * (ib % 2));
+            ans.scales_h |= ((ls >> 4) as u16) << (2 * ib)
+        }
+        pack_4bits(&l, &mut ans.qs);
+        ans
+    }
+}
+
 impl Quantize<f32, _256> for IQ4XS {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        Self::quantize_impl(data, None)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let d = self.delta.to_f32();
+        from_fn(|i| {
+            let (ib, j) = (i / _32, i % _32);
+            let dl = d * self.scale(ib) as f32;
+            let q = self.qs[ib * 16 + j % 16] >> (4 * (j / 16));
+            dl * KVALUES_IQ4NL[(q & 0xf) as usize] as f32
+        })
+    }
+}
+
+impl QuantizeWeighted<f32, _256> for IQ4XS {
+    #[inline]
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        Self::quantize_impl(data, Some(weights))
     }
 }
+
+#[test]
+fn test_iq4xs() {
+    crate::test_utils::test::<256, IQ4XS>(0.15, 0.);
+    crate::test_utils::test_weighted::<256, IQ4XS>(0.15, 0.);
+}
@@ -0,0 +1,103 @@
+use super::{_32, grids::KVALUES_MXFP4, max_abs};
+use crate::{DataBlock, Quantize};
+use std::array::from_fn;
+
+/// MXFP4 量化结构体
+///
+/// OCP 微缩放格式，32 个 E2M1 浮点数共享一个 E8M0 指数。
+#[repr(C)]
+pub struct MXFP4 {
+    /// E8M0 格式的共享指数
+    pub e: u8,
+    /// 量化值
+    pub qs: [u8; _32 / 2],
+}
+
+impl_data_block! {
+    MXFP4 = crate::types::MXFP4;
+    Self {
+        e: 0,
+        qs: [0; _32 / 2],
+    }
+}
+
+/// E8M0 指数对应的缩放因子的一半，即 `2^(e - 128)`，与量化值表的 2 倍缩放抵消
+#[inline]
+fn e8m0_to_f32_half(e: u8) -> f32 {
+    let bits = if e < 2 {
+        0x0020_0000 << e
+    } else {
+        (e as u32 - 1) << 23
+    };
+    f32::from_bits(bits)
+}
+
+/// 在量化值表中查找与 `x` 最接近的项，与 ggml 的 `best_index_mxfp4` 一致
+fn best_index(x: f32, d: f32) -> u8 {
+    let mut best = 0;
+    let mut best_err = (KVALUES_MXFP4[0] as f32 * d - x).abs();
+    for (i, &v) in KVALUES_MXFP4.iter().enumerate().skip(1) {
+        let err = (v as f32 * d - x).abs();
+        if err < best_err {
+            best = i;
+            best_err = err
+        }
+    }
+    best as _
+}
+
+impl Quantize<f32, _32> for MXFP4 {
+    fn quantize(data: &[f32; _32]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _32)
+        }
+
+        let max = max_abs(data);
+        // E2M1 的最大值为 6 = 1.5 * 2^2
+        let e = if max > 0. {
+            (max.log2().floor() - 2. + 127.) as u8
+        } else {
+            0
+        };
+        let d = e8m0_to_f32_half(e);
+
+        let (l, h) = data.split_at(_32 / 2);
+        Self {
+            e,
+            qs: from_fn(|i| best_index(l[i], d) | (best_index(h[i], d) << 4)),
+        }
+    }
+
+    fn dequantize(&self) -> [f32; _32] {
+        let d = e8m0_to_f32_half(self.e);
+        let f = |x: u8| KVALUES_MXFP4[x as usize] as f32 * d;
+
+        let mut ans = [0.; _32];
+        let (l, h) = ans.split_at_mut(_32 / 2);
+        for (i, &x) in self.qs.iter().enumerate() {
+            l[i] = f(x & 0xf);
+            h[i] = f(x >> 4);
+        }
+        ans
+    }
+}
+
+#[test]
+fn test_mxfp4() {
+    // 共享指数向下取整，超出 E2M1 范围的值会被截断
+    crate::test_utils::test::<32, MXFP4>(0.26, 0.);
+
+    assert_eq!(e8m0_to_f32_half(0), f32::MIN_POSITIVE / 4.);
+    assert_eq!(e8m0_to_f32_half(1), f32::MIN_POSITIVE / 2.);
+    assert_eq!(e8m0_to_f32_half(128), 1.);
+
+    // 可表示的数据可以无损量化
+    let data: [f32; _32] = from_fn(|i| {
+        let x = [0., 0.5, 1., 1.5, 2., 3., 4., 6.][i % 8];
+        if i % 3 == 0 { -x } else { x }
+    });
+    let dequant: [f32; _32] = MXFP4::quantize(&data).dequantize();
+    assert_eq!(dequant, data);
+}
@@ -1,5 +1,9 @@
-use super::{_256, DeltaMin};
-use crate::{DataBlock, Quantize};
+use super::{
+    _256, DeltaMin, imatrix_weight, make_qkx2_quants, make_qp_quants, max_positive, nearest_int,
+    pack_2bits, unpack_2bits,
+};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
+use std::{array::from_fn, iter::zip};
 
 /// Q2K 量化结构体
 #[repr(C)]
@@ -21,11 +25,115 @@ impl_data_block! {
     }
 }
 
+impl Q2K {
+    /// 用量化后的局部缩放因子和最小值重新计算量化值并打包
+    fn pack(mut self, data: &[f32; _256], mut l: [u8; _256]) -> Self {
+        let (delta, min) = self.delta_min.to_f32();
+        for (sc, (x, l)) in zip(
+            &self.scales,
+            zip(data.chunks_exact(16), l.chunks_exact_mut(16)),
+        ) {
+            let d = delta * (sc & 0xf) as f32;
+            if d == 0. {
+                continue;
+            }
+            let dm = min * (sc >> 4) as f32;
+            for (l, &x) in zip(l, x) {
+                *l = nearest_int((x + dm) / d).clamp(0, 3) as _
+            }
+        }
+
+        self.qs = pack_2bits(&l);
+        self
+    }
+}
+
 impl Quantize<f32, _256> for Q2K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let mut l = [0u8; _256];
+        let mut scales = [0.; _256 / 16];
+        let mut mins = [0.; _256 / 16];
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let weights: [f32; 16] = from_fn(|i| x[i].abs());
+            (scales[j], mins[j]) = make_qkx2_quants(3, x, &weights, l, -0.5, 0.1, 15, true);
+        }
+
+        // 局部缩放因子和最小值用 4 位量化，分别存入低 4 位和高 4 位
+        const Q4SCALE: f32 = 15.;
+        let max_scale = max_positive(&scales);
+        let max_min = max_positive(&mins);
+        let mut ans = Self::ZEROS;
+        if max_scale > 0. {
+            let iscale = Q4SCALE / max_scale;
+            for (y, &x) in zip(&mut ans.scales, &scales) {
+                *y = nearest_int(iscale * x) as _
+            }
+        }
+        if max_min > 0. {
+            let iscale = Q4SCALE / max_min;
+            for (y, &x) in zip(&mut ans.scales, &mins) {
+                *y |= (nearest_int(iscale * x) << 4) as u8
+            }
+        }
+        ans.delta_min = DeltaMin::new(max_scale / Q4SCALE, max_min / Q4SCALE);
+        ans.pack(data, l)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let (delta, min) = self.delta_min.to_f32();
+        from_fn(|i| {
+            let sc = self.scales[i / 16];
+            let (dl, ml) = (delta * (sc & 0xf) as f32, min * (sc >> 4) as f32);
+            dl * unpack_2bits(&self.qs, i) as f32 - ml
+        })
     }
 }
+
+impl QuantizeWeighted<f32, _256> for Q2K {
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        let sigma2 = data.iter().fold(0., |acc, x| acc + x * x) / _256 as f32;
+        let mut l = [0u8; _256];
+        let mut scales = [0.; _256 / 16];
+        let mut mins = [0.; _256 / 16];
+        let mut sw = [0.; _256 / 16];
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let weight: [f32; 16] = imatrix_weight(x, &weights[16 * j..], sigma2);
+            sw[j] = weight.iter().fold(0., |acc, w| acc + w);
+            (scales[j], mins[j]) = make_qkx2_quants(3, x, &weight, l, -0.9, 0.05, 36, false);
+        }
+
+        // 局部缩放因子和最小值按子块权重之和加权量化为 4 位
+        let mut ls = [0u8; _256 / 16];
+        let mut lm = [0u8; _256 / 16];
+        let delta = make_qp_quants(15, &scales, &mut ls, &sw);
+        let min = make_qp_quants(15, &mins, &mut lm, &sw);
+        Self {
+            scales: from_fn(|j| ls[j] | (lm[j] << 4)),
+            delta_min: DeltaMin::new(delta, min),
+            ..Self::ZEROS
+        }
+        .pack(data, l)
+    }
+}
+
+#[test]
+fn test_q2k() {
+    crate::test_utils::test_ref::<256, Q2K>(
+        &[
+            255, 204, 188, 220, 222, 238, 235, 255, 255, 236, 238, 204, 220, 205, 203, 90, 229,
+            253, 134, 138, 244, 109, 30, 90, 236, 62, 97, 85, 90, 195, 181, 163, 171, 237, 102, 66,
+            220, 164, 188, 81, 164, 18, 246, 241, 29, 86, 147, 8, 122, 89, 121, 21, 100, 66, 235,
+            210, 46, 202, 137, 134, 123, 213, 106, 166, 22, 24, 120, 142, 31, 172, 84, 95, 161,
+            185, 209, 162, 195, 41, 69, 56, 143, 41, 47, 44,
+        ],
+        -0.26937866,
+    );
+    crate::test_utils::test::<256, Q2K>(0.3, 0.);
+    crate::test_utils::test_weighted::<256, Q2K>(0.32, 0.);
+}
@@ -1,5 +1,9 @@
-use super::{_256, f16};
-use crate::{DataBlock, Quantize};
+use super::{
+    _32, _256, f16, imatrix_weight, make_q3_quants, make_qx_quants, max_by_abs, nearest_int,
+    pack_2bits, sigma2, unpack_2bits,
+};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
+use std::{array::from_fn, iter::zip};
 
 /// Q3K 量化结构体
 #[repr(C)]
@@ -24,11 +28,139 @@ impl_data_block! {
     }
 }
 
+impl Q3K {
+    /// 取出第 `j` 组的 6 位有符号局部缩放因子
+    #[inline]
+    fn scale(&self, j: usize) -> i8 {
+        let l = if j < 8 {
+            self.scales[j] & 0xf
+        } else {
+            self.scales[j - 8] >> 4
+        };
+        let h = (self.scales[8 + j % 4] >> (2 * (j / 4))) & 3;
+        (l | (h << 4)) as i8 - 32
+    }
+
+    /// 将 16 个 6 位局部缩放因子打包，低 4 位存入前 8 字节，高 2 位存入后 4 字节
+    fn pack_scales(ls: [u8; _256 / 16]) -> [u8; 12] {
+        let mut scales = [0; 12];
+        for (j, l) in ls.into_iter().enumerate() {
+            if j < 8 {
+                scales[j] = l & 0xf
+            } else {
+                scales[j - 8] |= (l & 0xf) << 4
+            }
+            scales[j % 4 + 8] |= (l >> 4) << (2 * (j / 4))
+        }
+        scales
+    }
+
+    /// 用量化后的局部缩放因子重新计算量化值并打包
+    fn pack(mut self, data: &[f32; _256], mut l: [i8; _256]) -> Self {
+        let delta = self.delta.to_f32();
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let d = delta * self.scale(j) as f32;
+            if d == 0. {
+                continue;
+            }
+            for (l, &x) in zip(l, x) {
+                *l = nearest_int(x / d).clamp(-4, 3) as i8 + 4
+            }
+        }
+
+        // 第 i 个量化值的最高位存入 hmask 第 i % 32 字节的第 i / 32 位
+        let l = from_fn(|i| {
+            let l = l[i] as u8;
+            if l > 3 {
+                self.hmask[i % _32] |= 1 << (i / _32);
+                l - 4
+            } else {
+                l
+            }
+        });
+        self.qs = pack_2bits(&l);
+        self
+    }
+}
+
 impl Quantize<f32, _256> for Q3K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let mut l = [0i8; _256];
+        let mut scales = [0.; _256 / 16];
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            scales[j] = make_q3_quants(4, x, l)
+        }
+
+        // 局部缩放因子用 6 位量化
+        let mut ans = Self::ZEROS;
+        let max_scale = max_by_abs(&scales);
+        if max_scale != 0. {
+            let iscale = -32. / max_scale;
+            ans.scales = Self::pack_scales(
+                scales.map(|x| ((nearest_int(iscale * x) as i8).clamp(-32, 31) + 32) as u8),
+            );
+            ans.delta = f16::from_f32(iscale.recip())
+        }
+        ans.pack(data, l)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        from_fn(|i| {
+            let dl = delta * self.scale(i / 16) as f32;
+            let h = if self.hmask[i % _32] & (1 << (i / _32)) != 0 {
+                0
+            } else {
+                4
+            };
+            dl * (unpack_2bits(&self.qs, i) as i8 - h) as f32
+        })
+    }
+}
+
+impl QuantizeWeighted<f32, _256> for Q3K {
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        let sigma2 = sigma2(data);
+        let mut l = [0i8; _256];
+        let mut scales = [0.; _256 / 16];
+        let mut sw = [0.; _256 / 16];
+        for (j, (x, l)) in zip(data.chunks_exact(16), l.chunks_exact_mut(16)).enumerate() {
+            let weight: [f32; 16] = imatrix_weight(x, &weights[16 * j..], sigma2);
+            sw[j] = weight.iter().fold(0., |acc, w| acc + w);
+            scales[j] = make_qx_quants(4, x, l, 1, Some(&weight))
+        }
+
+        // 局部缩放因子按子块权重之和加权量化为 6 位
+        let mut ls = [0i8; _256 / 16];
+        let delta = make_qx_quants(32, &scales, &mut ls, 1, Some(&sw));
+        Self {
+            scales: Self::pack_scales(ls.map(|l| l as u8)),
+            delta: f16::from_f32(delta),
+            ..Self::ZEROS
+        }
+        .pack(data, l)
     }
 }
+
+#[test]
+fn test_q3k() {
+    crate::test_utils::test_ref::<256, Q3K>(
+        &[
+            250, 184, 255, 191, 202, 144, 117, 21, 248, 49, 34, 183, 245, 15, 250, 219, 254, 207,
+            12, 81, 219, 13, 111, 89, 45, 0, 108, 45, 119, 132, 232, 131, 102, 147, 52, 49, 193,
+            163, 6, 216, 113, 81, 174, 132, 153, 190, 18, 111, 21, 221, 14, 208, 254, 75, 127, 50,
+            111, 162, 255, 181, 78, 142, 52, 87, 120, 180, 176, 240, 157, 65, 27, 166, 204, 150,
+            199, 17, 123, 123, 86, 13, 165, 77, 200, 249, 99, 169, 196, 199, 174, 159, 14, 127, 58,
+            123, 54, 141, 17, 119, 120, 168, 139, 131, 103, 112, 12, 195, 195, 240, 39, 161,
+        ],
+        2.9082565,
+    );
+    crate::test_utils::test::<256, Q3K>(0.15, 0.);
+    crate::test_utils::test_weighted::<256, Q3K>(0.26, 0.);
+}
@@ -1,5 +1,5 @@
-use super::{_32, f16, max_by_abs};
-use crate::{DataBlock, Quantize};
+use super::{_32, Q8_0, f16, max_by_abs};
+use crate::{DataBlock, Quantize, VecDot};
 use std::array::from_fn;
 
 /// Q4_0 量化结构体
@@ -55,9 +55,29 @@ impl Quantize<f32, _32> for Q4_0 {
         }
         ans
     }
+
+    #[inline]
+    fn dequantize_blocks(src: &[Self], dst: &mut [[f32; _32]]) {
+        crate::simd::dequantize_q4_0(src, dst)
+    }
+}
+
+impl VecDot for Q4_0 {
+    type Rhs = Q8_0;
+
+    fn dot(&self, rhs: &Self::Rhs) -> f32 {
+        let (l, h) = rhs.quants.split_at(_32 / 2);
+        let mut sum = 0;
+        for (i, &x) in self.quants.iter().enumerate() {
+            sum += ((x & 0xf) as i32 - 8) * l[i] as i32;
+            sum += ((x >> 4) as i32 - 8) * h[i] as i32;
+        }
+        sum as f32 * (self.delta.to_f32() * rhs.delta.to_f32())
+    }
 }
 
 #[test]
 fn test_q4_0() {
     crate::test_utils::test::<32, Q4_0>(8e-2, 0.);
+    crate::test_utils::test_vec_dot::<32, Q4_0>(1e-3);
 }
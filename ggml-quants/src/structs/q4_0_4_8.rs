@@ -1,2 +1,49 @@
-﻿/// Q4_0_4_8 量化结构体
-pub struct Q4_0_4_8 {}
+﻿use super::{_32, Q4_0, deinterleave_q4_0, f16, interleave_q4_0};
+use crate::{DataBlock, Interleave};
+
+/// Q4_0_4_8 量化结构体
+///
+/// 由 4 行中同一列的 [`Q4_0`] 交错而成，量化值以 8 字节为单位交错并存储为有符号的 4 位补码。
+#[repr(C)]
+pub struct Q4_0_4_8 {
+    /// 每行的缩放因子
+    pub delta: [f16; 4],
+    /// 交错的量化值
+    pub quants: [u8; _32 / 2 * 4],
+}
+
+impl_data_block! {
+    Q4_0_4_8 = crate::types::Q4_0_4_8;
+    Self {
+        delta: [f16::ZERO; 4],
+        quants: [0; _32 / 2 * 4],
+    }
+}
+
+impl Interleave<Q4_0, 4> for Q4_0_4_8 {
+    fn interleave(blocks: [&Q4_0; 4]) -> Self {
+        Self {
+            delta: blocks.map(|b| b.delta),
+            quants: interleave_q4_0(blocks, 8),
+        }
+    }
+
+    fn deinterleave(&self) -> [Q4_0; 4] {
+        deinterleave_q4_0(&self.delta, &self.quants, 8)
+    }
+}
+
+#[test]
+fn test_q4_0_4_8() {
+    use std::array::from_fn;
+
+    let blocks: [Q4_0; 4] = from_fn(|i| Q4_0 {
+        delta: f16::from_f32(i as f32),
+        quants: from_fn(|j| (i * 16 + j) as u8),
+    });
+    let block = Q4_0_4_8::interleave(from_fn(|i| &blocks[i]));
+    assert_eq!(block.quants[8], blocks[1].quants[0] ^ 0x88);
+    assert_eq!(block.quants[8 * 4], blocks[0].quants[8] ^ 0x88);
+
+    crate::test_utils::test_interleave::<4, Q4_0_4_8>()
+}
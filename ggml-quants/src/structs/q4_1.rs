@@ -1,5 +1,5 @@
-use super::{_32, DeltaMin, min_max};
-use crate::{DataBlock, Quantize};
+use super::{_32, DeltaMin, Q8_1, min_max};
+use crate::{DataBlock, Quantize, VecDot};
 use std::array::from_fn;
 
 /// Q4_1 量化结构体
@@ -60,7 +60,24 @@ impl Quantize<f32, _32> for Q4_1 {
     }
 }
 
+impl VecDot for Q4_1 {
+    type Rhs = Q8_1;
+
+    fn dot(&self, rhs: &Self::Rhs) -> f32 {
+        let (delta, min) = self.delta_min.to_f32();
+        let (l, h) = rhs.quants.split_at(_32 / 2);
+        let mut sum = 0;
+        for (i, &x) in self.quants.iter().enumerate() {
+            sum += (x & 0xf) as i32 * l[i] as i32;
+            sum += (x >> 4) as i32 * h[i] as i32;
+        }
+        // Q8_1 的 sum 已包含缩放因子，最小值部分无需逐个计算
+        sum as f32 * (delta * rhs.delta.to_f32()) + min * rhs.sum.to_f32()
+    }
+}
+
 #[test]
 fn test_q4_1() {
     crate::test_utils::test::<32, Q4_1>(4e-2, 0.);
+    crate::test_utils::test_vec_dot::<32, Q4_1>(1e-2);
 }
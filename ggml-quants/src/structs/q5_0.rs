@@ -1,5 +1,5 @@
-use super::{_32, f16, max_by_abs};
-use crate::{DataBlock, Quantize};
+use super::{_32, Q8_0, f16, max_by_abs};
+use crate::{DataBlock, Quantize, VecDot};
 use std::iter::zip;
 
 /// Q5_0 量化结构体
@@ -73,7 +73,26 @@ impl Quantize<f32, _32> for Q5_0 {
     }
 }
 
+impl VecDot for Q5_0 {
+    type Rhs = Q8_0;
+
+    fn dot(&self, rhs: &Self::Rhs) -> f32 {
+        let qh = u32::from_le_bytes(self.qh);
+        let f = |l, h| (l | (h as u8 & 0x10)) as i32 - 16;
+
+        let (l, h) = rhs.quants.split_at(_32 / 2);
+        let mut sum = 0;
+        #[rustfmt::skip]
+        for (i, x) in self.ql.iter().enumerate() {
+            sum += f(x & 0xf, (qh >>  i               ) << 4) * l[i] as i32;
+            sum += f(x >>  4,  qh >> (i + _32 / 2 - 4)      ) * h[i] as i32;
+        };
+        sum as f32 * (self.delta.to_f32() * rhs.delta.to_f32())
+    }
+}
+
 #[test]
 fn test_q5_0() {
     crate::test_utils::test::<32, Q5_0>(4e-2, 0.);
+    crate::test_utils::test_vec_dot::<32, Q5_0>(1e-3);
 }
@@ -1,5 +1,5 @@
-use super::{_32, DeltaMin, min_max};
-use crate::{DataBlock, Quantize};
+use super::{_32, DeltaMin, Q8_1, min_max};
+use crate::{DataBlock, Quantize, VecDot};
 use std::iter::zip;
 
 /// Q5_1 量化结构体
@@ -77,7 +77,28 @@ impl Quantize<f32, _32> for Q5_1 {
     }
 }
 
+impl VecDot for Q5_1 {
+    type Rhs = Q8_1;
+
+    fn dot(&self, rhs: &Self::Rhs) -> f32 {
+        let (delta, min) = self.delta_min.to_f32();
+        let qh = u32::from_le_bytes(self.qh);
+        let f = |l, h| (l | (h as u8 & 0x10)) as i32;
+
+        let (l, h) = rhs.quants.split_at(_32 / 2);
+        let mut sum = 0;
+        #[rustfmt::skip]
+        for (i, x) in self.ql.iter().enumerate() {
+            sum += f(x & 0xf, (qh >>  i               ) << 4) * l[i] as i32;
+            sum += f(x >>  4,  qh >> (i + _32 / 2 - 4)      ) * h[i] as i32;
+        };
+        // Q8_1 的 sum 已包含缩放因子，最小值部分无需逐个计算
+        sum as f32 * (delta * rhs.delta.to_f32()) + min * rhs.sum.to_f32()
+    }
+}
+
 #[test]
 fn test_q5_1() {
     crate::test_utils::test::<32, Q5_1>(2e-2, 0.);
+    crate::test_utils::test_vec_dot::<32, Q5_1>(1e-2);
 }
@@ -1,6 +1,7 @@
-﻿use super::_256;
-use crate::{DataBlock, Quantize};
+﻿use super::{_32, _256, quantize_k4, scale_min_k4};
+use crate::{DataBlock, Quantize, QuantizeWeighted};
 use half::f16;
+use std::iter::zip;
 
 /// Q5K 量化结构体
 #[repr(C)]
@@ -28,11 +29,90 @@ impl_data_block! {
     }
 }
 
+impl Q5K {
+    /// 量化一个数据块，`qw` 为可选的重要性权重
+    fn quantize_impl(data: &[f32; _256], qw: Option<&[f32; _256]>) -> Self {
+        let (delta_min, scales, l) = quantize_k4(data, qw, 31, -0.5, 15);
+
+        // 每 64 个元素中，前 32 个存入低 4 位，后 32 个存入高 4 位，第 5 位按组存入 qh
+        let mut qh = [0u8; _256 / 8];
+        let mut qs = [0u8; _256 / 2];
+        for (i, (l, qs)) in zip(l.chunks_exact(2 * _32), qs.chunks_exact_mut(_32)).enumerate() {
+            let (l1, l2) = l.split_at(_32);
+            for (j, qs) in qs.iter_mut().enumerate() {
+                qh[j] |= ((l1[j] >> 4) << (2 * i)) | ((l2[j] >> 4) << (2 * i + 1));
+                *qs = (l1[j] & 0xf) | ((l2[j] & 0xf) << 4);
+            }
+        }
+
+        Self {
+            delta: delta_min.delta,
+            min: delta_min.min,
+            scales,
+            qh,
+            qs,
+        }
+    }
+}
+
 impl Quantize<f32, _256> for Q5K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        Self::quantize_impl(data, None)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+        let min = self.min.to_f32();
+
+        let mut ans = [0.; _256];
+        for (i, (y, ql)) in
+            zip(ans.chunks_exact_mut(2 * _32), self.qs.chunks_exact(_32)).enumerate()
+        {
+            let (sc1, m1) = scale_min_k4(2 * i, &self.scales);
+            let (sc2, m2) = scale_min_k4(2 * i + 1, &self.scales);
+            let (d1, m1) = (delta * sc1 as f32, min * m1 as f32);
+            let (d2, m2) = (delta * sc2 as f32, min * m2 as f32);
+            let (l, h) = y.split_at_mut(_32);
+            for (j, (&ql, &qh)) in zip(ql, &self.qh).enumerate() {
+                let qh = qh >> (2 * i);
+                l[j] = d1 * ((ql & 0xf) | ((qh & 1) << 4)) as f32 - m1;
+                h[j] = d2 * ((ql >> 4) | ((qh & 2) << 3)) as f32 - m2;
+            }
+        }
+        ans
+    }
+}
+
+impl QuantizeWeighted<f32, _256> for Q5K {
+    #[inline]
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        Self::quantize_impl(data, Some(weights))
     }
 }
+
+#[test]
+fn test_q5k() {
+    crate::test_utils::test_ref::<256, Q5K>(
+        &[
+            34, 20, 23, 36, 254, 254, 255, 255, 253, 252, 253, 255, 204, 223, 255, 40, 124, 46, 97,
+            11, 76, 22, 243, 147, 62, 183, 164, 144, 115, 137, 124, 93, 31, 110, 229, 177, 58, 236,
+            206, 176, 204, 225, 141, 204, 146, 97, 137, 98, 167, 251, 192, 22, 225, 251, 184, 97,
+            224, 244, 72, 189, 82, 90, 247, 95, 43, 186, 165, 1, 230, 115, 162, 10, 196, 22, 162,
+            76, 152, 200, 77, 53, 89, 141, 224, 1, 191, 153, 14, 188, 85, 62, 151, 127, 141, 132,
+            12, 55, 36, 242, 241, 145, 253, 113, 77, 234, 56, 72, 236, 173, 13, 109, 43, 16, 82,
+            95, 14, 254, 192, 6, 111, 40, 176, 74, 59, 244, 111, 121, 89, 179, 194, 52, 112, 146,
+            251, 146, 145, 175, 55, 74, 25, 46, 104, 122, 151, 83, 155, 125, 90, 13, 84, 162, 211,
+            122, 47, 116, 48, 4, 157, 140, 163, 240, 90, 160, 46, 67, 169, 105, 48, 63, 136, 140,
+            254, 149, 230, 147, 4, 95,
+        ],
+        0.8948078,
+    );
+    crate::test_utils::test::<256, Q5K>(4e-2, 0.);
+    crate::test_utils::test_weighted::<256, Q5K>(5e-2, 0.);
+}
@@ -1,6 +1,7 @@
-﻿use super::_256;
-use crate::{DataBlock, Quantize};
+﻿use super::{_32, _256, GROUP_MAX_EPS, Q8K, make_qx_quants, max_by_abs, nearest_int};
+use crate::{DataBlock, Quantize, QuantizeWeighted, VecDot};
 use half::f16;
+use std::iter::zip;
 
 /// Q6K 量化结构体
 #[repr(C)]
@@ -10,7 +11,7 @@ pub struct Q6K {
     /// 高位量化值
     pub qh: [u8; _256 / 4],
     /// 局部缩放因子
-    pub scales: [u8; _256 / 16],
+    pub scales: [i8; _256 / 16],
     /// 全局缩放因子
     pub delta: f16,
 }
@@ -25,11 +26,169 @@ impl_data_block! {
     }
 }
 
+impl Q6K {
+    /// 量化一个数据块，`qw` 为可选的重要性权重
+    fn quantize_impl(data: &[f32; _256], qw: Option<&[f32; _256]>) -> Self {
+        // 每 16 个元素搜索一个局部缩放因子
+        let mut l = [0i8; _256];
+        let mut scales = [0.; _256 / 16];
+        for (j, (scale, (x, l))) in zip(
+            &mut scales,
+            zip(data.chunks_exact(16), l.chunks_exact_mut(16)),
+        )
+        .enumerate()
+        {
+            *scale = make_qx_quants(32, x, l, 1, qw.map(|qw| &qw[16 * j..][..16]));
+        }
+
+        let max_scale = max_by_abs(&scales);
+        if max_scale.abs() < GROUP_MAX_EPS {
+            return Self::ZEROS;
+        }
+
+        // 局部缩放因子用 8 位量化
+        let iscale = -128. / max_scale;
+        let delta = f16::from_f32(iscale.recip());
+        let scales = scales.map(|x| nearest_int(iscale * x).min(127) as i8);
+
+        // 用量化后的局部缩放因子重新计算量化值
+        for (&sc, (x, l)) in zip(&scales, zip(data.chunks_exact(16), l.chunks_exact_mut(16))) {
+            let d = delta.to_f32() * sc as f32;
+            if d == 0. {
+                continue;
+            }
+            for (l, &x) in zip(l, x) {
+                *l = (nearest_int(x / d).clamp(-32, 31) + 32) as _;
+            }
+        }
+
+        // 每 128 个元素分 4 组，低 4 位两两存入 ql，高 2 位四个一组存入 qh
+        let mut ql = [0u8; _256 / 2];
+        let mut qh = [0u8; _256 / 4];
+        for (l, (ql, qh)) in zip(
+            l.chunks_exact(4 * _32),
+            zip(ql.chunks_exact_mut(2 * _32), qh.chunks_exact_mut(_32)),
+        ) {
+            let l = |i: usize| l[i] as u8;
+            for (i, qh) in qh.iter_mut().enumerate() {
+                let [q1, q2, q3, q4] = [0, 1, 2, 3].map(|k| l(i + k * _32));
+                ql[i] = (q1 & 0xf) | ((q3 & 0xf) << 4);
+                ql[i + _32] = (q2 & 0xf) | ((q4 & 0xf) << 4);
+                *qh = (q1 >> 4) | ((q2 >> 4) << 2) | ((q3 >> 4) << 4) | ((q4 >> 4) << 6);
+            }
+        }
+
+        Self {
+            ql,
+            qh,
+            scales,
+            delta,
+        }
+    }
+}
+
 impl Quantize<f32, _256> for Q6K {
-    fn quantize(_data: &[f32; _256]) -> Self {
-        todo!()
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        Self::quantize_impl(data, None)
     }
+
     fn dequantize(&self) -> [f32; _256] {
-        todo!()
+        let delta = self.delta.to_f32();
+
+        let mut ans = [0.; _256];
+        for (y, (ql, (qh, sc))) in zip(
+            ans.chunks_exact_mut(4 * _32),
+            zip(
+                self.ql.chunks_exact(2 * _32),
+                zip(self.qh.chunks_exact(_32), self.scales.chunks_exact(8)),
+            ),
+        ) {
+            for (i, &qh) in qh.iter().enumerate() {
+                let is = i / 16;
+                let q = [
+                    (ql[i] & 0xf) | ((qh & 3) << 4),
+                    (ql[i + _32] & 0xf) | (((qh >> 2) & 3) << 4),
+                    (ql[i] >> 4) | (((qh >> 4) & 3) << 4),
+                    (ql[i + _32] >> 4) | (((qh >> 6) & 3) << 4),
+                ];
+                for (k, q) in q.into_iter().enumerate() {
+                    y[i + k * _32] = delta * sc[is + 2 * k] as f32 * (q as i8 - 32) as f32;
+                }
+            }
+        }
+        ans
     }
+
+    #[inline]
+    fn dequantize_blocks(src: &[Self], dst: &mut [[f32; _256]]) {
+        crate::simd::dequantize_q6k(src, dst)
+    }
+}
+
+impl QuantizeWeighted<f32, _256> for Q6K {
+    #[inline]
+    fn quantize_weighted(data: &[f32; _256], weights: &[f32; _256]) -> Self {
+        Self::quantize_impl(data, Some(weights))
+    }
+}
+
+impl VecDot for Q6K {
+    type Rhs = Q8K;
+
+    fn dot(&self, rhs: &Self::Rhs) -> f32 {
+        let mut sum = 0;
+        for (y, (ql, (qh, sc))) in zip(
+            rhs.quants.chunks_exact(4 * _32),
+            zip(
+                self.ql.chunks_exact(2 * _32),
+                zip(self.qh.chunks_exact(_32), self.scales.chunks_exact(8)),
+            ),
+        ) {
+            let mut sums = [0; 8];
+            for (i, &qh) in qh.iter().enumerate() {
+                let is = i / 16;
+                let q = [
+                    (ql[i] & 0xf) | ((qh & 3) << 4),
+                    (ql[i + _32] & 0xf) | (((qh >> 2) & 3) << 4),
+                    (ql[i] >> 4) | (((qh >> 4) & 3) << 4),
+                    (ql[i + _32] >> 4) | (((qh >> 6) & 3) << 4),
+                ];
+                for (k, q) in q.into_iter().enumerate() {
+                    sums[is + 2 * k] += (q as i32 - 32) * y[i + k * _32] as i32;
+                }
+            }
+            sum += zip(sums, sc).fold(0, |acc, (s, &sc)| acc + s * sc as i32);
+        }
+        sum as f32 * (self.delta.to_f32() * rhs.delta.to_f32())
+    }
+}
+
+#[test]
+fn test_q6k() {
+    crate::test_utils::test_ref::<256, Q6K>(
+        &[
+            223, 72, 240, 237, 3, 200, 50, 116, 81, 41, 16, 27, 86, 118, 111, 15, 150, 93, 51, 62,
+            181, 77, 223, 93, 43, 17, 186, 217, 193, 206, 97, 23, 171, 1, 199, 13, 82, 32, 24, 114,
+            162, 97, 54, 232, 4, 22, 1, 117, 86, 248, 245, 49, 253, 239, 133, 209, 104, 131, 198,
+            88, 20, 202, 73, 23, 100, 190, 93, 172, 129, 76, 109, 79, 240, 132, 6, 121, 174, 147,
+            114, 5, 213, 8, 33, 180, 246, 213, 18, 30, 14, 132, 66, 92, 64, 149, 143, 6, 173, 251,
+            34, 239, 73, 144, 29, 213, 152, 218, 103, 223, 189, 191, 140, 246, 8, 136, 175, 92,
+            112, 44, 141, 137, 235, 230, 31, 205, 53, 160, 221, 5, 136, 193, 122, 182, 200, 65, 35,
+            102, 144, 2, 93, 41, 102, 255, 136, 159, 168, 238, 101, 65, 223, 163, 191, 82, 183, 17,
+            245, 242, 31, 20, 144, 11, 186, 153, 249, 213, 228, 130, 43, 18, 222, 11, 73, 70, 187,
+            17, 171, 102, 234, 164, 64, 114, 163, 64, 104, 99, 28, 5, 45, 29, 63, 149, 120, 196,
+            132, 110, 123, 133, 124, 134, 128, 131, 132, 134, 134, 119, 132, 122, 108, 124, 28,
+            140,
+        ],
+        0.55330086,
+    );
+    crate::test_utils::test::<256, Q6K>(2.5e-2, 0.);
+    crate::test_utils::test_weighted::<256, Q6K>(4e-2, 0.);
+    crate::test_utils::test_vec_dot::<256, Q6K>(1e-3);
 }
@@ -1,6 +1,7 @@
 use super::{_32, max_abs};
-use crate::{DataBlock, Quantize};
+use crate::{DataBlock, Quantize, VecDot};
 use half::f16;
+use std::iter::zip;
 
 /// Q8_0 量化结构体
 #[repr(C)]
@@ -45,9 +46,24 @@ impl Quantize<f32, _32> for Q8_0 {
         let delta = self.delta.to_f32();
         self.quants.map(|x| x as f32 * delta)
     }
+
+    #[inline]
+    fn dequantize_blocks(src: &[Self], dst: &mut [[f32; _32]]) {
+        crate::simd::dequantize_q8_0(src, dst)
+    }
+}
+
+impl VecDot for Q8_0 {
+    type Rhs = Self;
+
+    fn dot(&self, rhs: &Self::Rhs) -> f32 {
+        let sum = zip(&self.quants, &rhs.quants).fold(0, |acc, (&x, &y)| acc + x as i32 * y as i32);
+        sum as f32 * (self.delta.to_f32() * rhs.delta.to_f32())
+    }
 }
 
 #[test]
 fn test_q8_0() {
     crate::test_utils::test::<32, Q8_0>(4.5e-3, 0.);
+    crate::test_utils::test_vec_dot::<32, Q8_0>(1e-3);
 }
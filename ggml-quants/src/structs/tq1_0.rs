@@ -0,0 +1,91 @@
+use super::{_256, f16, max_abs};
+use crate::{DataBlock, Quantize};
+use std::array::from_fn;
+
+/// TQ1_0 量化结构体
+///
+/// 三值（-1、0、1）量化，每字节以 3 进制存储 5 个量化值，最后 16 个值每字节存储 4 个。
+#[repr(C)]
+pub struct TQ1_0 {
+    /// 每字节 5 个量化值
+    pub qs: [u8; (_256 - 4 * _256 / 64) / 5],
+    /// 每字节 4 个量化值
+    pub qh: [u8; _256 / 64],
+    /// 缩放因子
+    pub delta: f16,
+}
+
+impl_data_block! {
+    TQ1_0 = crate::types::TQ1_0;
+    Self {
+        qs: [0; (_256 - 4 * _256 / 64) / 5],
+        qh: [0; _256 / 64],
+        delta: f16::ZERO,
+    }
+}
+
+const POW3: [u8; 5] = [1, 3, 9, 27, 81];
+
+/// 将 5 个三值量化值（0、1、2）打包为一个字节，第一个值位于最高位
+///
+/// 打包值按 `256 / 243` 向上缩放，使每个值都能由乘法和移位取出。
+#[inline]
+fn pack(trits: [u8; 5]) -> u8 {
+    let q = trits.iter().fold(0u16, |q, &t| q * 3 + t as u16);
+    (q * 256).div_ceil(243) as _
+}
+
+/// 取出打包字节中的第 `n` 个三值量化值
+#[inline]
+fn unpack(q: u8, n: usize) -> u8 {
+    ((q.wrapping_mul(POW3[n]) as u16 * 3) >> 8) as _
+}
+
+impl Quantize<f32, _256> for TQ1_0 {
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let d = max_abs(data);
+        let id = if d != 0. { d.recip() } else { 0. };
+        // 与 ggml 的 `lroundf` 一致，远离零取整
+        let trit = |x: f32| ((x * id).round() as i32 + 1) as u8;
+
+        let (head, tail) = data.split_at(160);
+        let (mid, tail) = tail.split_at(80);
+        Self {
+            qs: from_fn(|j| {
+                if j < 32 {
+                    pack(from_fn(|n| trit(head[j + n * 32])))
+                } else {
+                    pack(from_fn(|n| trit(mid[j - 32 + n * 16])))
+                }
+            }),
+            qh: from_fn(|j| pack(from_fn(|n| if n < 4 { trit(tail[j + n * 4]) } else { 0 }))),
+            delta: f16::from_f32(d),
+        }
+    }
+
+    fn dequantize(&self) -> [f32; _256] {
+        let d = self.delta.to_f32();
+        let f = |q: u8, n: usize| (unpack(q, n) as i32 - 1) as f32 * d;
+        from_fn(|i| match i {
+            0..160 => f(self.qs[i % 32], i / 32),
+            160..240 => f(self.qs[32 + (i - 160) % 16], (i - 160) / 16),
+            _ => f(self.qh[(i - 240) % 4], (i - 240) / 4),
+        })
+    }
+}
+
+#[test]
+fn test_tq1_0() {
+    crate::test_utils::test::<256, TQ1_0>(0.51, 0.);
+
+    // 三值数据可以无损量化
+    let data: [f32; _256] = from_fn(|i| (i % 3) as f32 - 1.);
+    let dequant: [f32; _256] = TQ1_0::quantize(&data).dequantize();
+    assert_eq!(dequant, data);
+}
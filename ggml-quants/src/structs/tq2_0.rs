@@ -0,0 +1,56 @@
+use super::{_256, f16, max_abs, pack_2bits, unpack_2bits};
+use crate::{DataBlock, Quantize};
+use std::array::from_fn;
+
+/// TQ2_0 量化结构体
+///
+/// 三值（-1、0、1）量化，每个量化值占 2 位。
+#[repr(C)]
+pub struct TQ2_0 {
+    /// 量化值
+    pub qs: [u8; _256 / 4],
+    /// 缩放因子
+    pub delta: f16,
+}
+
+impl_data_block! {
+    TQ2_0 = crate::types::TQ2_0;
+    Self {
+        qs: [0; _256 / 4],
+        delta: f16::ZERO,
+    }
+}
+
+impl Quantize<f32, _256> for TQ2_0 {
+    fn quantize(data: &[f32; _256]) -> Self {
+        // 验证块大小是否正确，需要对常量进行断言
+        #[allow(clippy::assertions_on_constants)]
+        const {
+            assert!(Self::COUNT == _256)
+        }
+
+        let d = max_abs(data);
+        let id = if d != 0. { d.recip() } else { 0. };
+        // 与 ggml 的 `lroundf` 一致，远离零取整
+        let l = data.map(|x| ((x * id).round() as i32 + 1) as u8);
+        Self {
+            qs: pack_2bits(&l),
+            delta: f16::from_f32(d),
+        }
+    }
+
+    fn dequantize(&self) -> [f32; _256] {
+        let d = self.delta.to_f32();
+        from_fn(|i| (unpack_2bits(&self.qs, i) as i32 - 1) as f32 * d)
+    }
+}
+
+#[test]
+fn test_tq2_0() {
+    crate::test_utils::test::<256, TQ2_0>(0.51, 0.);
+
+    // 三值数据可以无损量化
+    let data: [f32; _256] = from_fn(|i| (i % 3) as f32 - 1.);
+    let dequant: [f32; _256] = TQ2_0::quantize(&data).dequantize();
+    assert_eq!(dequant, data);
+}
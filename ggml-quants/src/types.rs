@@ -16,10 +16,12 @@ layout!(IQ3S    ; 256);
 layout!(IQ3XXS  ; 256);
 layout!(IQ4NL   ;  32);
 layout!(IQ4XS   ; 256);
+layout!(MXFP4   ;  32);
 layout!(Q2K     ; 256);
 layout!(Q3K     ; 256);
-layout!(Q4_0_4_4; 256);
-layout!(Q4_0_4_8; 256);
+// 交错类型的布局描述整个交错块，按行计算大小时见 `ggus::GGmlType::size`
+layout!(Q4_0_4_4; 128);
+layout!(Q4_0_4_8; 128);
 layout!(Q4_0_8_8; 256);
 layout!(Q4_0    ;  32);
 layout!(Q4_1    ;  32);
@@ -31,6 +33,8 @@ layout!(Q6K     ; 256);
 layout!(Q8_0    ;  32);
 layout!(Q8_1    ;  32);
 layout!(Q8K     ; 256);
+layout!(TQ1_0   ; 256);
+layout!(TQ2_0   ; 256);
 
 #[rustfmt::skip]
 #[test]
@@ -44,6 +48,7 @@ fn test_layout() {
     assert_eq!("iq3xxs", IQ3XXS  .to_string());
     assert_eq!("iq4nl" , IQ4NL   .to_string());
     assert_eq!("iq4xs" , IQ4XS   .to_string());
+    assert_eq!("mxfp4" , MXFP4   .to_string());
     assert_eq!("q2k"   , Q2K     .to_string());
     assert_eq!("q3k"   , Q3K     .to_string());
     assert_eq!("q40"   , Q4_0    .to_string());
@@ -59,4 +64,6 @@ fn test_layout() {
     assert_eq!("q80"   , Q8_0    .to_string());
     assert_eq!("q81"   , Q8_1    .to_string());
     assert_eq!("q8k"   , Q8K     .to_string());
+    assert_eq!("tq10"  , TQ1_0   .to_string());
+    assert_eq!("tq20"  , TQ2_0   .to_string());
 }
@@ -0,0 +1,27 @@
+#![no_main]
+
+use ggus::{GGuf, GGufStreamReader};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // 解析成功时，所有张量信息都有效且数据都在数据段内
+    if let Ok(gguf) = GGuf::new(data) {
+        for tensor in gguf.tensors.values() {
+            let info = tensor.to_info().unwrap();
+            let _ = &gguf.data[info.offset()..][..info.nbytes()];
+        }
+    }
+
+    if let Ok(mut stream) = GGufStreamReader::new(std::io::Cursor::new(data)) {
+        let names = stream.tensors.keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            let _ = stream.read_tensor(&name);
+        }
+    }
+
+    if let Ok(v3) = GGuf::upgrade(data) {
+        let _ = GGuf::new(&v3);
+    }
+
+    let _ = GGuf::swap_to_native(&mut data.to_vec());
+});
@@ -0,0 +1,349 @@
+use crate::{
+    GGmlQuantError, GGmlType, GGuf, GGufError, GGufFileHeader, GGufMetaDataValueType as Ty,
+    GGufReadError, GGufReader, GGufScalar, metadata::MAX_ARRAY_DEPTH,
+};
+use std::{mem::offset_of, str::FromStr};
+
+/// GGUF 文件的字节序。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GGufEndian {
+    /// 小端序。
+    Little,
+    /// 大端序。
+    Big,
+}
+
+impl GGufEndian {
+    /// 本机字节序。
+    pub const NATIVE: Self = if cfg!(target_endian = "little") {
+        Self::Little
+    } else {
+        Self::Big
+    };
+
+    /// 判断是否为本机字节序。
+    #[inline]
+    pub const fn is_native(self) -> bool {
+        matches!(
+            (self, Self::NATIVE),
+            (Self::Little, Self::Little) | (Self::Big, Self::Big)
+        )
+    }
+}
+
+impl FromStr for GGufEndian {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "little" | "le" => Ok(Self::Little),
+            "big" | "be" => Ok(Self::Big),
+            "native" => Ok(Self::NATIVE),
+            _ => Err(format!("unknown endian: {s}")),
+        }
+    }
+}
+
+impl GGufFileHeader {
+    /// 获取 GGUF 文件的字节序。
+    #[inline]
+    pub const fn endian(&self) -> GGufEndian {
+        match (self.is_native_endian(), GGufEndian::NATIVE) {
+            (true, endian) => endian,
+            (false, GGufEndian::Little) => GGufEndian::Big,
+            (false, GGufEndian::Big) => GGufEndian::Little,
+        }
+    }
+}
+
+impl GGuf<'_> {
+    /// 将 `data` 中非本机字节序的 GGUF 文件头、元数据和张量信息原地转换为本机字节序。
+    ///
+    /// 数据已是本机字节序时不做修改。张量数据不会被转换，可按类型调用 [`GGmlType::swap_bytes`]。
+    pub fn swap_to_native(data: &mut [u8]) -> Result<(), GGufError> {
+        let header = GGufReader::new(data)
+            .read_header()
+            .map_err(GGufError::Reading)?;
+        if !header.is_magic_correct() {
+            return Err(GGufError::MagicMismatch);
+        }
+        if header.is_native_endian() {
+            return Ok(());
+        }
+
+        let mut swapper = Swapper::new(data, true);
+        let (n_tensors, n_meta_kvs) = swapper.header().map_err(GGufError::Reading)?;
+        for _ in 0..n_meta_kvs {
+            swapper.meta_kv().map_err(GGufError::Reading)?
+        }
+        for _ in 0..n_tensors {
+            swapper.tensor_info().map_err(GGufError::Reading)?
+        }
+        Ok(())
+    }
+}
+
+/// 原地交换 GGUF 各部分字节序的游标。
+///
+/// `to_native` 为真时数据从非本机字节序转换为本机字节序，否则从本机字节序转换为非本机字节序，
+/// 长度和类型等字段总是按本机字节序解析。
+pub(crate) struct Swapper<'a> {
+    data: &'a mut [u8],
+    pos: usize,
+    to_native: bool,
+}
+
+impl<'a> Swapper<'a> {
+    #[inline]
+    pub fn new(data: &'a mut [u8], to_native: bool) -> Self {
+        Self {
+            data,
+            pos: 0,
+            to_native,
+        }
+    }
+
+    /// 交换一个值的字节序，返回其本机字节序的值。
+    fn swap<T: GGufScalar>(&mut self) -> Result<T, GGufReadError> {
+        let len = size_of::<T>();
+        let bytes = self
+            .data
+            .get_mut(self.pos..)
+            .and_then(|data| data.get_mut(..len))
+            .ok_or(GGufReadError::Eos)?;
+        self.pos += len;
+        if self.to_native {
+            bytes.reverse();
+            GGufReader::new(bytes).read()
+        } else {
+            let ans = GGufReader::new(bytes).read();
+            bytes.reverse();
+            ans
+        }
+    }
+
+    /// 交换 `len` 个宽度为 `width` 的值的字节序。
+    fn swap_n(&mut self, width: usize, len: usize) -> Result<(), GGufReadError> {
+        let bytes = width
+            .checked_mul(len)
+            .and_then(|n| self.data.get_mut(self.pos..)?.get_mut(..n))
+            .ok_or(GGufReadError::Eos)?;
+        self.pos += bytes.len();
+        if width > 1 {
+            for x in bytes.chunks_exact_mut(width) {
+                x.reverse()
+            }
+        }
+        Ok(())
+    }
+
+    /// 交换文件头的字节序，返回张量数量和元数据键值对数量。
+    fn header(&mut self) -> Result<(u64, u64), GGufReadError> {
+        self.swap_n(1, 4)?;
+        self.swap::<u32>()?;
+        Ok((self.swap()?, self.swap()?))
+    }
+
+    fn str(&mut self) -> Result<(), GGufReadError> {
+        let len = self.swap::<u64>()?;
+        self.swap_n(1, len.try_into().map_err(|_| GGufReadError::Eos)?)
+    }
+
+    fn meta_kv(&mut self) -> Result<(), GGufReadError> {
+        self.str()?;
+        let ty = self.swap::<u32>()?.try_into()?;
+        self.meta_value(ty, 1, 0)
+    }
+
+    /// 交换 `len` 个 `ty` 类型元数据值的字节序。
+    pub fn meta_value(&mut self, ty: Ty, len: usize, depth: usize) -> Result<(), GGufReadError> {
+        match ty {
+            Ty::U8 | Ty::I8 | Ty::Bool => self.swap_n(1, len),
+            Ty::U16 | Ty::I16 => self.swap_n(2, len),
+            Ty::U32 | Ty::I32 | Ty::F32 => self.swap_n(4, len),
+            Ty::U64 | Ty::I64 | Ty::F64 => self.swap_n(8, len),
+            Ty::String => (0..len).try_for_each(|_| self.str()),
+            Ty::Array if depth >= MAX_ARRAY_DEPTH => Err(GGufReadError::NestingTooDeep),
+            Ty::Array => (0..len).try_for_each(|_| {
+                let ty = self.swap::<u32>()?.try_into()?;
+                let len = self.swap::<u64>()?;
+                let len = len.try_into().map_err(|_| GGufReadError::Eos)?;
+                self.meta_value(ty, len, depth + 1)
+            }),
+        }
+    }
+
+    fn tensor_info(&mut self) -> Result<(), GGufReadError> {
+        self.str()?;
+        let ndim = self.swap::<u32>()?;
+        self.swap_n(8, ndim as _)?;
+        self.swap::<u32>()?;
+        self.swap::<u64>()?;
+        Ok(())
+    }
+}
+
+impl GGmlType {
+    /// 原地交换当前类型数据的字节序，量化类型只交换数据块中多字节的字段。
+    #[rustfmt::skip]
+    pub fn swap_bytes(self, data: &mut [u8]) -> Result<(), GGmlQuantError> {
+        use ggml_quants::*;
+
+        /// 数据块中多字节字段的偏移、宽度和个数
+        macro_rules! fields {
+            ($blk:ty; $($field:ident: $width:expr, $len:expr);* $(;)?) => {
+                (size_of::<$blk>(), &[$((offset_of!($blk, $field), $width, $len)),*][..])
+            };
+        }
+
+        let (size, fields): (usize, &[(usize, usize, usize)]) = match self {
+            Self::I8 | Self::IQ1M | Self::MXFP4 => return Ok(()),
+            Self::F16 | Self::BF16 | Self::I16 => (2, &[(0, 2, 1)]),
+            Self::F32 | Self::I32              => (4, &[(0, 4, 1)]),
+            Self::F64 | Self::I64              => (8, &[(0, 8, 1)]),
+            Self::Q4_0     => fields!(Q4_0    ; delta: 2, 1),
+            Self::Q4_1     => fields!(Q4_1    ; delta_min: 2, 2),
+            Self::Q5_0     => fields!(Q5_0    ; delta: 2, 1),
+            Self::Q5_1     => fields!(Q5_1    ; delta_min: 2, 2),
+            Self::Q8_0     => fields!(Q8_0    ; delta: 2, 1),
+            Self::Q8_1     => fields!(Q8_1    ; delta: 2, 1; sum: 2, 1),
+            Self::Q2K      => fields!(Q2K     ; delta_min: 2, 2),
+            Self::Q3K      => fields!(Q3K     ; delta: 2, 1),
+            Self::Q4K      => fields!(Q4K     ; delta_min: 2, 2),
+            Self::Q5K      => fields!(Q5K     ; delta: 2, 1; min: 2, 1),
+            Self::Q6K      => fields!(Q6K     ; delta: 2, 1),
+            Self::Q8K      => fields!(Q8K     ; delta: 2, 1; sums: 2, 16),
+            Self::IQ1S     => fields!(IQ1S    ; delta: 2, 1; qh: 2, 8),
+            Self::IQ2XXS   => fields!(IQ2XXS  ; delta: 2, 1; qs: 2, 32),
+            Self::IQ2XS    => fields!(IQ2XS   ; delta: 2, 1; qs: 2, 32),
+            Self::IQ2S     => fields!(IQ2S    ; delta: 2, 1),
+            Self::IQ3XXS   => fields!(IQ3XXS  ; delta: 2, 1),
+            Self::IQ3S     => fields!(IQ3S    ; delta: 2, 1),
+            Self::IQ4NL    => fields!(IQ4NL   ; delta: 2, 1),
+            Self::IQ4XS    => fields!(IQ4XS   ; delta: 2, 1; scales_h: 2, 1),
+            Self::Q4_0_4_4 => fields!(Q4_0_4_4; delta: 2, 4),
+            Self::Q4_0_4_8 => fields!(Q4_0_4_8; delta: 2, 4),
+            Self::Q4_0_8_8 => fields!(Q4_0_8_8; delta: 2, 8),
+            Self::TQ1_0    => fields!(TQ1_0   ; delta: 2, 1),
+            Self::TQ2_0    => fields!(TQ2_0   ; delta: 2, 1),
+            _              => return Err(GGmlQuantError::Unsupported(self)),
+        };
+
+        if !data.len().is_multiple_of(size) {
+            return Err(QuantizeError::Indivisible.into());
+        }
+        for block in data.chunks_exact_mut(size) {
+            for &(offset, width, len) in fields {
+                for x in block[offset..][..width * len].chunks_exact_mut(width) {
+                    x.reverse()
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GGufFileWriter, GGufMetaMapExt};
+    use std::io::Cursor;
+
+    fn write_file(endian: GGufEndian) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let header = GGufFileHeader::new(3, 2, 3);
+        let mut writer =
+            GGufFileWriter::with_endian(Cursor::new(&mut buf), header, endian).unwrap();
+        writer.write_alignment(32).unwrap();
+        writer
+            .write_meta_kv("general.architecture", Ty::String, &{
+                let mut s = 5u64.to_ne_bytes().to_vec();
+                s.extend_from_slice(b"llama");
+                s
+            })
+            .unwrap();
+        let mut arr = Vec::new();
+        arr.extend_from_slice(&(Ty::I32 as u32).to_ne_bytes());
+        arr.extend_from_slice(&2u64.to_ne_bytes());
+        arr.extend_from_slice(&7i32.to_ne_bytes());
+        arr.extend_from_slice(&(-9i32).to_ne_bytes());
+        writer.write_meta_kv("test.array", Ty::Array, &arr).unwrap();
+
+        let mut writer = writer.finish(true);
+        let f32 = [1.5f32, -2.].map(f32::to_ne_bytes).concat();
+        writer
+            .write_tensor("f32", GGmlType::F32, &[2], f32)
+            .unwrap();
+        let q8_0 = [0x12u8, 0x34].into_iter().chain(0..32).collect::<Vec<_>>();
+        writer
+            .write_tensor("q8_0", GGmlType::Q8_0, &[32], q8_0)
+            .unwrap();
+        writer.finish().unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_swap_endian() {
+        let native = write_file(GGufEndian::NATIVE);
+        let foreign = write_file(match GGufEndian::NATIVE {
+            GGufEndian::Little => GGufEndian::Big,
+            GGufEndian::Big => GGufEndian::Little,
+        });
+        assert_eq!(native.len(), foreign.len());
+        assert_ne!(native, foreign);
+
+        let header = GGufReader::new(&foreign).read_header().unwrap();
+        assert!(!header.is_native_endian());
+        assert!(!header.endian().is_native());
+        assert!(matches!(
+            GGuf::new(&foreign),
+            Err(GGufError::EndianNotSupport)
+        ));
+
+        // 转换元数据和张量信息后可以解析
+        let mut data = foreign.clone();
+        GGuf::swap_to_native(&mut data).unwrap();
+        let gguf = GGuf::new(&data).unwrap();
+        assert_eq!(gguf.header.version, 3);
+        assert_eq!(gguf.general_architecture().unwrap(), "llama");
+        let arr = gguf.get_i32_arr("test.array").unwrap();
+        assert_eq!(arr.collect::<Result<Vec<_>, _>>().unwrap(), [7, -9]);
+
+        // 再按类型转换张量数据后与本机字节序的文件一致
+        let offset = data.len() - gguf.data.len();
+        let infos = gguf
+            .tensors
+            .values()
+            .map(|t| t.to_info().unwrap())
+            .collect::<Vec<_>>();
+        for info in infos {
+            let data = &mut data[offset + info.offset()..][..info.nbytes()];
+            info.ty().swap_bytes(data).unwrap()
+        }
+        assert_eq!(data, native);
+
+        // 本机字节序的文件不会被修改
+        let mut data = native.clone();
+        GGuf::swap_to_native(&mut data).unwrap();
+        assert_eq!(data, native);
+    }
+
+    #[test]
+    fn test_swap_bytes() {
+        let mut data = [1, 2, 3, 4, 5, 6, 7, 8];
+        GGmlType::F32.swap_bytes(&mut data).unwrap();
+        assert_eq!(data, [4, 3, 2, 1, 8, 7, 6, 5]);
+        GGmlType::I8.swap_bytes(&mut data).unwrap();
+        assert_eq!(data, [4, 3, 2, 1, 8, 7, 6, 5]);
+        assert!(GGmlType::F64.swap_bytes(&mut data[..4]).is_err());
+
+        let mut data = [0u8; 36];
+        data[..4].copy_from_slice(&[1, 2, 3, 4]);
+        GGmlType::Q8_1.swap_bytes(&mut data).unwrap();
+        assert_eq!(data[..4], [2, 1, 4, 3]);
+
+        assert_eq!("be".parse(), Ok(GGufEndian::Big));
+        assert_eq!("Little".parse(), Ok(GGufEndian::Little));
+        assert!("middle".parse::<GGufEndian>().is_err());
+    }
+}
@@ -27,12 +27,14 @@ pub enum GGufError {
     Reading(GGufReadError),
     /// GGUF 文件的魔术值不匹配，表示文件格式不正确。
     MagicMismatch,
-    /// GGUF 文件的字节序不支持，当前实现仅支持本机字节序。
+    /// GGUF 文件不是本机字节序，需先调用 [`GGuf::swap_to_native`] 转换。
     EndianNotSupport,
-    /// GGUF 文件的版本不支持，当前实现仅支持版本 3。
+    /// GGUF 文件的版本不支持，当前实现支持版本 2 和 3，版本 1 需先调用 [`GGuf::upgrade`] 转换。
     VersionNotSupport,
     /// 元数据键值对中的对齐类型与预期不匹配。
     AlignmentTypeMismatch(GGufMetaDataValueType),
+    /// 元数据键值对中的对齐值不是 2 的幂。
+    InvalidAlignment(u64),
     /// 元数据键重复，GGUF 文件中不允许有重复的元数据键。
     DuplicateMetaKey(String),
     /// 张量名称重复，GGUF 文件中不允许有重复的张量名称。
@@ -47,6 +49,7 @@ impl fmt::Display for GGufError {
             Self::EndianNotSupport => f.write_str("endian not support"),
             Self::VersionNotSupport => f.write_str("version not support"),
             Self::AlignmentTypeMismatch(ty) => write!(f, "alignment type mismatch: {ty:?}"),
+            Self::InvalidAlignment(alignment) => write!(f, "invalid alignment: {alignment}"),
             Self::DuplicateMetaKey(key) => write!(f, "duplicate meta key: {key}"),
             Self::DuplicateTensorName(name) => write!(f, "duplicate tensor name: {name}"),
         }
@@ -61,6 +64,39 @@ impl GGufMetaMap for GGuf<'_> {
     }
 }
 
+impl GGufFileHeader {
+    /// 检查文件头能否被解析。
+    pub(crate) fn check(&self) -> Result<(), GGufError> {
+        use GGufError::*;
+        if !self.is_magic_correct() {
+            return Err(MagicMismatch);
+        }
+        if !self.is_native_endian() {
+            return Err(EndianNotSupport);
+        }
+        // v2 与 v3 的布局相同，v3 只增加了对大端序的支持
+        if !matches!(self.version, 2 | 3) {
+            return Err(VersionNotSupport);
+        }
+        Ok(())
+    }
+}
+
+/// 读取 `general.alignment` 的值，与 llama.cpp 一致要求为 2 的幂。
+pub(crate) fn read_alignment(kv: &GGufMetaKV) -> Result<usize, GGufError> {
+    use GGufError::*;
+    type Ty = GGufMetaDataValueType;
+    let alignment = match kv.ty() {
+        Ty::U32 => kv.value_reader().read::<u32>().map_err(Reading)? as u64,
+        Ty::U64 => kv.value_reader().read::<u64>().map_err(Reading)?,
+        ty => return Err(AlignmentTypeMismatch(ty)),
+    };
+    match usize::try_from(alignment) {
+        Ok(n) if n.is_power_of_two() => Ok(n),
+        _ => Err(InvalidAlignment(alignment)),
+    }
+}
+
 impl<'a> GGuf<'a> {
     /// 创建一个新的 [`GGuf`] 实例，解析给定的 GGUF 数据。
     pub fn new(data: &'a [u8]) -> Result<Self, GGufError> {
@@ -69,28 +105,20 @@ impl<'a> GGuf<'a> {
         let mut reader = GGufReader::new(data);
 
         let header = reader.read_header().map_err(Reading)?;
-        if !header.is_magic_correct() {
-            return Err(MagicMismatch);
-        }
-        if !header.is_native_endian() {
-            return Err(EndianNotSupport);
-        }
-        if header.version != 3 {
-            return Err(VersionNotSupport);
-        }
+        header.check()?;
+
+        // 计数来自文件，按每项的最小字节数限制预分配的容量
+        let remaining = reader.remaining().len();
+        let capacity =
+            |count: u64, min_size: usize| count.min((remaining / min_size) as _) as usize;
 
         let mut alignment = DEFAULT_ALIGNMENT;
-        let mut meta_kvs = IndexMap::with_capacity(header.metadata_kv_count as _);
+        let mut meta_kvs = IndexMap::with_capacity(capacity(header.metadata_kv_count, 12));
         for _ in 0..header.metadata_kv_count {
             let kv = reader.read_meta_kv().map_err(Reading)?;
             let k = kv.key();
             if k == GENERAL_ALIGNMENT {
-                type Ty = GGufMetaDataValueType;
-                alignment = match kv.ty() {
-                    Ty::U32 => kv.value_reader().read::<u32>().map_err(Reading)? as _,
-                    Ty::U64 => kv.value_reader().read::<u64>().map_err(Reading)? as _,
-                    ty => return Err(AlignmentTypeMismatch(ty)),
-                }
+                alignment = read_alignment(&kv)?
             }
             if meta_kvs.insert(k, kv).is_some() {
                 return Err(DuplicateMetaKey(k.into()));
@@ -98,15 +126,13 @@ impl<'a> GGuf<'a> {
         }
 
         let mut data_len = 0;
-        let mut tensors = IndexMap::with_capacity(header.tensor_count as _);
+        let mut tensors = IndexMap::with_capacity(capacity(header.tensor_count, 24));
         for _ in 0..header.tensor_count {
             let tensor = reader.read_tensor_meta().map_err(Reading)?;
             let name = tensor.name();
-            let info = tensor.to_info();
-            let end = info.offset() + info.nbytes();
-            if end > data_len {
-                data_len = end;
-            }
+            // 读取时已检查张量的结束位置不会溢出
+            let info = tensor.to_info().map_err(Reading)?;
+            data_len = data_len.max(info.offset() + info.nbytes());
             if tensors.insert(name, tensor).is_some() {
                 return Err(DuplicateTensorName(name.into()));
             }
@@ -122,6 +148,8 @@ impl<'a> GGuf<'a> {
         let data = reader.remaining();
         let data = if data.len() == data_len {
             data
+        } else if data.len() < data_len {
+            return Err(Reading(GGufReadError::Eos));
         } else {
             let padding = pad(data_len, alignment);
             if data.len() == data_len + padding {
@@ -334,13 +362,13 @@ mod tests {
         assert_eq!(gguf.tensors.len(), 2);
 
         let tensor1 = gguf.tensors.get("tensor1").unwrap();
-        let tensor1_info = tensor1.to_info();
+        let tensor1_info = tensor1.to_info().unwrap();
         assert_eq!(tensor1_info.ty(), GGmlType::F32);
         assert_eq!(tensor1_info.shape(), &[3, 4]);
         assert_eq!(tensor1_info.offset(), 0);
 
         let tensor2 = gguf.tensors.get("tensor2").unwrap();
-        let tensor2_info = tensor2.to_info();
+        let tensor2_info = tensor2.to_info().unwrap();
         assert_eq!(tensor2_info.ty(), GGmlType::F16);
         assert_eq!(tensor2_info.shape(), &[5]);
         assert_eq!(tensor2_info.offset(), 48);
@@ -413,6 +441,7 @@ mod tests {
                 GGufError::AlignmentTypeMismatch(GGufMetaDataValueType::String),
                 "alignment type mismatch: String",
             ),
+            (GGufError::InvalidAlignment(3), "invalid alignment: 3"),
             (GGufError::Reading(GGufReadError::Eos), "reading error: Eos"),
         ];
 
@@ -441,4 +470,91 @@ mod tests {
         let gguf = GGuf::new(&data).expect("Error parsing valid GGUF data");
         assert_eq!(gguf.tensors.len(), 2);
     }
+
+    // 找到 `pattern` 之后 `skip` 字节处的位置
+    fn find(data: &[u8], pattern: &[u8], skip: usize) -> usize {
+        let pos = data.windows(pattern.len()).position(|w| w == pattern);
+        pos.unwrap() + pattern.len() + skip
+    }
+
+    #[test]
+    fn test_malformed_fields() {
+        let valid = create_minimal_gguf_data();
+
+        // 对齐值为 0
+        let mut data = valid.clone();
+        let pos = find(&data, b"general.alignment", 4);
+        data[pos..][..4].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(
+            GGuf::new(&data),
+            Err(GGufError::InvalidAlignment(0))
+        ));
+
+        // 未知的元数据值类型
+        let mut data = valid.clone();
+        let pos = find(&data, b"llm.context_length", 0);
+        data[pos..][..4].copy_from_slice(&13u32.to_le_bytes());
+        assert!(matches!(
+            GGuf::new(&data),
+            Err(GGufError::Reading(GGufReadError::UnknownValueType(13)))
+        ));
+
+        // 未知的张量类型
+        let mut data = valid.clone();
+        let pos = find(&data, b"tensor1", 4 + 16);
+        data[pos..][..4].copy_from_slice(&100u32.to_le_bytes());
+        assert!(matches!(
+            GGuf::new(&data),
+            Err(GGufError::Reading(GGufReadError::UnknownTensorType(100)))
+        ));
+
+        // 形状不是块大小的整数倍
+        data[pos..][..4].copy_from_slice(&(GGmlType::Q4_0 as u32).to_le_bytes());
+        assert!(matches!(
+            GGuf::new(&data),
+            Err(GGufError::Reading(GGufReadError::InvalidTensorShape))
+        ));
+
+        // 张量大小溢出
+        let mut data = valid.clone();
+        let pos = find(&data, b"tensor1", 4);
+        data[pos..][..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            GGuf::new(&data),
+            Err(GGufError::Reading(GGufReadError::InvalidTensorShape))
+        ));
+
+        // 张量数据超出文件
+        let mut data = valid.clone();
+        data.truncate(data.len() - 1);
+        assert!(matches!(
+            GGuf::new(&data),
+            Err(GGufError::Reading(GGufReadError::Eos))
+        ));
+    }
+
+    #[test]
+    fn test_corrupted_data_never_panics() {
+        let valid = create_minimal_gguf_data();
+
+        let check = |data: &[u8]| {
+            if let Ok(gguf) = GGuf::new(data) {
+                for tensor in gguf.tensors.values() {
+                    let info = tensor.to_info().unwrap();
+                    assert!(info.offset() + info.nbytes() <= gguf.data.len());
+                }
+            }
+        };
+
+        for len in 0..valid.len() {
+            check(&valid[..len])
+        }
+        for pos in 0..valid.len() {
+            for byte in [0x00, 0x01, 0x7f, 0x80, 0xff] {
+                let mut data = valid.clone();
+                data[pos] = byte;
+                check(&data)
+            }
+        }
+    }
 }
@@ -23,7 +23,7 @@ impl GGufReader<'_> {
     pub fn read_header(&mut self) -> Result<GGufFileHeader, GGufReadError> {
         let ptr = self.remaining().as_ptr().cast::<GGufFileHeader>();
         self.skip::<GGufFileHeader>(1)?;
-        Ok(unsafe { ptr.read() })
+        Ok(unsafe { ptr.read_unaligned() })
     }
 }
 
@@ -48,11 +48,18 @@ impl GGufFileHeader {
     /// 检查 GGUF 文件头的字节序是否与本机字节序一致。
     #[inline]
     pub const fn is_native_endian(&self) -> bool {
-        // 先判断 native endian 再判断 file endian
-        if u32::from_ne_bytes(MAGIC) == u32::from_le_bytes(MAGIC) {
-            self.version == u32::from_le(self.version)
-        } else {
-            self.version == u32::from_be(self.version)
+        // 版本号很小，按非本机字节序读出时低 16 位为 0，与 llama.cpp 的判断方式一致
+        self.version & 0xffff != 0
+    }
+
+    /// 交换文件头各字段的字节序。
+    #[inline]
+    pub const fn swap_bytes(self) -> Self {
+        Self {
+            magic: self.magic,
+            version: self.version.swap_bytes(),
+            tensor_count: self.tensor_count.swap_bytes(),
+            metadata_kv_count: self.metadata_kv_count.swap_bytes(),
         }
     }
 
@@ -87,4 +94,7 @@ fn test_read_header() {
 
     // 验证字节序检测
     assert!(header.is_native_endian());
+    let swapped = header.swap_bytes();
+    assert!(!swapped.is_native_endian());
+    assert_eq!(swapped.swap_bytes().version, 2);
 }
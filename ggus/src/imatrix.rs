@@ -0,0 +1,240 @@
+use crate::{GGmlType, GGuf, GGufError, GGufReadError, GGufReader};
+use indexmap::IndexMap;
+use std::{error::Error, fmt};
+
+/// llama.cpp 重要性矩阵（imatrix），为每个张量提供按列统计的重要性向量。
+///
+/// 支持 `llama-imatrix` 生成的旧版 `.dat` 二进制格式和新版 GGUF 格式。
+pub struct GGufImatrix {
+    /// 重要性向量，以 GGUF 张量名为键。
+    ///
+    /// 对于混合专家张量，向量依次包含每个专家的重要性，长度为行长度与专家数量之积。
+    pub entries: IndexMap<String, Vec<f32>>,
+}
+
+/// 解析重要性矩阵时可能遇到的错误类型。
+#[derive(Debug)]
+pub enum GGufImatrixError {
+    /// 解析 GGUF 格式的重要性矩阵时发生的错误。
+    GGuf(GGufError),
+    /// 读取旧版格式的重要性矩阵时发生的错误。
+    Reading(GGufReadError),
+    /// 旧版格式中的长度字段为负数。
+    NegativeLength(i32),
+    /// GGUF 格式中 `<name>.in_sum2` 张量缺少对应的 `<name>.counts` 张量。
+    MissingCounts(String),
+    /// GGUF 格式中的张量类型不是 `F32`。
+    TypeMismatch(String, GGmlType),
+    /// GGUF 格式中 `<name>.counts` 张量的形状与 `<name>.in_sum2` 不匹配。
+    ShapeMismatch(String),
+}
+
+impl fmt::Display for GGufImatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GGuf(e) => write!(f, "gguf error: {e}"),
+            Self::Reading(e) => write!(f, "reading error: {e:?}"),
+            Self::NegativeLength(len) => write!(f, "negative length: {len}"),
+            Self::MissingCounts(name) => write!(f, "missing counts for {name}"),
+            Self::TypeMismatch(name, ty) => write!(f, "type mismatch: {name} is {ty:?}"),
+            Self::ShapeMismatch(name) => write!(f, "shape mismatch: {name}"),
+        }
+    }
+}
+
+impl Error for GGufImatrixError {}
+
+const SUFFIX_SUMS: &str = ".in_sum2";
+const SUFFIX_COUNTS: &str = ".counts";
+
+impl GGufImatrix {
+    /// 解析重要性矩阵文件，根据魔术值自动识别 GGUF 格式或旧版格式。
+    pub fn new(data: &[u8]) -> Result<Self, GGufImatrixError> {
+        if data.starts_with(b"GGUF") {
+            Self::from_gguf(data)
+        } else {
+            Self::from_legacy(data)
+        }
+    }
+
+    /// 获取指定张量的重要性向量。
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&[f32]> {
+        self.entries.get(name).map(Vec::as_slice)
+    }
+
+    fn from_legacy(data: &[u8]) -> Result<Self, GGufImatrixError> {
+        use GGufImatrixError::*;
+
+        fn len(reader: &mut GGufReader) -> Result<usize, GGufImatrixError> {
+            let len = reader.read::<i32>().map_err(Reading)?;
+            usize::try_from(len).map_err(|_| NegativeLength(len))
+        }
+
+        let mut reader = GGufReader::new(data);
+        let n_entries = len(&mut reader)?;
+        // 计数来自文件，每项至少包含 3 个 i32 字段，按剩余字节数限制预分配的容量
+        let mut entries = IndexMap::with_capacity(n_entries.min(reader.remaining().len() / 12));
+        for _ in 0..n_entries {
+            let name_len = len(&mut reader)?;
+            let name = reader.remaining();
+            reader.skip::<u8>(name_len).map_err(Reading)?;
+            let name = std::str::from_utf8(&name[..name_len])
+                .map_err(|e| Reading(GGufReadError::Utf8(e)))?;
+
+            let ncall = reader.read::<i32>().map_err(Reading)?;
+            let nval = len(&mut reader)?;
+            let values = read_f32s(&mut reader, nval).map_err(Reading)?;
+            // 旧版格式保存的是累加值，需要除以调用次数
+            let values = if ncall > 0 {
+                values.into_iter().map(|x| x / ncall as f32).collect()
+            } else {
+                values
+            };
+            entries.insert(name.into(), values);
+        }
+        // 文件末尾的调用次数和数据集名称不影响重要性，忽略
+        Ok(Self { entries })
+    }
+
+    fn from_gguf(data: &[u8]) -> Result<Self, GGufImatrixError> {
+        use GGufImatrixError::{MissingCounts, Reading, ShapeMismatch, TypeMismatch};
+
+        let gguf = GGuf::new(data).map_err(GGufImatrixError::GGuf)?;
+        let f32s = |name: &str| -> Result<(Vec<u64>, Vec<f32>), GGufImatrixError> {
+            let info = gguf.tensors[name].to_info().map_err(Reading)?;
+            if info.ty() != GGmlType::F32 {
+                return Err(TypeMismatch(name.into(), info.ty()));
+            }
+            let data = &gguf.data[info.offset()..][..info.nbytes()];
+            let values = read_f32s(&mut GGufReader::new(data), info.nbytes() / size_of::<f32>())
+                .map_err(Reading)?;
+            Ok((info.shape().to_vec(), values))
+        };
+
+        let mut entries = IndexMap::new();
+        for &name in gguf.tensors.keys() {
+            let Some(key) = name.strip_suffix(SUFFIX_SUMS) else {
+                continue;
+            };
+            let counts = format!("{key}{SUFFIX_COUNTS}");
+            if !gguf.tensors.contains_key(&*counts) {
+                return Err(MissingCounts(key.into()));
+            }
+
+            let (shape, sums) = f32s(name)?;
+            let (_, counts) = f32s(&counts)?;
+            // in_sum2 的形状为 [ne0, n_mat]，counts 的形状为 [1, n_mat]
+            let ne0 = shape.first().copied().unwrap_or(1) as usize;
+            if ne0 == 0 || sums.len() != ne0 * counts.len() {
+                return Err(ShapeMismatch(key.into()));
+            }
+            let values = sums
+                .chunks_exact(ne0)
+                .zip(counts)
+                .flat_map(|(sums, count)| {
+                    sums.iter()
+                        .map(move |&sum| if count > 0. { sum / count } else { 1. })
+                })
+                .collect();
+            entries.insert(key.into(), values);
+        }
+        Ok(Self { entries })
+    }
+}
+
+fn read_f32s(reader: &mut GGufReader, len: usize) -> Result<Vec<f32>, GGufReadError> {
+    let data = reader.remaining();
+    reader.skip::<f32>(len)?;
+    Ok(data[..len * size_of::<f32>()]
+        .chunks_exact(size_of::<f32>())
+        .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GGufFileHeader, GGufFileWriter};
+
+    #[test]
+    fn test_legacy() {
+        let mut data = Vec::new();
+        data.extend_from_slice(&2i32.to_ne_bytes());
+        for (name, ncall, values) in [
+            ("blk.0.attn_q.weight", 2i32, [2.0f32, 4., 6., 8.]),
+            ("blk.0.ffn_up.weight", 0, [1., 2., 3., 4.]),
+        ] {
+            data.extend_from_slice(&(name.len() as i32).to_ne_bytes());
+            data.extend_from_slice(name.as_bytes());
+            data.extend_from_slice(&ncall.to_ne_bytes());
+            data.extend_from_slice(&(values.len() as i32).to_ne_bytes());
+            for x in values {
+                data.extend_from_slice(&x.to_ne_bytes())
+            }
+        }
+        // 末尾的调用次数和数据集名称
+        data.extend_from_slice(&10i32.to_ne_bytes());
+        data.extend_from_slice(&4i32.to_ne_bytes());
+        data.extend_from_slice(b"wiki");
+
+        let imatrix = GGufImatrix::new(&data).unwrap();
+        assert_eq!(imatrix.entries.len(), 2);
+        assert_eq!(
+            imatrix.get("blk.0.attn_q.weight"),
+            Some(&[1., 2., 3., 4.][..])
+        );
+        assert_eq!(
+            imatrix.get("blk.0.ffn_up.weight"),
+            Some(&[1., 2., 3., 4.][..])
+        );
+        assert_eq!(imatrix.get("output.weight"), None);
+
+        assert!(matches!(
+            GGufImatrix::new(&data[..data.len() / 2]),
+            Err(GGufImatrixError::Reading(GGufReadError::Eos))
+        ));
+        assert!(matches!(
+            GGufImatrix::new(&(-1i32).to_ne_bytes()),
+            Err(GGufImatrixError::NegativeLength(-1))
+        ));
+        // 数量有误时报告错误而不是按数量分配空间
+        let mut huge = i32::MAX.to_ne_bytes().to_vec();
+        huge.extend_from_slice(&data[4..20]);
+        assert!(matches!(
+            GGufImatrix::new(&huge),
+            Err(GGufImatrixError::Reading(GGufReadError::Eos))
+        ));
+    }
+
+    #[test]
+    fn test_gguf() {
+        fn bytes(values: &[f32]) -> Vec<u8> {
+            values.iter().flat_map(|x| x.to_ne_bytes()).collect()
+        }
+
+        let mut data = Vec::new();
+        let writer = GGufFileWriter::new(&mut data, GGufFileHeader::new(3, 3, 0)).unwrap();
+        let mut writer = writer.finish::<Vec<u8>>(true);
+        #[rustfmt::skip]
+        let tensors = [
+            ("blk.0.ffn_up_exps.weight.in_sum2", vec![2, 2], bytes(&[2., 4., 3., 6.])),
+            ("blk.0.ffn_up_exps.weight.counts" , vec![1, 2], bytes(&[2., 0.])),
+            ("blk.0.attn_q.weight.counts"      , vec![1, 1], bytes(&[1.])),
+        ];
+        for (name, shape, data) in tensors {
+            writer
+                .write_tensor(name, GGmlType::F32, &shape, data)
+                .unwrap()
+        }
+        writer.finish().unwrap();
+
+        let imatrix = GGufImatrix::new(&data).unwrap();
+        assert_eq!(imatrix.entries.len(), 1);
+        assert_eq!(
+            imatrix.get("blk.0.ffn_up_exps.weight"),
+            Some(&[1., 2., 1., 1.][..])
+        );
+        assert_eq!(imatrix.get("blk.0.attn_q.weight"), None);
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{
+    DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT, GGuf, GGufError, GGufFileHeader,
+    GGufMetaDataValueType as Ty, GGufReadError, GGufReader, GGufWriter, metadata::MAX_ARRAY_DEPTH,
+    pad,
+};
+use std::str::from_utf8;
+
+impl<'a> GGufReader<'a> {
+    /// 读取 GGUF v1 格式的字符串，其长度为 u32。
+    pub fn read_str_v1(&mut self) -> Result<&'a str, GGufReadError> {
+        let len = self.read::<u32>()? as _;
+        let (s, tail) = self
+            .remaining()
+            .split_at_checked(len)
+            .ok_or(GGufReadError::Eos)?;
+        let ans = from_utf8(s).map_err(GGufReadError::Utf8)?;
+        *self = GGufReader::new(tail);
+        Ok(ans)
+    }
+}
+
+impl GGuf<'_> {
+    /// 将旧版本的 GGUF 文件转换为 v3 版本。
+    ///
+    /// v2 与 v3 的布局相同，只修改版本号；
+    /// v1 的文件头计数、字符串长度、数组长度和张量形状均为 u32，转换为 u64 后重新排布，张量数据按原样复制。
+    pub fn upgrade(data: &[u8]) -> Result<Vec<u8>, GGufError> {
+        use GGufError::*;
+
+        let mut reader = GGufReader::new(data);
+        let magic = reader.read::<u32>().map_err(Reading)?.to_ne_bytes();
+        let version = reader.read::<u32>().map_err(Reading)?;
+        let header = GGufFileHeader::new(version, 0, 0);
+        if magic != *b"GGUF" {
+            return Err(MagicMismatch);
+        }
+        if !header.is_native_endian() {
+            return Err(EndianNotSupport);
+        }
+        match version {
+            1 => {
+                let mut ans = Vec::with_capacity(data.len());
+                Upgrader {
+                    data,
+                    reader,
+                    writer: GGufWriter::new(&mut ans),
+                }
+                .upgrade()?;
+                Ok(ans)
+            }
+            2 | 3 => {
+                let mut ans = data.to_vec();
+                ans[4..8].copy_from_slice(&3u32.to_ne_bytes());
+                Ok(ans)
+            }
+            _ => Err(VersionNotSupport),
+        }
+    }
+}
+
+/// 从 v1 读取并按 v3 写入的转换器。
+struct Upgrader<'a, 'w> {
+    data: &'a [u8],
+    reader: GGufReader<'a>,
+    writer: GGufWriter<&'w mut Vec<u8>>,
+}
+
+impl Upgrader<'_, '_> {
+    fn upgrade(mut self) -> Result<(), GGufError> {
+        use GGufError::*;
+
+        let n_tensors = self.reader.read::<u32>().map_err(Reading)?;
+        let n_meta_kvs = self.reader.read::<u32>().map_err(Reading)?;
+        self.writer
+            .write_header(GGufFileHeader::new(3, n_tensors as _, n_meta_kvs as _))
+            .unwrap();
+
+        let alignment = self.meta_kvs(n_meta_kvs).map_err(Reading)?;
+        let alignment = match alignment {
+            Some(n) if n.is_power_of_two() => n as _,
+            Some(n) => return Err(InvalidAlignment(n as _)),
+            None => DEFAULT_ALIGNMENT,
+        };
+        self.tensors(n_tensors, alignment).map_err(Reading)
+    }
+
+    /// 转换元数据键值对，返回其中的对齐值。
+    fn meta_kvs(&mut self, n: u32) -> Result<Option<u32>, GGufReadError> {
+        let mut alignment = None;
+        for _ in 0..n {
+            let key = self.reader.read_str_v1()?;
+            let ty = self.reader.read_meta_type()?;
+            if key == GENERAL_ALIGNMENT && ty == Ty::U32 {
+                alignment = Some(self.reader.clone().read::<u32>()?)
+            }
+            self.writer.write_str(key).unwrap();
+            self.writer.write(&[ty]).unwrap();
+            self.meta_value(ty, 1, 0)?
+        }
+        Ok(alignment)
+    }
+
+    /// 转换张量信息并复制张量数据。
+    fn tensors(&mut self, n: u32, alignment: usize) -> Result<(), GGufReadError> {
+        for _ in 0..n {
+            let name = self.reader.read_str_v1()?;
+            let ndim = self.reader.read::<u32>()?;
+            self.reader.clone().skip::<u32>(ndim as _)?;
+            let shape = (0..ndim)
+                .map(|_| self.reader.read::<u32>().map(u64::from))
+                .collect::<Result<Vec<_>, _>>()?;
+            let ty = self.reader.read_ggml_type()?;
+            let offset = self.reader.read::<u64>()?;
+            self.writer
+                .write_tensor_info(name, &shape, ty, offset)
+                .unwrap()
+        }
+
+        // 张量数据相对数据段起始位置的偏移不变，只需重新对齐数据段
+        if n > 0 {
+            let data = self.reader.remaining();
+            let cursor = self.data.len() - data.len();
+            let data = data
+                .get(pad(cursor, alignment)..)
+                .ok_or(GGufReadError::Eos)?;
+            self.writer.write_padding(alignment).unwrap();
+            self.writer.write_data(data).unwrap()
+        }
+
+        Ok(())
+    }
+
+    /// 转换 `len` 个 `ty` 类型的元数据值。
+    fn meta_value(&mut self, ty: Ty, len: usize, depth: usize) -> Result<(), GGufReadError> {
+        match ty {
+            Ty::U8 | Ty::I8 | Ty::Bool => self.copy(len),
+            Ty::U16 | Ty::I16 => self.copy(len * 2),
+            Ty::U32 | Ty::I32 | Ty::F32 => self.copy(len * 4),
+            Ty::U64 | Ty::I64 | Ty::F64 => self.copy(len * 8),
+            Ty::String => (0..len).try_for_each(|_| {
+                let s = self.reader.read_str_v1()?;
+                self.writer.write_str(s).unwrap();
+                Ok(())
+            }),
+            Ty::Array if depth >= MAX_ARRAY_DEPTH => Err(GGufReadError::NestingTooDeep),
+            Ty::Array => (0..len).try_for_each(|_| {
+                let ty = self.reader.read_meta_type()?;
+                let len = self.reader.read::<u32>()?;
+                self.writer.write(&[ty]).unwrap();
+                self.writer.write(&[len as u64]).unwrap();
+                self.meta_value(ty, len as _, depth + 1)
+            }),
+        }
+    }
+
+    /// 原样复制 `n` 个字节。
+    fn copy(&mut self, n: usize) -> Result<(), GGufReadError> {
+        let data = self.reader.remaining();
+        self.reader.skip::<u8>(n)?;
+        self.writer.write_data(&data[..n]).unwrap();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GGmlType;
+    use crate::GGufMetaMapExt;
+
+    fn push_str_v1(data: &mut Vec<u8>, s: &str) {
+        data.extend_from_slice(&(s.len() as u32).to_ne_bytes());
+        data.extend_from_slice(s.as_bytes())
+    }
+
+    // 按 v1 布局构造一个文件
+    fn create_v1_data() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"GGUF");
+        data.extend_from_slice(&1u32.to_ne_bytes());
+        data.extend_from_slice(&1u32.to_ne_bytes());
+        data.extend_from_slice(&3u32.to_ne_bytes());
+
+        push_str_v1(&mut data, "general.architecture");
+        data.extend_from_slice(&(Ty::String as u32).to_ne_bytes());
+        push_str_v1(&mut data, "llama");
+
+        push_str_v1(&mut data, "general.alignment");
+        data.extend_from_slice(&(Ty::U32 as u32).to_ne_bytes());
+        data.extend_from_slice(&64u32.to_ne_bytes());
+
+        push_str_v1(&mut data, "test.array");
+        data.extend_from_slice(&(Ty::Array as u32).to_ne_bytes());
+        data.extend_from_slice(&(Ty::String as u32).to_ne_bytes());
+        data.extend_from_slice(&2u32.to_ne_bytes());
+        push_str_v1(&mut data, "a");
+        push_str_v1(&mut data, "bc");
+
+        push_str_v1(&mut data, "tensor");
+        data.extend_from_slice(&2u32.to_ne_bytes());
+        data.extend_from_slice(&4u32.to_ne_bytes());
+        data.extend_from_slice(&2u32.to_ne_bytes());
+        data.extend_from_slice(&(GGmlType::F32 as u32).to_ne_bytes());
+        data.extend_from_slice(&0u64.to_ne_bytes());
+
+        data.resize(data.len() + pad(data.len(), 64), 0);
+        for i in 0..8 {
+            data.extend_from_slice(&(i as f32).to_ne_bytes())
+        }
+        data
+    }
+
+    #[test]
+    fn test_upgrade_v1() {
+        let v1 = create_v1_data();
+        assert!(matches!(GGuf::new(&v1), Err(GGufError::VersionNotSupport)));
+
+        let v3 = GGuf::upgrade(&v1).unwrap();
+        let gguf = GGuf::new(&v3).unwrap();
+        assert_eq!(gguf.header.version, 3);
+        assert_eq!(gguf.alignment, 64);
+        assert_eq!(gguf.general_architecture().unwrap(), "llama");
+        let arr = gguf.get_str_arr("test.array").unwrap();
+        assert_eq!(arr.collect::<Result<Vec<_>, _>>().unwrap(), ["a", "bc"]);
+
+        let info = gguf.tensors["tensor"].to_info().unwrap();
+        assert_eq!(info.ty(), GGmlType::F32);
+        assert_eq!(info.shape(), [4, 2]);
+        assert_eq!(gguf.data, &v1[v1.len() - 32..]);
+
+        // 截断的文件报告错误
+        assert!(GGuf::upgrade(&v1[..v1.len() - 40]).is_err());
+    }
+
+    #[test]
+    fn test_upgrade_v2() {
+        let mut v2 = Vec::new();
+        {
+            let mut writer = GGufWriter::new(&mut v2);
+            writer.write_header(GGufFileHeader::new(2, 0, 0)).unwrap();
+        }
+        assert_eq!(GGuf::new(&v2).unwrap().header.version, 2);
+        let mut v3 = GGuf::upgrade(&v2).unwrap();
+        assert_eq!(GGuf::new(&v3).unwrap().header.version, 3);
+
+        v3[4] = 4;
+        assert!(matches!(
+            GGuf::upgrade(&v3),
+            Err(GGufError::VersionNotSupport)
+        ));
+        v3[0] = b'X';
+        assert!(matches!(GGuf::upgrade(&v3), Err(GGufError::MagicMismatch)));
+    }
+}
@@ -3,22 +3,31 @@
 
 pub extern crate ggml_quants;
 
+mod endian;
 mod file;
 mod header;
+mod imatrix;
+mod legacy;
 mod metadata;
 mod name;
+mod quant;
 mod read;
+mod stream;
 mod tensor;
 mod write;
 
+pub use endian::GGufEndian;
 pub use file::{GGuf, GGufError};
 pub use header::GGufFileHeader;
+pub use imatrix::{GGufImatrix, GGufImatrixError};
 pub use metadata::{
     DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT, GGmlTokenType, GGufFileType, GGufMetaDataValueType,
     GGufMetaError, GGufMetaKV, GGufMetaMap, GGufMetaMapExt, GGufMetaValueArray,
 };
 pub use name::{GGufExtNotMatch, GGufFileName};
-pub use read::{GGufReadError, GGufReader};
+pub use quant::GGmlQuantError;
+pub use read::{GGufReadError, GGufReader, GGufScalar};
+pub use stream::{GGufStreamError, GGufStreamReader};
 pub use tensor::{GGmlType, GGmlTypeSize, GGufTensorInfo, GGufTensorMeta};
 pub use write::{
     DataFuture, GGufFileSimulator, GGufFileWriter, GGufTensorSimulator, GGufTensorWriter,
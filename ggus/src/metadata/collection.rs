@@ -63,7 +63,7 @@ pub trait GGufMetaMapExt: GGufMetaMap {
             Ty::I16  => convert!(read!(i16)      ),
             Ty::I32  => convert!(read!(i32)      ),
             Ty::I64  => convert!(read!(i64)      ),
-            Ty::Bool => if read!(bool) { 1 } else { 0 },
+            Ty::Bool => GGufReader::new(val).read_bool().map_err(GGufMetaError::Read)?.into(),
             _        => return Err(GGufMetaError::TypeMismatch(ty)),
         };
 
@@ -103,7 +103,7 @@ pub trait GGufMetaMapExt: GGufMetaMap {
     }
 
     /// 获取字符串数组类型的元数据键值对。
-    fn get_str_arr(&self, key: &str) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn get_str_arr(&self, key: &str) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
         let mut reader = GGufReader::new(val);
         let (ty, len) = match ty {
@@ -118,7 +118,7 @@ pub trait GGufMetaMapExt: GGufMetaMap {
     }
 
     /// 获取 i32 数组类型的元数据键值对。
-    fn get_i32_arr(&self, key: &str) -> Result<GGufMetaValueArray<i32>, GGufMetaError> {
+    fn get_i32_arr(&self, key: &str) -> Result<GGufMetaValueArray<'_, i32>, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
         let mut reader = GGufReader::new(val);
         let (ty, len) = match ty {
@@ -133,7 +133,7 @@ pub trait GGufMetaMapExt: GGufMetaMap {
     }
 
     /// 获取 f32 数组类型的元数据键值对。
-    fn get_f32_arr(&self, key: &str) -> Result<GGufMetaValueArray<f32>, GGufMetaError> {
+    fn get_f32_arr(&self, key: &str) -> Result<GGufMetaValueArray<'_, f32>, GGufMetaError> {
         let (ty, val) = self.get(key).ok_or(GGufMetaError::NotExist)?;
         let mut reader = GGufReader::new(val);
         let (ty, len) = match ty {
@@ -268,26 +268,26 @@ pub trait GGufMetaMapExt: GGufMetaMap {
 
     /// 获取标签。
     #[inline]
-    fn general_tags(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn general_tags(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("general.tags")
     }
 
     /// 获取语言。
     #[inline]
-    fn general_languages(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn general_languages(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("general.languages")
     }
 
     /// 获取数据集。
     #[inline]
-    fn general_datasets(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn general_datasets(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("general.datasets")
     }
 
     /// 获取文件类型。
     #[inline]
     fn general_filetype(&self) -> Result<GGufFileType, GGufMetaError> {
-        (self.get_usize("general.filetype")? as u32)
+        (self.get_usize("general.file_type")? as u32)
             .try_into()
             .map_err(|_| GGufMetaError::OutOfRange)
     }
@@ -589,31 +589,31 @@ pub trait GGufMetaMapExt: GGufMetaMap {
 
     /// 获取 ggml 分词器的词汇表。
     #[inline]
-    fn tokenizer_ggml_tokens(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn tokenizer_ggml_tokens(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("tokenizer.ggml.tokens")
     }
 
     /// 获取 ggml 分词器的分数。
     #[inline]
-    fn tokenizer_ggml_scores(&self) -> Result<GGufMetaValueArray<f32>, GGufMetaError> {
+    fn tokenizer_ggml_scores(&self) -> Result<GGufMetaValueArray<'_, f32>, GGufMetaError> {
         self.get_f32_arr("tokenizer.ggml.scores")
     }
 
     /// 获取 ggml 分词器的 token 类型。
     #[inline]
-    fn tokenizer_ggml_token_type(&self) -> Result<GGufMetaValueArray<i32>, GGufMetaError> {
+    fn tokenizer_ggml_token_type(&self) -> Result<GGufMetaValueArray<'_, i32>, GGufMetaError> {
         self.get_i32_arr("tokenizer.ggml.token_type")
     }
 
     /// 获取 ggml 分词器的合并规则。
     #[inline]
-    fn tokenizer_ggml_merges(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn tokenizer_ggml_merges(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("tokenizer.ggml.merges")
     }
 
     /// 获取 ggml 分词器的添加的 token。
     #[inline]
-    fn tokenizer_ggml_added_tokens(&self) -> Result<GGufMetaValueArray<str>, GGufMetaError> {
+    fn tokenizer_ggml_added_tokens(&self) -> Result<GGufMetaValueArray<'_, str>, GGufMetaError> {
         self.get_str_arr("tokenizer.ggml.added_tokens")
     }
 
@@ -1204,7 +1204,7 @@ mod tests {
             "general.repo_url".to_string(),
             (Ty::String, encode_string("https://github.com/example/repo")),
         );
-        map.insert("general.filetype".to_string(), (Ty::U32, encode_u32(0)));
+        map.insert("general.file_type".to_string(), (Ty::U32, encode_u32(0)));
 
         // source 相关字段
         map.insert(
@@ -1368,7 +1368,7 @@ mod tests {
     fn test_filetype_errors() {
         // 测试无效的 filetype 值
         let mut map = HashMap::new();
-        map.insert("general.filetype".to_string(), (Ty::U32, encode_u32(999))); // 无效的值
+        map.insert("general.file_type".to_string(), (Ty::U32, encode_u32(999))); // 无效的值
 
         let meta_map = TestMetaMap { data: map };
 
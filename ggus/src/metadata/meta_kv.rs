@@ -1,5 +1,5 @@
-use super::GGufMetaDataValueType as Ty;
-use crate::{GGufReadError, GGufReader};
+use super::{GGufMetaDataValueType as Ty, MAX_ARRAY_DEPTH};
+use crate::{GGufReadError, GGufReader, GGufScalar};
 use std::marker::PhantomData;
 
 /// [`GGufMetaKV`] 结构体表示 GGUF 文件中的元数据键值对。
@@ -13,15 +13,20 @@ impl<'a> GGufReader<'a> {
         let data = self.remaining();
 
         let _k = self.read_str()?;
-        let ty = self.read()?;
-        self.read_meta_value(ty, 1)?;
+        let ty = self.read_meta_type()?;
+        self.read_meta_value(ty, 1, 0)?;
 
         let data = &data[..data.len() - self.remaining().len()];
         Ok(unsafe { GGufMetaKV::new_unchecked(data) })
     }
 
     /// 读取元数据值。
-    fn read_meta_value(&mut self, ty: Ty, len: usize) -> Result<&mut Self, GGufReadError> {
+    fn read_meta_value(
+        &mut self,
+        ty: Ty,
+        len: usize,
+        depth: usize,
+    ) -> Result<&mut Self, GGufReadError> {
         match ty {
             Ty::U8 => self.skip::<u8>(len),
             Ty::I8 => self.skip::<i8>(len),
@@ -46,8 +51,14 @@ impl<'a> GGufReader<'a> {
                 Ok(self)
             }
             Ty::Array => {
-                let (ty, len) = self.read_arr_header()?;
-                self.read_meta_value(ty, len)
+                if depth >= MAX_ARRAY_DEPTH {
+                    return Err(GGufReadError::NestingTooDeep);
+                }
+                for _ in 0..len {
+                    let (ty, len) = self.read_arr_header()?;
+                    self.read_meta_value(ty, len, depth + 1)?;
+                }
+                Ok(self)
             }
         }
     }
@@ -80,7 +91,7 @@ impl<'a> GGufMetaKV<'a> {
     /// 获取元数据键值对的类型。
     #[inline]
     pub fn ty(&self) -> Ty {
-        self.reader().skip_str().unwrap().read().unwrap()
+        self.reader().skip_str().unwrap().read_meta_type().unwrap()
     }
 
     /// 获取元数据键值对的值字节。
@@ -98,7 +109,7 @@ impl<'a> GGufMetaKV<'a> {
     /// 读取整数类型的值。
     pub fn read_integer(&self) -> isize {
         let mut reader = self.reader();
-        let ty = reader.skip_str().unwrap().read::<Ty>().unwrap();
+        let ty = reader.skip_str().unwrap().read_meta_type().unwrap();
         match ty {
             Ty::Bool | Ty::U8 => reader.read::<u8>().unwrap().into(),
             Ty::I8 => reader.read::<i8>().unwrap().into(),
@@ -115,7 +126,7 @@ impl<'a> GGufMetaKV<'a> {
     /// 读取无符号整数类型的值。
     pub fn read_unsigned(&self) -> usize {
         let mut reader = self.reader();
-        let ty = reader.skip_str().unwrap().read::<Ty>().unwrap();
+        let ty = reader.skip_str().unwrap().read_meta_type().unwrap();
         match ty {
             Ty::Bool | Ty::U8 => reader.read::<u8>().unwrap().into(),
             Ty::U16 => reader.read::<u16>().unwrap().into(),
@@ -177,7 +188,7 @@ impl<'a> Iterator for GGufMetaValueArray<'a, str> {
     }
 }
 
-impl<T: Copy> Iterator for GGufMetaValueArray<'_, T> {
+impl<T: GGufScalar> Iterator for GGufMetaValueArray<'_, T> {
     type Item = Result<T, GGufReadError>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.len != 0 {
@@ -431,6 +442,32 @@ mod tests {
         assert_eq!(result.unwrap(), numbers);
     }
 
+    #[test]
+    fn test_nested_array_values() {
+        // 数组的数组需要读取每个子数组
+        let mut value_bytes = encode_array_header(Ty::Array, 2);
+        value_bytes.extend_from_slice(&encode_array_header(Ty::U32, 1));
+        value_bytes.extend_from_slice(&encode_u32(1));
+        value_bytes.extend_from_slice(&encode_array_header(Ty::U32, 2));
+        value_bytes.extend_from_slice(&encode_u32(2));
+        value_bytes.extend_from_slice(&encode_u32(3));
+        let data = build_kv_data("nested", Ty::Array, &value_bytes);
+        let kv = GGufMetaKV::new(&data).unwrap();
+        assert_eq!(kv.value_bytes(), value_bytes);
+
+        // 嵌套过深的数组报告错误而不是耗尽栈空间
+        let mut value_bytes = Vec::new();
+        for _ in 0..=MAX_ARRAY_DEPTH {
+            value_bytes.extend_from_slice(&encode_array_header(Ty::Array, 1));
+        }
+        value_bytes.extend_from_slice(&encode_array_header(Ty::U8, 0));
+        let data = build_kv_data("deep", Ty::Array, &value_bytes);
+        assert!(matches!(
+            GGufMetaKV::new(&data),
+            Err(GGufReadError::NestingTooDeep)
+        ));
+    }
+
     #[test]
     fn test_meta_value_array_helpers() {
         // 测试 GGufMetaValueArray 辅助方法
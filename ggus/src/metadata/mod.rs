@@ -6,6 +6,8 @@
 mod collection;
 mod meta_kv;
 
+use crate::GGufReadError;
+
 pub use collection::{GGufMetaError, GGufMetaMap, GGufMetaMapExt};
 pub use meta_kv::{GGufMetaKV, GGufMetaValueArray};
 
@@ -14,6 +16,9 @@ pub const DEFAULT_ALIGNMENT: usize = 32;
 /// 表示对齐方式的键。
 pub const GENERAL_ALIGNMENT: &str = "general.alignment";
 
+/// 元数据数组的最大嵌套层数，防止恶意数据耗尽栈空间。
+pub(crate) const MAX_ARRAY_DEPTH: usize = 16;
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[repr(u32)]
 pub enum GGufMetaDataValueType {
@@ -71,6 +76,24 @@ impl GGufMetaDataValueType {
     }
 }
 
+impl TryFrom<u32> for GGufMetaDataValueType {
+    type Error = GGufReadError;
+
+    /// 从文件中存储的类型编号转换。
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        use GGufMetaDataValueType as Ty;
+        #[rustfmt::skip]
+        const TYPES: [Ty; 13] = [
+            Ty::U8  , Ty::I8 , Ty::U16   , Ty::I16  , Ty::U32, Ty::I32, Ty::F32,
+            Ty::Bool, Ty::String, Ty::Array, Ty::U64, Ty::I64, Ty::F64,
+        ];
+        TYPES
+            .get(value as usize)
+            .copied()
+            .ok_or(GGufReadError::UnknownValueType(value))
+    }
+}
+
 /// GGUF 文件类型枚举。
 ///
 /// 表示 GGUF 文件中使用的主要数据类型。
@@ -115,6 +138,9 @@ pub enum GGufFileType {
     MostlyQ4_0_4_4 = 33,
     MostlyQ4_0_4_8 = 34,
     MostlyQ4_0_8_8 = 35,
+    MostlyTQ1_0 = 36,
+    MostlyTQ2_0 = 37,
+    MostlyMXFP4MoE = 38,
     // GUESSED = 1024  # not specified in the model file
 }
 
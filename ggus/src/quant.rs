@@ -0,0 +1,261 @@
+use crate::GGmlType;
+use ggml_quants::{DataBlock, QuantErrorStats, QuantExt, QuantizeError};
+use std::{error::Error, fmt, ptr::copy_nonoverlapping};
+
+/// 按运行时类型量化或反量化时可能遇到的错误。
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GGmlQuantError {
+    /// 数据类型不支持此操作。
+    Unsupported(GGmlType),
+    /// 数据长度不正确。
+    Quantize(QuantizeError),
+}
+
+impl fmt::Display for GGmlQuantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsupported(ty) => write!(f, "unsupported type: {ty:?}"),
+            Self::Quantize(e) => write!(f, "quantize error: {e:?}"),
+        }
+    }
+}
+
+impl Error for GGmlQuantError {}
+
+impl From<QuantizeError> for GGmlQuantError {
+    #[inline]
+    fn from(value: QuantizeError) -> Self {
+        Self::Quantize(value)
+    }
+}
+
+impl GGmlType {
+    /// 将 `src` 中当前类型的数据反量化为 `f32`，写入 `dst`。
+    ///
+    /// 交错类型 `Q4_0_4_4`、`Q4_0_4_8` 和 `Q4_0_8_8` 的布局依赖行长，
+    /// 需要先用 [`ggml_quants::InterleaveExt`] 拆分为 `Q4_0`。
+    #[rustfmt::skip]
+    pub fn dequantize_to_f32(self, src: &[u8], dst: &mut [f32]) -> Result<(), GGmlQuantError> {
+        use ggml_quants::*;
+        match self {
+            Self::F32    => copy_f32(src, dst),
+            Self::F16    => dequantize::<f16   ,   1>(src, dst),
+            Self::BF16   => dequantize::<bf16  ,   1>(src, dst),
+            Self::Q4_0   => dequantize::<Q4_0  ,  32>(src, dst),
+            Self::Q4_1   => dequantize::<Q4_1  ,  32>(src, dst),
+            Self::Q5_0   => dequantize::<Q5_0  ,  32>(src, dst),
+            Self::Q5_1   => dequantize::<Q5_1  ,  32>(src, dst),
+            Self::Q8_0   => dequantize::<Q8_0  ,  32>(src, dst),
+            Self::Q8_1   => dequantize::<Q8_1  ,  32>(src, dst),
+            Self::Q2K    => dequantize::<Q2K   , 256>(src, dst),
+            Self::Q3K    => dequantize::<Q3K   , 256>(src, dst),
+            Self::Q4K    => dequantize::<Q4K   , 256>(src, dst),
+            Self::Q5K    => dequantize::<Q5K   , 256>(src, dst),
+            Self::Q6K    => dequantize::<Q6K   , 256>(src, dst),
+            Self::Q8K    => dequantize::<Q8K   , 256>(src, dst),
+            Self::IQ1S   => dequantize::<IQ1S  , 256>(src, dst),
+            Self::IQ1M   => dequantize::<IQ1M  , 256>(src, dst),
+            Self::IQ2XXS => dequantize::<IQ2XXS, 256>(src, dst),
+            Self::IQ2XS  => dequantize::<IQ2XS , 256>(src, dst),
+            Self::IQ2S   => dequantize::<IQ2S  , 256>(src, dst),
+            Self::IQ3XXS => dequantize::<IQ3XXS, 256>(src, dst),
+            Self::IQ3S   => dequantize::<IQ3S  , 256>(src, dst),
+            Self::IQ4NL  => dequantize::<IQ4NL ,  32>(src, dst),
+            Self::IQ4XS  => dequantize::<IQ4XS , 256>(src, dst),
+            Self::TQ1_0  => dequantize::<TQ1_0 , 256>(src, dst),
+            Self::TQ2_0  => dequantize::<TQ2_0 , 256>(src, dst),
+            Self::MXFP4  => dequantize::<MXFP4 ,  32>(src, dst),
+            _            => return Err(GGmlQuantError::Unsupported(self)),
+        }
+        .map_err(Into::into)
+    }
+
+    /// 将 `src` 中的 `f32` 数据量化为当前类型，写入 `dst`。
+    ///
+    /// 尚未实现量化算法的 `IQ1S`、`IQ1M`、`IQ2XXS`、`IQ2XS` 和 `IQ2S` 以及交错类型返回 [`GGmlQuantError::Unsupported`]。
+    #[rustfmt::skip]
+    pub fn quantize_from_f32(self, src: &[f32], dst: &mut [u8]) -> Result<(), GGmlQuantError> {
+        use ggml_quants::*;
+        match self {
+            Self::F32    => copy_f32_back(src, dst),
+            Self::F16    => quantize::<f16   ,   1>(src, dst),
+            Self::BF16   => quantize::<bf16  ,   1>(src, dst),
+            Self::Q4_0   => quantize::<Q4_0  ,  32>(src, dst),
+            Self::Q4_1   => quantize::<Q4_1  ,  32>(src, dst),
+            Self::Q5_0   => quantize::<Q5_0  ,  32>(src, dst),
+            Self::Q5_1   => quantize::<Q5_1  ,  32>(src, dst),
+            Self::Q8_0   => quantize::<Q8_0  ,  32>(src, dst),
+            Self::Q8_1   => quantize::<Q8_1  ,  32>(src, dst),
+            Self::Q2K    => quantize::<Q2K   , 256>(src, dst),
+            Self::Q3K    => quantize::<Q3K   , 256>(src, dst),
+            Self::Q4K    => quantize::<Q4K   , 256>(src, dst),
+            Self::Q5K    => quantize::<Q5K   , 256>(src, dst),
+            Self::Q6K    => quantize::<Q6K   , 256>(src, dst),
+            Self::Q8K    => quantize::<Q8K   , 256>(src, dst),
+            Self::IQ3XXS => quantize::<IQ3XXS, 256>(src, dst),
+            Self::IQ3S   => quantize::<IQ3S  , 256>(src, dst),
+            Self::IQ4NL  => quantize::<IQ4NL ,  32>(src, dst),
+            Self::IQ4XS  => quantize::<IQ4XS , 256>(src, dst),
+            Self::TQ1_0  => quantize::<TQ1_0 , 256>(src, dst),
+            Self::TQ2_0  => quantize::<TQ2_0 , 256>(src, dst),
+            Self::MXFP4  => quantize::<MXFP4 ,  32>(src, dst),
+            _            => return Err(GGmlQuantError::Unsupported(self)),
+        }
+        .map_err(Into::into)
+    }
+
+    /// 将 `src` 量化为当前类型再反量化，统计量化误差。
+    ///
+    /// 当前类型须同时支持 [`GGmlType::quantize_from_f32`] 和 [`GGmlType::dequantize_to_f32`]。
+    pub fn quant_error(self, src: &[f32]) -> Result<QuantErrorStats, GGmlQuantError> {
+        let size = self.size();
+        if !src.len().is_multiple_of(size.block_size as _) {
+            return Err(QuantizeError::Indivisible.into());
+        }
+        let mut bytes = vec![0u8; size.elements_to_bytes(&[src.len() as _])];
+        self.quantize_from_f32(src, &mut bytes)?;
+        let mut restored = vec![0.; src.len()];
+        self.dequantize_to_f32(&bytes, &mut restored)?;
+        Ok(QuantErrorStats::new(src, &restored)?)
+    }
+}
+
+fn dequantize<B: QuantExt<f32, N>, const N: usize>(
+    src: &[u8],
+    dst: &mut [f32],
+) -> Result<(), QuantizeError> {
+    if !src.len().is_multiple_of(size_of::<B>()) {
+        return Err(QuantizeError::Indivisible);
+    }
+    match unsafe { src.align_to::<B>() } {
+        ([], blocks, []) => B::dequantize_slice(dst, blocks),
+        // 数据未对齐时先复制到对齐的缓冲区
+        _ => {
+            let blocks = src
+                .chunks_exact(size_of::<B>())
+                .map(|block| unsafe { block.as_ptr().cast::<B>().read_unaligned() })
+                .collect::<Vec<_>>();
+            B::dequantize_slice(dst, &blocks)
+        }
+    }
+}
+
+fn quantize<B: DataBlock + QuantExt<f32, N>, const N: usize>(
+    src: &[f32],
+    dst: &mut [u8],
+) -> Result<(), QuantizeError> {
+    if !src.len().is_multiple_of(N) {
+        return Err(QuantizeError::Indivisible);
+    }
+    let len = src.len() / N;
+    if dst.len() != len * size_of::<B>() {
+        return Err(QuantizeError::LengthMismatch);
+    }
+    match unsafe { dst.align_to_mut::<B>() } {
+        ([], blocks, []) => B::quantize_slice(blocks, src),
+        // 数据未对齐时先量化到对齐的缓冲区
+        _ => {
+            let mut blocks = (0..len).map(|_| B::ZEROS).collect::<Vec<_>>();
+            B::quantize_slice(&mut blocks, src)?;
+            unsafe { copy_nonoverlapping(blocks.as_ptr().cast(), dst.as_mut_ptr(), dst.len()) };
+            Ok(())
+        }
+    }
+}
+
+fn copy_f32(src: &[u8], dst: &mut [f32]) -> Result<(), QuantizeError> {
+    if !src.len().is_multiple_of(size_of::<f32>()) {
+        return Err(QuantizeError::Indivisible);
+    }
+    if src.len() != size_of_val(dst) {
+        return Err(QuantizeError::LengthMismatch);
+    }
+    unsafe { copy_nonoverlapping(src.as_ptr(), dst.as_mut_ptr().cast(), src.len()) };
+    Ok(())
+}
+
+fn copy_f32_back(src: &[f32], dst: &mut [u8]) -> Result<(), QuantizeError> {
+    if dst.len() != size_of_val(src) {
+        return Err(QuantizeError::LengthMismatch);
+    }
+    unsafe { copy_nonoverlapping(src.as_ptr().cast(), dst.as_mut_ptr(), dst.len()) };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let src = (0..512).map(|i| (i as f32 / 64.).sin()).collect::<Vec<_>>();
+        for ty in [
+            GGmlType::F32,
+            GGmlType::F16,
+            GGmlType::BF16,
+            GGmlType::Q8_0,
+            GGmlType::Q6K,
+            GGmlType::IQ4XS,
+        ] {
+            let mut bytes = vec![0u8; ty.size().elements_to_bytes(&[src.len() as _])];
+            ty.quantize_from_f32(&src, &mut bytes).unwrap();
+            let mut dst = vec![0f32; src.len()];
+            ty.dequantize_to_f32(&bytes, &mut dst).unwrap();
+            for (a, b) in src.iter().zip(&dst) {
+                assert!((a - b).abs() < 0.1, "{ty:?}: {a} vs {b}")
+            }
+
+            // 未对齐的数据与对齐的数据结果一致
+            let mut unaligned = vec![0u8; bytes.len() + 1];
+            ty.quantize_from_f32(&src, &mut unaligned[1..]).unwrap();
+            assert_eq!(unaligned[1..], bytes);
+            let mut dst_ = vec![0f32; src.len()];
+            ty.dequantize_to_f32(&unaligned[1..], &mut dst_).unwrap();
+            assert_eq!(dst_, dst)
+        }
+    }
+
+    #[test]
+    fn test_quant_error() {
+        let src = (0..512).map(|i| (i as f32 / 64.).sin()).collect::<Vec<_>>();
+        let f32 = GGmlType::F32.quant_error(&src).unwrap();
+        assert_eq!(f32.rmse, 0.);
+        let q8_0 = GGmlType::Q8_0.quant_error(&src).unwrap();
+        let q4_0 = GGmlType::Q4_0.quant_error(&src).unwrap();
+        assert!(0. < q8_0.rmse && q8_0.rmse < q4_0.rmse);
+        assert_eq!(
+            GGmlType::Q4K.quant_error(&src[..32]),
+            Err(GGmlQuantError::Quantize(QuantizeError::Indivisible))
+        );
+        assert_eq!(
+            GGmlType::IQ2XXS.quant_error(&src),
+            Err(GGmlQuantError::Unsupported(GGmlType::IQ2XXS))
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let src = [0f32; 32];
+        let mut dst = [0u8; 64];
+        assert_eq!(
+            GGmlType::IQ2XXS.quantize_from_f32(&src, &mut dst),
+            Err(GGmlQuantError::Unsupported(GGmlType::IQ2XXS))
+        );
+        assert_eq!(
+            GGmlType::Q4_0_4_4.dequantize_to_f32(&dst, &mut [0.; 32]),
+            Err(GGmlQuantError::Unsupported(GGmlType::Q4_0_4_4))
+        );
+        assert_eq!(
+            GGmlType::Q8_0.quantize_from_f32(&src, &mut dst),
+            Err(GGmlQuantError::Quantize(QuantizeError::LengthMismatch))
+        );
+        assert_eq!(
+            GGmlType::Q8_0.quantize_from_f32(&src[..31], &mut dst),
+            Err(GGmlQuantError::Quantize(QuantizeError::Indivisible))
+        );
+        assert_eq!(
+            GGmlType::Q8_0.dequantize_to_f32(&dst[..34], &mut [0.; 64]),
+            Err(GGmlQuantError::Quantize(QuantizeError::LengthMismatch))
+        );
+    }
+}
@@ -1,8 +1,5 @@
 use crate::metadata::GGufMetaDataValueType;
-use std::{
-    alloc::Layout,
-    str::{Utf8Error, from_utf8, from_utf8_unchecked},
-};
+use std::str::{Utf8Error, from_utf8, from_utf8_unchecked};
 
 /// [`GGufReader`] 定义读取 GGUF 文件的读取器。
 #[derive(Clone)]
@@ -18,8 +15,37 @@ pub enum GGufReadError {
     Utf8(Utf8Error),
     /// 读取布尔值时遇到的错误，表示读取到的字节不是 0 或 1。
     Bool(u8),
+    /// 张量类型未知或已不受支持。
+    UnknownTensorType(u32),
+    /// 元数据值类型未知。
+    UnknownValueType(u32),
+    /// 张量形状与类型的块大小不匹配，或张量大小溢出。
+    InvalidTensorShape,
+    /// 元数据数组嵌套过深。
+    NestingTooDeep,
 }
 
+/// [`GGufScalar`] 标记可以从任意字节读取的标量类型，即整数和浮点数。
+///
+/// 此 trait 是密封的，枚举等存在无效位模式的类型须通过 [`GGufReader::read_meta_type`]
+/// 和 [`GGufReader::read_ggml_type`] 等方法读取并检查。
+pub trait GGufScalar: sealed::Sealed + Copy {}
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! scalar {
+    ($($ty:ty)*) => {
+        $(
+            impl sealed::Sealed for $ty {}
+            impl GGufScalar for $ty {}
+        )*
+    };
+}
+
+scalar!(u8 i8 u16 i16 u32 i32 u64 i64 f32 f64);
+
 impl<'a> GGufReader<'a> {
     /// 创建一个新的 [`GGufReader`] 实例。
     #[inline]
@@ -35,7 +61,8 @@ impl<'a> GGufReader<'a> {
 
     /// 跳过指定长度的字节。
     pub(crate) fn skip<T>(&mut self, len: usize) -> Result<&mut Self, GGufReadError> {
-        let len = Layout::array::<T>(len).unwrap().size();
+        // 长度溢出时必然超出剩余数据
+        let len = size_of::<T>().checked_mul(len).ok_or(GGufReadError::Eos)?;
         let (_, tail) = self.0.split_at_checked(len).ok_or(GGufReadError::Eos)?;
         self.0 = tail;
         Ok(self)
@@ -48,7 +75,7 @@ impl<'a> GGufReader<'a> {
     }
 
     /// 读取指定类型的值。
-    pub fn read<T: Copy>(&mut self) -> Result<T, GGufReadError> {
+    pub fn read<T: GGufScalar>(&mut self) -> Result<T, GGufReadError> {
         let ptr = self.0.as_ptr().cast::<T>();
         self.skip::<T>(1)?;
         Ok(unsafe { ptr.read_unaligned() })
@@ -84,9 +111,14 @@ impl<'a> GGufReader<'a> {
         unsafe { from_utf8_unchecked(s) }
     }
 
+    /// 读取元数据值类型。
+    pub fn read_meta_type(&mut self) -> Result<GGufMetaDataValueType, GGufReadError> {
+        self.read::<u32>()?.try_into()
+    }
+
     /// 读取一个数组头部，返回元数据类型和数组长度。
     pub fn read_arr_header(&mut self) -> Result<(GGufMetaDataValueType, usize), GGufReadError> {
-        Ok((self.read()?, self.read::<u64>()? as _))
+        Ok((self.read_meta_type()?, self.read::<u64>()? as _))
     }
 }
 
@@ -103,6 +135,12 @@ mod tests {
         assert_eq!(reader.read::<u8>().unwrap(), 3);
         assert_eq!(reader.read::<u8>().unwrap(), 4);
         assert_eq!(reader.read::<u8>().unwrap(), 5);
+        assert_eq!(reader.read::<u8>(), Err(GGufReadError::Eos));
+
+        let data = [1.5f32.to_ne_bytes(), (-2i32).to_ne_bytes()].concat();
+        let mut reader = GGufReader::new(&data[..]);
+        assert_eq!(reader.read::<f32>().unwrap(), 1.5);
+        assert_eq!(reader.read::<i32>().unwrap(), -2);
     }
 
     #[test]
@@ -113,4 +151,34 @@ mod tests {
         assert!(reader.read_bool().unwrap());
         assert!(matches!(reader.read_bool(), Err(GGufReadError::Bool(2))));
     }
+
+    #[test]
+    fn test_read_meta_type() {
+        let data = [9u32, 12, 13, u32::MAX]
+            .iter()
+            .flat_map(|x| x.to_ne_bytes())
+            .collect::<Vec<_>>();
+        let mut reader = GGufReader::new(&data);
+        assert_eq!(reader.read_meta_type(), Ok(GGufMetaDataValueType::Array));
+        assert_eq!(reader.read_meta_type(), Ok(GGufMetaDataValueType::F64));
+        assert_eq!(
+            reader.read_meta_type(),
+            Err(GGufReadError::UnknownValueType(13))
+        );
+        assert_eq!(
+            reader.read_meta_type(),
+            Err(GGufReadError::UnknownValueType(u32::MAX))
+        );
+    }
+
+    #[test]
+    fn test_skip_overflow() {
+        let data: &[u8] = &[0; 8];
+        let mut reader = GGufReader::new(data);
+        assert!(matches!(
+            reader.skip::<u64>(usize::MAX),
+            Err(GGufReadError::Eos)
+        ));
+        assert_eq!(reader.remaining().len(), 8);
+    }
 }
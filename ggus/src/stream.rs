@@ -0,0 +1,371 @@
+use crate::{
+    DEFAULT_ALIGNMENT, GENERAL_ALIGNMENT, GGufError, GGufFileHeader, GGufMetaDataValueType as Ty,
+    GGufMetaKV, GGufMetaMap, GGufReadError, GGufReader, GGufScalar, GGufTensorInfo, GGufTensorMeta,
+    file::read_alignment, metadata::MAX_ARRAY_DEPTH, pad,
+};
+use indexmap::IndexMap;
+use std::{
+    error::Error,
+    fmt,
+    io::{self, ErrorKind, Read, Seek, SeekFrom, Take},
+};
+
+/// 从任意 [`Read`] 流式解析 GGUF 文件的读取器。
+///
+/// 文件头、元数据和张量信息在创建时读出并持有所有权，张量数据在需要时通过 [`Seek`] 定位读取。
+pub struct GGufStreamReader<R> {
+    reader: R,
+    /// GGUF 文件头。
+    pub header: GGufFileHeader,
+    /// 对齐方式。
+    pub alignment: usize,
+    /// 元数据键值对，值为元数据类型和值的原始字节。
+    pub meta_kvs: IndexMap<String, (Ty, Vec<u8>)>,
+    /// 张量信息。
+    pub tensors: IndexMap<String, GGufTensorInfo>,
+    /// 数据段相对文件起始位置的偏移。
+    data_offset: u64,
+    /// 解析时读取的字节数。
+    parsed: u64,
+    /// 文件起始位置在流中的位置，首次定位时确定。
+    base: Option<u64>,
+}
+
+/// 流式解析 GGUF 文件时可能遇到的错误类型。
+#[derive(Debug)]
+pub enum GGufStreamError {
+    /// 读取流时发生的错误。
+    Io(io::Error),
+    /// 解析 GGUF 文件时发生的错误。
+    GGuf(GGufError),
+}
+
+impl fmt::Display for GGufStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::GGuf(e) => write!(f, "gguf error: {e}"),
+        }
+    }
+}
+
+impl Error for GGufStreamError {}
+
+impl From<io::Error> for GGufStreamError {
+    #[inline]
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<GGufError> for GGufStreamError {
+    #[inline]
+    fn from(value: GGufError) -> Self {
+        Self::GGuf(value)
+    }
+}
+
+impl<R> GGufMetaMap for GGufStreamReader<R> {
+    fn get(&self, key: &str) -> Option<(Ty, &[u8])> {
+        self.meta_kvs.get(key).map(|(ty, value)| (*ty, &**value))
+    }
+}
+
+impl<R: Read> GGufStreamReader<R> {
+    /// 从 `reader` 的当前位置读取并解析文件头、元数据和张量信息，读取停止在张量信息之后。
+    pub fn new(mut reader: R) -> Result<Self, GGufStreamError> {
+        use GGufError::*;
+
+        let mut buf = Buffered::new(&mut reader);
+
+        let header = buf.header()?;
+        header.check()?;
+        buf.finish();
+
+        let mut alignment = DEFAULT_ALIGNMENT;
+        let mut meta_kvs = IndexMap::new();
+        for _ in 0..header.metadata_kv_count {
+            buf.str()?;
+            let ty = buf.meta_type()?;
+            buf.meta_value(ty, 1, 0)?;
+            let data = buf.finish();
+
+            let kv = GGufMetaKV::new(&data).map_err(Reading)?;
+            let k = kv.key();
+            if k == GENERAL_ALIGNMENT {
+                alignment = read_alignment(&kv)?
+            }
+            let value = (kv.ty(), kv.value_bytes().to_vec());
+            if meta_kvs.insert(k.to_string(), value).is_some() {
+                return Err(DuplicateMetaKey(k.into()).into());
+            }
+        }
+
+        let mut tensors = IndexMap::new();
+        for _ in 0..header.tensor_count {
+            buf.str()?;
+            let ndim = buf.read::<u32>()?;
+            buf.take(ndim as u64 * size_of::<u64>() as u64)?;
+            buf.read::<u32>()?;
+            buf.read::<u64>()?;
+            let data = buf.finish();
+
+            let tensor = GGufTensorMeta::new(&data).map_err(Reading)?;
+            let name = tensor.name();
+            let info = tensor.to_info().map_err(Reading)?;
+            if tensors.insert(name.to_string(), info).is_some() {
+                return Err(DuplicateTensorName(name.into()).into());
+            }
+        }
+
+        let parsed = buf.pos;
+        let data_offset = if tensors.is_empty() {
+            parsed
+        } else {
+            parsed + pad(parsed as _, alignment) as u64
+        };
+
+        Ok(Self {
+            reader,
+            header,
+            alignment,
+            meta_kvs,
+            tensors,
+            data_offset,
+            parsed,
+            base: None,
+        })
+    }
+
+    /// 获取数据段相对文件起始位置的偏移。
+    #[inline]
+    pub const fn data_offset(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// 取回内部的读取器。
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> GGufStreamReader<R> {
+    /// 定位到指定张量数据的起始位置，返回只能读取该张量数据的读取器。
+    pub fn tensor_reader(&mut self, name: &str) -> io::Result<Take<&mut R>> {
+        let info = self.tensors.get(name).ok_or_else(|| {
+            io::Error::new(ErrorKind::NotFound, format!("tensor {name} not found"))
+        })?;
+        let (offset, len) = (info.offset() as u64, info.nbytes() as u64);
+
+        let base = match self.base {
+            Some(base) => base,
+            None => *self
+                .base
+                .insert(self.reader.stream_position()? - self.parsed),
+        };
+        self.reader
+            .seek(SeekFrom::Start(base + self.data_offset + offset))?;
+        Ok(self.reader.by_ref().take(len))
+    }
+
+    /// 读取指定张量的数据，张量信息有误时不会预先分配过大的空间。
+    pub fn read_tensor(&mut self, name: &str) -> io::Result<Vec<u8>> {
+        let mut reader = self.tensor_reader(name)?;
+        let len = reader.limit();
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        if (data.len() as u64) < len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(data)
+    }
+}
+
+/// 从流中读取一个结构并缓存其全部字节。
+struct Buffered<'r, R> {
+    reader: &'r mut R,
+    buf: Vec<u8>,
+    pos: u64,
+}
+
+impl<'r, R: Read> Buffered<'r, R> {
+    fn new(reader: &'r mut R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            pos: 0,
+        }
+    }
+
+    /// 读取 `len` 个字节，长度字段有误时不会预先分配过大的空间。
+    fn take(&mut self, len: u64) -> io::Result<&[u8]> {
+        let start = self.buf.len();
+        let n = self.reader.by_ref().take(len).read_to_end(&mut self.buf)?;
+        self.pos += n as u64;
+        if (n as u64) < len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        Ok(&self.buf[start..])
+    }
+
+    fn header(&mut self) -> Result<GGufFileHeader, GGufStreamError> {
+        let bytes = self.take(size_of::<GGufFileHeader>() as _)?;
+        GGufReader::new(bytes)
+            .read_header()
+            .map_err(|e| GGufError::Reading(e).into())
+    }
+
+    fn read<T: GGufScalar>(&mut self) -> Result<T, GGufStreamError> {
+        let bytes = self.take(size_of::<T>() as _)?;
+        GGufReader::new(bytes)
+            .read()
+            .map_err(|e| GGufError::Reading(e).into())
+    }
+
+    fn meta_type(&mut self) -> Result<Ty, GGufStreamError> {
+        let ty = self.read::<u32>()?;
+        ty.try_into().map_err(|e| GGufError::Reading(e).into())
+    }
+
+    fn str(&mut self) -> Result<(), GGufStreamError> {
+        let len = self.read::<u64>()?;
+        self.take(len)?;
+        Ok(())
+    }
+
+    fn meta_value(&mut self, ty: Ty, len: u64, depth: usize) -> Result<(), GGufStreamError> {
+        let width = match ty {
+            Ty::U8 | Ty::I8 | Ty::Bool => 1,
+            Ty::U16 | Ty::I16 => 2,
+            Ty::U32 | Ty::I32 | Ty::F32 => 4,
+            Ty::U64 | Ty::I64 | Ty::F64 => 8,
+            Ty::String => return (0..len).try_for_each(|_| self.str()),
+            Ty::Array if depth >= MAX_ARRAY_DEPTH => {
+                return Err(GGufError::Reading(GGufReadError::NestingTooDeep).into());
+            }
+            Ty::Array => {
+                return (0..len).try_for_each(|_| {
+                    let ty = self.meta_type()?;
+                    let len = self.read()?;
+                    self.meta_value(ty, len, depth + 1)
+                });
+            }
+        };
+        let len = len
+            .checked_mul(width)
+            .ok_or_else(|| io::Error::from(ErrorKind::InvalidData))?;
+        self.take(len)?;
+        Ok(())
+    }
+
+    /// 取出已缓存的字节。
+    fn finish(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GGmlType, GGuf, GGufFileWriter, GGufMetaMapExt};
+    use std::io::Cursor;
+
+    fn create_file() -> Vec<u8> {
+        let mut buf = Vec::new();
+        let header = GGufFileHeader::new(3, 2, 2);
+        let mut writer = GGufFileWriter::new(&mut buf, header).unwrap();
+        writer.write_alignment(64).unwrap();
+        writer
+            .write_meta_kv("general.architecture", Ty::String, &{
+                let mut s = 5u64.to_ne_bytes().to_vec();
+                s.extend_from_slice(b"llama");
+                s
+            })
+            .unwrap();
+
+        let mut writer = writer.finish(true);
+        let a = (0..6)
+            .flat_map(|i| (i as f32).to_ne_bytes())
+            .collect::<Vec<_>>();
+        writer.write_tensor("a", GGmlType::F32, &[3, 2], a).unwrap();
+        writer
+            .write_tensor("b", GGmlType::I8, &[5], vec![1u8; 5])
+            .unwrap();
+        writer.finish().unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_stream_reader() {
+        let file = create_file();
+        let gguf = GGuf::new(&file).unwrap();
+
+        // 文件前有其他数据时也能定位张量
+        let mut data = vec![0xff; 100];
+        data.extend_from_slice(&file);
+        let mut cursor = Cursor::new(data);
+        cursor.set_position(100);
+
+        let mut stream = GGufStreamReader::new(cursor).unwrap();
+        assert_eq!(stream.header.tensor_count, 2);
+        assert_eq!(stream.alignment, 64);
+        assert_eq!(stream.general_architecture().unwrap(), "llama");
+        assert_eq!(stream.meta_kvs.len(), gguf.meta_kvs.len());
+        assert_eq!(
+            stream.data_offset() as usize,
+            gguf.data.as_ptr() as usize - file.as_ptr() as usize,
+        );
+
+        for (name, tensor) in &gguf.tensors {
+            let info = tensor.to_info().unwrap();
+            assert_eq!(stream.tensors[*name].shape(), info.shape());
+            let data = stream.read_tensor(name).unwrap();
+            assert_eq!(data, &gguf.data[info.offset()..][..info.nbytes()]);
+        }
+        // 乱序读取
+        assert_eq!(stream.read_tensor("b").unwrap(), [1; 5]);
+        assert_eq!(
+            stream.read_tensor("c").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn test_stream_reader_errors() {
+        let file = create_file();
+
+        // 不可定位的流也能解析元数据
+        let stream = GGufStreamReader::new(&file[..]).unwrap();
+        assert_eq!(stream.tensors.len(), 2);
+
+        assert!(matches!(
+            GGufStreamReader::new(&file[..30]),
+            Err(GGufStreamError::Io(e)) if e.kind() == ErrorKind::UnexpectedEof
+        ));
+
+        let mut wrong = file.clone();
+        wrong[0] = b'X';
+        assert!(matches!(
+            GGufStreamReader::new(&wrong[..]),
+            Err(GGufStreamError::GGuf(GGufError::MagicMismatch))
+        ));
+
+        // 张量形状有误时数据不足，报告错误而不是分配张量声明的大小
+        let mut huge = file.clone();
+        let info = [
+            &1u64.to_ne_bytes()[..],
+            b"a",
+            &2u32.to_ne_bytes(),
+            &3u64.to_ne_bytes(),
+        ]
+        .concat();
+        let pos = huge.windows(info.len()).position(|w| w == info).unwrap() + info.len() - 8;
+        huge[pos..][..8].copy_from_slice(&(1u64 << 40).to_ne_bytes());
+        let mut stream = GGufStreamReader::new(Cursor::new(huge)).unwrap();
+        assert_eq!(
+            stream.read_tensor("a").unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
+    }
+}
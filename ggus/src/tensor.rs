@@ -3,7 +3,7 @@
 
 use crate::{GGufReadError, GGufReader};
 use std::{
-    alloc::{Layout, alloc, dealloc},
+    alloc::{Layout, alloc, dealloc, handle_alloc_error},
     ptr::{NonNull, copy_nonoverlapping},
     slice::from_raw_parts,
 };
@@ -48,6 +48,9 @@ pub enum GGmlType {
     Q4_0_4_4 = 31,
     Q4_0_4_8 = 32,
     Q4_0_8_8 = 33,
+    TQ1_0 = 34,
+    TQ2_0 = 35,
+    MXFP4 = 39,
 }
 
 /// GGML 数据类型的大小和块大小。
@@ -79,20 +82,65 @@ impl GGmlTypeSize {
     }
 
     /// 计算给定形状的元素总数转换为字节数。
+    ///
+    /// # Panics
+    ///
+    /// 形状的第一维不是块大小的整数倍或字节数溢出时 panic，不可信的形状应使用 [`GGmlTypeSize::checked_elements_to_bytes`]。
     #[inline]
     pub fn elements_to_bytes(&self, shape: &[u64]) -> usize {
+        self.checked_elements_to_bytes(shape).unwrap_or_else(|| {
+            panic!(
+                "shape {shape:?} mismatch with block size {}",
+                self.block_size
+            )
+        })
+    }
+
+    /// 计算给定形状的元素总数转换为字节数，形状的第一维不是块大小的整数倍或字节数溢出时返回 `None`。
+    pub fn checked_elements_to_bytes(&self, shape: &[u64]) -> Option<usize> {
         let blk = self.block_size as u64;
         let ele = self.type_size as u64;
-        match shape {
-            [] => {
-                assert_eq!(blk, 1);
-                ele as _
-            }
-            [last, others @ ..] => {
-                assert_eq!(last % blk, 0);
-                (others.iter().product::<u64>() * last / blk * ele) as _
-            }
+        let (last, others) = shape.split_first().unwrap_or((&1, &[]));
+        if last % blk != 0 {
+            return None;
         }
+        others
+            .iter()
+            .try_fold(last / blk, |acc, &d| acc.checked_mul(d))?
+            .checked_mul(ele)?
+            .try_into()
+            .ok()
+    }
+}
+
+impl TryFrom<u32> for GGmlType {
+    type Error = GGufReadError;
+
+    /// 从文件中存储的类型编号转换，已移除支持的类型视为未知类型。
+    #[allow(deprecated)]
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        use GGmlType as Ty;
+        #[rustfmt::skip]
+        const TYPES: [GGmlType; 37] = [
+            Ty::F32   , Ty::F16    , Ty::Q4_0    , Ty::Q4_1    , Ty::Q4_2    , Ty::Q4_3    ,
+            Ty::Q5_0  , Ty::Q5_1   , Ty::Q8_0    , Ty::Q8_1    , Ty::Q2K     , Ty::Q3K     ,
+            Ty::Q4K   , Ty::Q5K    , Ty::Q6K     , Ty::Q8K     , Ty::IQ2XXS  , Ty::IQ2XS   ,
+            Ty::IQ3XXS, Ty::IQ1S   , Ty::IQ4NL   , Ty::IQ3S    , Ty::IQ2S    , Ty::IQ4XS   ,
+            Ty::I8    , Ty::I16    , Ty::I32     , Ty::I64     , Ty::F64     , Ty::IQ1M    ,
+            Ty::BF16  , Ty::Q4_0_4_4, Ty::Q4_0_4_8, Ty::Q4_0_8_8, Ty::TQ1_0  , Ty::TQ2_0   ,
+            Ty::MXFP4 ,
+        ];
+        match TYPES.iter().find(|&&ty| ty as u32 == value) {
+            Some(Ty::Q4_2 | Ty::Q4_3) | None => Err(GGufReadError::UnknownTensorType(value)),
+            Some(&ty) => Ok(ty),
+        }
+    }
+}
+
+impl GGufReader<'_> {
+    /// 读取张量类型。
+    pub fn read_ggml_type(&mut self) -> Result<GGmlType, GGufReadError> {
+        self.read::<u32>()?.try_into()
     }
 }
 
@@ -136,9 +184,13 @@ impl GGmlType {
             Self::F64      => size!(t: f64   ),
             Self::IQ1M     => size!(q: IQ1M  ),
             Self::BF16     => size!(q: bf16   ),
+            // 交错类型跨行重排 Q4_0 数据块，按行计算时与 Q4_0 大小一致
             Self::Q4_0_4_4 |
             Self::Q4_0_4_8 |
-            Self::Q4_0_8_8 => todo!(),
+            Self::Q4_0_8_8 => size!(q: Q4_0  ),
+            Self::TQ1_0    => size!(q: TQ1_0 ),
+            Self::TQ2_0    => size!(q: TQ2_0 ),
+            Self::MXFP4    => size!(q: MXFP4 ),
             _              => unimplemented!(),
         }
     }
@@ -155,6 +207,9 @@ impl GGmlType {
             Self::Q8_0   => quantized::Q8_0  ,
             Self::Q8_1   => quantized::Q8_1  ,
             Self::Q4_0   => quantized::Q4_0  ,
+            Self::Q4_0_4_4 => quantized::Q4_0_4_4,
+            Self::Q4_0_4_8 => quantized::Q4_0_4_8,
+            Self::Q4_0_8_8 => quantized::Q4_0_8_8,
             Self::Q4_1   => quantized::Q4_1  ,
             Self::Q5_0   => quantized::Q5_0  ,
             Self::Q5_1   => quantized::Q5_1  ,
@@ -173,6 +228,9 @@ impl GGmlType {
             Self::IQ2S   => quantized::IQ2S  ,
             Self::IQ4XS  => quantized::IQ4XS ,
             Self::IQ1M   => quantized::IQ1M  ,
+            Self::TQ1_0  => quantized::TQ1_0 ,
+            Self::TQ2_0  => quantized::TQ2_0 ,
+            Self::MXFP4  => quantized::MXFP4 ,
             Self::I8     => primitive::I8    ,
             Self::I16    => primitive::I16   ,
             Self::I32    => primitive::I32   ,
@@ -200,7 +258,10 @@ impl<'a> GGufReader<'a> {
             .skip::<u64>(1)?;
 
         let data = &data[..data.len() - self.remaining().len()];
-        Ok(unsafe { GGufTensorMeta::new_unchecked(data) })
+        let ans = unsafe { GGufTensorMeta::new_unchecked(data) };
+        // 检查类型和形状
+        ans.to_info()?;
+        Ok(ans)
     }
 }
 
@@ -228,26 +289,41 @@ impl<'a> GGufTensorMeta<'a> {
         unsafe { reader.read_str_unchecked() }
     }
 
-    /// 将 [`GGufTensorMeta`] 转换为 [`GGufTensorInfo`]。
-    #[inline]
-    pub fn to_info(&self) -> GGufTensorInfo {
+    /// 将 [`GGufTensorMeta`] 转换为 [`GGufTensorInfo`]，检查张量类型和形状。
+    pub fn to_info(&self) -> Result<GGufTensorInfo, GGufReadError> {
         let mut reader = GGufReader::new(self.0);
-        let ndim: u32 = reader.skip_str().unwrap().read().unwrap();
-        let layout = Layout::array::<u64>(ndim as _).unwrap();
-        let shape = unsafe {
-            let dst = alloc(layout);
-            copy_nonoverlapping(reader.remaining().as_ptr(), dst, layout.size());
-            NonNull::new_unchecked(dst).cast()
+        let ndim: u32 = reader.skip_str()?.read()?;
+        let shape = reader.remaining();
+        let ty = reader.skip::<u64>(ndim as _)?.read_ggml_type()?;
+        let offset: u64 = reader.read()?;
+
+        let layout = Layout::array::<u64>(ndim as _).map_err(|_| GGufReadError::Eos)?;
+        let shape = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            unsafe {
+                let dst = alloc(layout);
+                if dst.is_null() {
+                    handle_alloc_error(layout)
+                }
+                copy_nonoverlapping(shape.as_ptr(), dst, layout.size());
+                NonNull::new_unchecked(dst).cast()
+            }
         };
-        let ty = reader.skip::<u64>(ndim as _).unwrap().read().unwrap();
-        let offset = reader.read().unwrap();
-
-        GGufTensorInfo {
+        let ans = GGufTensorInfo {
             ty,
             ndim,
             shape,
             offset,
-        }
+        };
+
+        // 张量大小和结束位置都不能溢出
+        ty.size()
+            .checked_elements_to_bytes(ans.shape())
+            .and_then(|nbytes| offset.checked_add(nbytes as _))
+            .filter(|&end| usize::try_from(end).is_ok())
+            .ok_or(GGufReadError::InvalidTensorShape)?;
+        Ok(ans)
     }
 }
 
@@ -291,9 +367,16 @@ impl GGufTensorInfo {
 
 impl Drop for GGufTensorInfo {
     fn drop(&mut self) {
-        let ptr = self.shape.as_ptr().cast();
-        let layout = Layout::array::<u64>(self.ndim as _).unwrap();
-        unsafe { dealloc(ptr, layout) }
+        // 创建时已检查布局
+        let layout = unsafe {
+            Layout::from_size_align_unchecked(
+                self.ndim as usize * size_of::<u64>(),
+                align_of::<u64>(),
+            )
+        };
+        if layout.size() != 0 {
+            unsafe { dealloc(self.shape.as_ptr().cast(), layout) }
+        }
     }
 }
 
@@ -317,6 +400,24 @@ mod tests {
         let q4_0_size = GGmlType::Q4_0.size();
         assert!(q4_0_size.block_size > 1);
         assert!(q4_0_size.type_size > 0);
+
+        // 测试交错类型的大小与 Q4_0 一致
+        for ty in [GGmlType::Q4_0_4_4, GGmlType::Q4_0_4_8, GGmlType::Q4_0_8_8] {
+            let size = ty.size();
+            assert_eq!(size.block_size, q4_0_size.block_size);
+            assert_eq!(size.type_size, q4_0_size.type_size);
+        }
+
+        // 测试三值类型和 MXFP4 的大小与 ggml 一致
+        for (ty, block_size, type_size) in [
+            (GGmlType::TQ1_0, 256, 54),
+            (GGmlType::TQ2_0, 256, 66),
+            (GGmlType::MXFP4, 32, 17),
+        ] {
+            let size = ty.size();
+            assert_eq!(size.block_size, block_size);
+            assert_eq!(size.type_size, type_size);
+        }
     }
 
     #[test]
@@ -363,7 +464,7 @@ mod tests {
         assert_eq!(meta.name(), name);
 
         // 转换为 info 并检查
-        let info = meta.to_info();
+        let info = meta.to_info().unwrap();
         assert_eq!(info.ty(), ty);
         assert_eq!(info.ndim, ndim);
         assert_eq!(info.shape(), &shape);
@@ -398,7 +499,7 @@ mod tests {
         let meta = reader.read_tensor_meta().unwrap();
 
         assert_eq!(meta.name(), name);
-        let info = meta.to_info();
+        let info = meta.to_info().unwrap();
         assert_eq!(info.ty(), ty);
         assert_eq!(info.shape(), &shape);
         assert_eq!(info.offset(), offset as usize);
@@ -427,11 +528,11 @@ mod tests {
 
         // 在作用域内创建并销毁 GGufTensorInfo
         {
-            let _info = meta.to_info();
+            let _info = meta.to_info().unwrap();
         }
 
         for _ in 0..5 {
-            let _info = meta.to_info();
+            let _info = meta.to_info().unwrap();
         }
     }
 
@@ -537,6 +638,12 @@ mod tests {
         let _q5_1_layout = GGmlType::Q5_1.to_digit_layout();
         let _q8_0_layout = GGmlType::Q8_0.to_digit_layout();
         let _q8_1_layout = GGmlType::Q8_1.to_digit_layout();
+        let _q4_0_4_4_layout = GGmlType::Q4_0_4_4.to_digit_layout();
+        let _q4_0_4_8_layout = GGmlType::Q4_0_4_8.to_digit_layout();
+        let _q4_0_8_8_layout = GGmlType::Q4_0_8_8.to_digit_layout();
+        let _tq1_0_layout = GGmlType::TQ1_0.to_digit_layout();
+        let _tq2_0_layout = GGmlType::TQ2_0.to_digit_layout();
+        let _mxfp4_layout = GGmlType::MXFP4.to_digit_layout();
 
         // 测试高级量化类型
         let _q2k_layout = GGmlType::Q2K.to_digit_layout();
@@ -563,5 +670,22 @@ mod tests {
         let _i32_layout = GGmlType::I32.to_digit_layout();
         let _i64_layout = GGmlType::I64.to_digit_layout();
         let _f64_layout = GGmlType::F64.to_digit_layout();
+
+        // 所有类型的大小与 digit_layout 一致，交错类型的布局是多行的交错块，每个元素的大小仍一致
+        for ty in (0..64).filter_map(|i| GGmlType::try_from(i).ok()) {
+            let size = ty.size();
+            let layout = ty.to_digit_layout();
+            let rows = match ty {
+                GGmlType::Q4_0_4_4 | GGmlType::Q4_0_4_8 => 4,
+                GGmlType::Q4_0_8_8 => 8,
+                _ => 1,
+            };
+            assert_eq!(
+                rows * size.block_size as usize,
+                layout.group_size(),
+                "{ty:?}"
+            );
+            assert_eq!(rows * size.type_size as usize, layout.nbytes(), "{ty:?}");
+        }
     }
 }
@@ -1,9 +1,9 @@
 use super::GGufWriter;
-use crate::{DEFAULT_ALIGNMENT, GGmlType, GGufFileHeader, GGufMetaDataValueType, pad};
+use crate::{DEFAULT_ALIGNMENT, GGmlType, GGufEndian, GGufFileHeader, GGufMetaDataValueType, pad};
 use log::trace;
 use std::{
     borrow::Borrow,
-    io::{Result, Write},
+    io::{Error, ErrorKind, Result, Write},
     time::Instant,
 };
 
@@ -17,7 +17,7 @@ pub struct GGufFileWriter<T: Write> {
 pub struct GGufTensorWriter<T: Write, U> {
     writer: GGufWriter<T>,
     alignment: usize,
-    data: Vec<U>,
+    data: Vec<(GGmlType, U)>,
     offset: usize,
     write_data: bool,
 }
@@ -40,7 +40,15 @@ impl<T: Write> GGufFileWriter<T> {
     /// 创建一个新的 [`GGufFileWriter`] 实例，初始化 GGUF 文件头。
     #[inline]
     pub fn new(writer: T, header: GGufFileHeader) -> Result<Self> {
-        let mut writer = GGufWriter::new(writer);
+        Self::with_endian(writer, header, GGufEndian::NATIVE)
+    }
+
+    /// 创建按指定字节序写入的 [`GGufFileWriter`] 实例。
+    ///
+    /// 元数据值和张量数据均以本机字节序传入，写入时按张量类型转换。
+    #[inline]
+    pub fn with_endian(writer: T, header: GGufFileHeader, endian: GGufEndian) -> Result<Self> {
+        let mut writer = GGufWriter::with_endian(writer, endian);
         writer.write_header(header)?;
         Ok(Self {
             writer,
@@ -102,7 +110,7 @@ impl<T: Write, U: DataFuture> GGufTensorWriter<T, U> {
         let len = ty.size().elements_to_bytes(shape);
         self.offset += len;
         if self.write_data {
-            self.data.push(data)
+            self.data.push((ty, data))
         }
         Ok(())
     }
@@ -116,11 +124,21 @@ impl<T: Write, U: DataFuture> GGufTensorWriter<T, U> {
             ..
         } = self;
 
+        let swap = !writer.endian().is_native();
         let total = data.len().to_string();
         let width = total.len();
-        for (i, data) in data.into_iter().enumerate() {
+        for (i, (ty, data)) in data.into_iter().enumerate() {
             let t0 = Instant::now();
             let data = data.get();
+            let swapped = if swap {
+                let mut data = data.to_vec();
+                ty.swap_bytes(&mut data)
+                    .map_err(|e| Error::new(ErrorKind::Unsupported, format!("{e:?}")))?;
+                Some(data)
+            } else {
+                None
+            };
+            let data = swapped.as_deref().unwrap_or(data);
             let t1 = Instant::now();
             writer.write_padding(alignment)?;
             writer.write_data(data)?;
@@ -256,7 +274,7 @@ mod tests {
         // 验证偏移量和数据缓存
         assert_eq!(tensor_writer.offset, 24); // 2*3*4=24字节
         assert_eq!(tensor_writer.data.len(), 1);
-        assert_eq!(tensor_writer.data[0].get(), data.as_slice());
+        assert_eq!(tensor_writer.data[0].1.get(), data.as_slice());
     }
 
     #[test]
@@ -1,7 +1,10 @@
-use crate::{GENERAL_ALIGNMENT, GGmlType, GGufFileHeader, GGufMetaDataValueType, pad};
+use crate::{
+    GENERAL_ALIGNMENT, GGmlType, GGufEndian, GGufFileHeader, GGufMetaDataValueType,
+    endian::Swapper, pad,
+};
 use internal::Internal;
 use std::{
-    io::{Result, Write},
+    io::{Error, ErrorKind, Result, Write},
     slice::from_raw_parts,
 };
 
@@ -13,7 +16,25 @@ impl<T: Write> GGufWriter<T> {
     /// 创建一个新的 [`GGufWriter`] 实例。
     #[inline]
     pub fn new(writer: T) -> Self {
-        Self(Internal::new(writer))
+        Self::with_endian(writer, GGufEndian::NATIVE)
+    }
+
+    /// 创建一个按指定字节序写入的 [`GGufWriter`] 实例。
+    ///
+    /// 传入的元数据值仍为本机字节序，写入时转换；[`GGufWriter::write_data`] 写入的数据不会被转换。
+    #[inline]
+    pub fn with_endian(writer: T, endian: GGufEndian) -> Self {
+        Self(Internal::new(writer, !endian.is_native()))
+    }
+
+    /// 获取写入的字节序。
+    #[inline]
+    pub const fn endian(&self) -> GGufEndian {
+        match (self.0.swap(), GGufEndian::NATIVE) {
+            (false, endian) => endian,
+            (true, GGufEndian::Little) => GGufEndian::Big,
+            (true, GGufEndian::Big) => GGufEndian::Little,
+        }
     }
 
     /// 获取已写入的字节数。
@@ -24,6 +45,11 @@ impl<T: Write> GGufWriter<T> {
 
     /// 写入 GGUF 文件头。
     pub fn write_header(&mut self, header: GGufFileHeader) -> Result<()> {
+        let header = if self.0.swap() {
+            header.swap_bytes()
+        } else {
+            header
+        };
         self.write(unsafe {
             from_raw_parts(
                 &header as *const _ as *const u8,
@@ -34,8 +60,16 @@ impl<T: Write> GGufWriter<T> {
 
     /// 写入指定值。
     pub fn write<U: Copy + 'static>(&mut self, val: &[U]) -> Result<()> {
-        self.0
-            .write_bytes(unsafe { from_raw_parts(val.as_ptr().cast(), size_of_val(val)) })
+        let bytes = unsafe { from_raw_parts(val.as_ptr().cast(), size_of_val(val)) };
+        if self.0.swap() && size_of::<U>() > 1 {
+            let mut bytes = bytes.to_vec();
+            for x in bytes.chunks_exact_mut(size_of::<U>()) {
+                x.reverse()
+            }
+            self.0.write_bytes(&bytes)
+        } else {
+            self.0.write_bytes(bytes)
+        }
     }
 
     /// 写入字符串。
@@ -50,7 +84,7 @@ impl<T: Write> GGufWriter<T> {
         self.write_meta_kv(
             GENERAL_ALIGNMENT,
             GGufMetaDataValueType::U32,
-            &(alignment as u32).to_le_bytes(),
+            &(alignment as u32).to_ne_bytes(),
         )?;
         Ok(())
     }
@@ -64,13 +98,21 @@ impl<T: Write> GGufWriter<T> {
     ) -> Result<Option<usize>> {
         self.write_str(key)?;
         self.write(&[ty])?;
-        self.write(val)?;
+        if self.0.swap() {
+            let mut val = val.to_vec();
+            Swapper::new(&mut val, false)
+                .meta_value(ty, 1, 0)
+                .map_err(|e| Error::new(ErrorKind::InvalidData, format!("{e:?}")))?;
+            self.0.write_bytes(&val)?
+        } else {
+            self.0.write_bytes(val)?
+        }
 
         Ok(if key == GENERAL_ALIGNMENT {
             let &[a, b, c, d] = val else {
                 panic!("general.alignment must be an u32")
             };
-            Some(u32::from_le_bytes([a, b, c, d]) as _)
+            Some(u32::from_ne_bytes([a, b, c, d]) as _)
         } else {
             None
         })
@@ -99,21 +141,21 @@ impl<T: Write> GGufWriter<T> {
         Ok(())
     }
 
-    /// 写入数据。
+    /// 写入数据，不转换字节序。
     pub fn write_data(&mut self, data: &[u8]) -> Result<()> {
-        self.write(data)
+        self.0.write_bytes(data)
     }
 }
 
 mod internal {
     use std::io::{BufWriter, Result, Write};
 
-    pub(super) struct Internal<T: Write>(BufWriter<T>, usize);
+    pub(super) struct Internal<T: Write>(BufWriter<T>, usize, bool);
 
     impl<T: Write> Internal<T> {
         #[inline]
-        pub fn new(writer: T) -> Self {
-            Self(BufWriter::new(writer), 0)
+        pub fn new(writer: T, swap: bool) -> Self {
+            Self(BufWriter::new(writer), 0, swap)
         }
 
         #[inline]
@@ -121,6 +163,12 @@ mod internal {
             self.1
         }
 
+        /// 写入时是否交换字节序
+        #[inline]
+        pub const fn swap(&self) -> bool {
+            self.2
+        }
+
         #[inline]
         pub fn write_bytes(&mut self, val: &[u8]) -> Result<()> {
             self.1 += val.len();
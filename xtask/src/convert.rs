@@ -1,16 +1,19 @@
 use crate::{
     LogArgs, list_files, merge_shards,
-    utils::{Operator, OutputArgs, operate, show_file_info},
+    utils::{OperateError, Operator, OutputArgs, load_imatrix, operate, show_file_info},
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 #[derive(Args, Default)]
 pub struct ConvertArgs {
     /// File to convert
     file_pattern: String,
-    /// Steps to apply, separated by "->", maybe "sort", "permute-qk", "merge-linear", "split-linear", "to-llama:<extra>", "cast:<types>", "filter-meta:<key>" or "filter-tensor:<name>"
-    #[clap(long, short = 'x')]
+    /// Steps to apply, separated by "->", maybe "sort", "permute-qk", "merge-linear", "split-linear", "to-llama:<extra>", "cast:<types>", "cast:<preset>", "cast:<rules>", "fit:<budget>:<types>", "filter-meta:<key>", "filter-tensor:<name>" or "endian:<little|big|native>", none to only rewrite the file as GGUF v3
+    #[clap(long, short = 'x', default_value = "")]
     steps: String,
+    /// Importance matrix file generated by llama-imatrix, used by the "cast" step for weighted quantization
+    #[clap(long)]
+    imatrix: Option<PathBuf>,
 
     #[clap(flatten)]
     output: OutputArgs,
@@ -23,6 +26,7 @@ impl ConvertArgs {
         let Self {
             file_pattern,
             steps,
+            imatrix,
             output,
             log,
         } = self;
@@ -34,27 +38,57 @@ impl ConvertArgs {
             return;
         }
 
-        let files = operate(
-            merge_shards(&files).to_owned(),
-            files,
-            steps.split("->").map(|op| match op.trim() {
-                "sort" => Operator::SortTensors,
-                "permute-qk" => Operator::PermuteQK(true),
-                "permute-qk-rev" | "!permute-qk" => Operator::PermuteQK(false),
-                "merge-linear" => Operator::MergeLinear(true),
-                "split-linear" | "!merge-linear" => Operator::MergeLinear(false),
-                "to-llama" => Operator::ToLlama(HashMap::new()),
+        let imatrix = match imatrix.as_deref().map(load_imatrix).transpose() {
+            Ok(imatrix) => imatrix.map(Arc::new),
+            Err(e) => {
+                eprintln!("{}", OperateError::Cast(e));
+                std::process::exit(1)
+            }
+        };
+
+        let operators = steps
+            .split("->")
+            .map(str::trim)
+            .filter(|op| !op.is_empty())
+            .map(|op| match op {
+                "sort" => Ok(Operator::SortTensors),
+                "permute-qk" => Ok(Operator::PermuteQK(true)),
+                "permute-qk-rev" | "!permute-qk" => Ok(Operator::PermuteQK(false)),
+                "merge-linear" => Ok(Operator::MergeLinear(true)),
+                "split-linear" | "!merge-linear" => Ok(Operator::MergeLinear(false)),
+                "to-llama" => Ok(Operator::ToLlama(HashMap::new())),
                 op => match op.split_once(':') {
-                    Some(("cast", types)) => Operator::cast(types),
-                    Some(("to-llama", extra)) => Operator::to_llama(extra),
-                    Some(("filter-meta", key)) => Operator::filter_meta_key(key),
-                    Some(("filter-tensor", name)) => Operator::filter_tensor_name(name),
+                    Some(("cast", types)) => Operator::cast(types, imatrix.clone()),
+                    Some(("fit", args)) => Operator::fit(args, imatrix.clone()),
+                    Some(("to-llama", extra)) => Ok(Operator::to_llama(extra)),
+                    Some(("filter-meta", key)) => Ok(Operator::filter_meta_key(key)),
+                    Some(("filter-tensor", name)) => Ok(Operator::filter_tensor_name(name)),
+                    Some(("endian", endian)) => Operator::endian(endian),
                     _ => panic!("Unsupported operation: {op}"),
                 },
-            }),
+            })
+            .collect::<Result<Vec<_>, _>>();
+        let operators = match operators {
+            Ok(operators) => operators,
+            Err(e) => {
+                eprintln!("{}", OperateError::Cast(e));
+                std::process::exit(1)
+            }
+        };
+
+        let files = operate(
+            merge_shards(&files).to_owned(),
+            files,
+            operators,
             output.into(),
-        )
-        .unwrap();
+        );
+        let files = match files {
+            Ok(files) => files,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1)
+            }
+        };
 
         show_file_info(&files);
     }
@@ -3,6 +3,7 @@
 mod convert;
 mod diff;
 mod merge;
+mod quant_report;
 mod set_meta;
 mod show;
 mod show_data;
@@ -27,6 +28,7 @@ fn main() {
         Convert(args) => args.convert(),
         Diff(args) => args.diff(),
         SetMeta(args) => args.set_meta(),
+        QuantReport(args) => args.report(),
     }
 }
 
@@ -54,6 +56,8 @@ enum Commands {
     Diff(diff::DiffArgs),
     /// Set metadata of gguf files
     SetMeta(set_meta::SetMetaArgs),
+    /// Report quantization error of each tensor under a target type
+    QuantReport(quant_report::QuantReportArgs),
 }
 
 #[derive(Args, Default)]
@@ -121,7 +125,7 @@ fn list_files(pattern: &str) -> impl Iterator<Item = std::path::PathBuf> {
         })
 }
 
-fn merge_shards<T: AsRef<Path>>(files: &[T]) -> GGufFileName {
+fn merge_shards<T: AsRef<Path>>(files: &[T]) -> GGufFileName<'_> {
     files
         .iter()
         .map(|name| GGufFileName::try_from(name.as_ref().file_name().unwrap().to_str().unwrap()))
@@ -52,6 +52,7 @@ impl MergeArgs {
                 shard_max_file_size: Default::default(),
                 shard_no_tensor_first: false,
                 write_data: !no_data,
+                threads: None,
             },
         )
         .unwrap();
@@ -0,0 +1,113 @@
+use crate::utils::{compile_patterns, for_each_tensor, parse_type};
+use ggus::{GGmlType, ggml_quants::QuantErrorStats};
+use std::path::PathBuf;
+
+#[derive(Args, Default)]
+pub struct QuantReportArgs {
+    /// The file to measure
+    file: PathBuf,
+    /// Type to quantize tensors to
+    #[clap(long)]
+    to: String,
+    /// Tensors to measure
+    #[clap(long, short = 't', default_value = "*")]
+    filter_tensor: String,
+}
+
+impl QuantReportArgs {
+    pub fn report(self) {
+        let Self {
+            file,
+            to,
+            filter_tensor,
+        } = self;
+
+        let to = match parse_type(&to) {
+            Ok(to) => to,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1)
+            }
+        };
+        let filter_tensor = compile_patterns(&filter_tensor);
+
+        let mut reports = Vec::new();
+        let mut skipped = Vec::new();
+        let read = for_each_tensor(&file, |name, ty, shape, data| {
+            if !filter_tensor.is_match(name) {
+                return;
+            }
+            match measure(data, ty, shape, to) {
+                Ok(stats) => reports.push((name.to_string(), ty, stats)),
+                Err(reason) => skipped.push((name.to_string(), ty, reason)),
+            }
+        });
+        if let Err(e) = read {
+            eprintln!("{e}");
+            std::process::exit(1)
+        }
+        // 信噪比越低误差越大，排在前面
+        reports.sort_by(|(_, _, a), (_, _, b)| a.snr.total_cmp(&b.snr));
+
+        let width = reports
+            .iter()
+            .map(|(name, ..)| name.len())
+            .chain(skipped.iter().map(|(name, ..)| name.len()))
+            .max()
+            .unwrap_or(0)
+            .max("tensor".len());
+        println!(
+            "{:<width$}  {:<8}  {:>10}  {:>10}  {:>10}  {:>8}",
+            "tensor", "type", "rmse", "max abs", "cosine", "snr(dB)"
+        );
+        for (name, ty, stats) in reports {
+            let QuantErrorStats {
+                rmse,
+                max_abs,
+                cosine,
+                snr,
+            } = stats;
+            println!(
+                "{name:<width$}  {:<8}  {rmse:>10.3e}  {max_abs:>10.3e}  {cosine:>10.6}  {snr:>8.2}",
+                format!("{ty:?}")
+            )
+        }
+        if !skipped.is_empty() {
+            println!();
+            for (name, ty, reason) in skipped {
+                println!(
+                    "{name:<width$}  {:<8}  skipped: {reason}",
+                    format!("{ty:?}")
+                )
+            }
+        }
+    }
+}
+
+/// 将张量反量化为 `f32` 后按 `to` 类型量化，统计误差。
+fn measure(
+    data: &[u8],
+    from: GGmlType,
+    shape: &[u64],
+    to: GGmlType,
+) -> Result<QuantErrorStats, String> {
+    let row = shape.first().copied().unwrap_or(1);
+    let block = to.size().block_size as u64;
+    if !row.is_multiple_of(block) {
+        return Err(format!("row length {row} is not a multiple of {block}"));
+    }
+    let mut src = vec![0.; shape.iter().product::<u64>() as usize];
+    from.dequantize_to_f32(data, &mut src)
+        .map_err(|e| e.to_string())?;
+    to.quant_error(&src).map_err(|e| e.to_string())
+}
+
+#[test]
+fn test_measure() {
+    let src = (0..512).map(|i| (i as f32 / 64.).sin()).collect::<Vec<_>>();
+    let data = src.iter().flat_map(|x| x.to_ne_bytes()).collect::<Vec<_>>();
+    let stats = measure(&data, GGmlType::F32, &[256, 2], GGmlType::Q4_0).unwrap();
+    assert!(stats.cosine > 0.99);
+    assert!(measure(&data, GGmlType::F32, &[32, 16], GGmlType::Q4K).is_err());
+    assert!(measure(&data[..64], GGmlType::Q4_0_4_4, &[32, 4], GGmlType::Q8_0).is_err());
+}
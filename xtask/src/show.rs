@@ -195,7 +195,7 @@ fn show_tensors(reader: &mut GGufReader, count: usize, filter: &Regex) -> Result
             return Err(Failed);
         }
         if filter.is_match(name) {
-            let info = tensor.to_info();
+            let info = tensor.to_info().unwrap();
             name_width = name.len().max(name_width);
             off_width = info.offset().to_string().len().max(off_width);
             tensors.insert(name, info);
@@ -290,7 +290,7 @@ fn fmt_meta_val(
                 T::I64 => buf.push_str(&reader.read::<i64>()?.to_string()),
                 T::F32 => buf.push_str(&fmt_exp(reader.read::<f32>()?)),
                 T::F64 => buf.push_str(&fmt_exp(reader.read::<f64>()? as _)),
-                T::Bool => buf.push(if reader.read()? { '√' } else { '×' }),
+                T::Bool => buf.push(if reader.read_bool()? { '√' } else { '×' }),
                 T::String => {
                     let str = reader.read_str()?;
                     if str.lines().nth(1).is_some() {
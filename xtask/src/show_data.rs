@@ -40,7 +40,8 @@ impl<'a> Fmt<'a> {
             .tensors
             .get(name)
             .unwrap_or_else(|| panic!("tensor `{name}` not exist in this file"))
-            .to_info();
+            .to_info()
+            .unwrap();
         let ty = tensor.ty().to_digit_layout();
         Self {
             ty,
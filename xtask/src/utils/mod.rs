@@ -7,14 +7,19 @@ mod read;
 mod write;
 
 use file_info::FileInfo;
-use ggus::{GGmlType, GGufError, GGufFileName, GGufMetaDataValueType, GGufMetaMap, GGufWriter};
+use ggus::{
+    DataFuture, GGmlType, GGuf, GGufEndian, GGufError, GGufFileName, GGufMetaDataValueType,
+    GGufMetaMap, GGufReader, GGufWriter, ggml_quants::rayon::ThreadPoolBuilder,
+};
 use indexmap::IndexMap;
-use log::info;
-use memmap2::{Mmap, MmapMut};
+use log::{info, warn};
+use memmap2::{Mmap, MmapMut, MmapOptions};
 use std::{
     borrow::Cow,
+    fmt,
     fs::File,
     io,
+    ops::Deref,
     path::Path,
     sync::{Arc, LazyLock},
     time::Instant,
@@ -23,14 +28,26 @@ use std::{
 pub(crate) use diff::diff;
 pub(crate) use file_info::show_file_info;
 pub(crate) use name_pattern::compile_patterns;
-pub(crate) use operator::Operator;
-pub(crate) use output::{OutputArgs, OutputConfig};
+pub(crate) use operator::{CastError, Operator, load_imatrix, parse_type};
+pub(crate) use output::{MemSize, OutputArgs, OutputConfig};
 
-#[allow(dead_code)]
 #[derive(Debug)]
 pub(crate) enum OperateError {
     GGuf(GGufError),
     Io(io::Error),
+    Cast(CastError),
+    Swap(String, GGmlType),
+}
+
+impl fmt::Display for OperateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::GGuf(e) => write!(f, "gguf error: {e}"),
+            Self::Io(e) => write!(f, "io error: {e}"),
+            Self::Cast(e) => write!(f, "cast error: {e}"),
+            Self::Swap(name, ty) => write!(f, "cannot swap byte order of tensor {name} ({ty:?})"),
+        }
+    }
 }
 
 pub(crate) fn operate<T: AsRef<Path>>(
@@ -39,17 +56,28 @@ pub(crate) fn operate<T: AsRef<Path>>(
     operations: impl IntoIterator<Item = Operator>,
     out: OutputConfig,
 ) -> Result<Vec<FileInfo>, OperateError> {
+    // 转换在写入文件时才真正执行，量化使用全局线程池，因此在此限制其线程数
+    if let Some(n) = out.threads
+        && let Err(e) = ThreadPoolBuilder::new().num_threads(n).build_global()
+    {
+        warn!("failed to limit threads to {n}: {e}")
+    }
+
     let files = input_files
         .into_iter()
-        .map(|path| File::open(path).and_then(|f| unsafe { Mmap::map(&f) }))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(OperateError::Io)?;
+        .map(map_native)
+        .collect::<Result<Vec<_>, _>>()?;
 
-    let mut content = Content::new(name, files.iter().map(|m| &**m)).map_err(OperateError::GGuf)?;
+    let mut content = Content::new(name, files.iter().map(|(m, endian)| (&**m, *endian)))
+        .map_err(OperateError::GGuf)?;
+    // 默认保持第一个输入文件的字节序
+    if let Some(&(_, endian)) = files.first() {
+        content.endian = endian
+    }
     for op in operations {
         let name = op.to_string();
         let time = Instant::now();
-        content.apply(op);
+        content.apply(op)?;
         info!("run step {name} in {:?}", time.elapsed());
     }
     let time = Instant::now();
@@ -58,8 +86,79 @@ pub(crate) fn operate<T: AsRef<Path>>(
     ans
 }
 
+/// 读取单个文件，按顺序对每个张量调用 `f`，传入的张量数据均为本机字节序。
+///
+/// 文件的映射和旧版本、字节序的转换与 [`operate`] 相同。
+pub(crate) fn for_each_tensor(
+    path: impl AsRef<Path>,
+    mut f: impl FnMut(&str, GGmlType, &[u64], &[u8]),
+) -> Result<(), OperateError> {
+    let (data, endian) = map_native(path)?;
+    let content =
+        Content::new(GGufFileName::default(), [(&*data, endian)]).map_err(OperateError::GGuf)?;
+    for (name, tensor) in &content.tensors {
+        f(name, tensor.ty, &tensor.shape, tensor.data.get())
+    }
+    Ok(())
+}
+
+/// 输入文件的数据。
+enum InputData {
+    /// 直接映射的文件
+    Mapped(Mmap),
+    /// 从旧版本转换为 v3 的文件
+    Upgraded(Vec<u8>),
+}
+
+impl Deref for InputData {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Mapped(mmap) => mmap,
+            Self::Upgraded(data) => data,
+        }
+    }
+}
+
+/// 映射输入文件。
+///
+/// 非本机字节序的文件映射为私有副本，只将文件头、元数据和张量信息转换为本机字节序，
+/// 张量数据在使用时才逐个转换；v1 版本的文件读入内存并转换为 v3 版本。
+fn map_native(path: impl AsRef<Path>) -> Result<(InputData, GGufEndian), OperateError> {
+    let file = File::open(path).map_err(OperateError::Io)?;
+    let mmap = unsafe { Mmap::map(&file) }.map_err(OperateError::Io)?;
+    let endian = match GGufReader::new(&mmap).read_header() {
+        Ok(header) if header.is_magic_correct() && !header.is_native_endian() => header.endian(),
+        Ok(header) if header.is_magic_correct() && header.version == 1 => {
+            let data = GGuf::upgrade(&mmap).map_err(OperateError::GGuf)?;
+            return Ok((InputData::Upgraded(data), GGufEndian::NATIVE));
+        }
+        // 文件头有误时交给后续解析报告错误
+        _ => return Ok((InputData::Mapped(mmap), GGufEndian::NATIVE)),
+    };
+    drop(mmap);
+
+    // 私有映射只复制被修改的页，张量数据所在的页仍与文件共享
+    let mut data = unsafe { MmapOptions::new().map_copy(&file) }.map_err(OperateError::Io)?;
+    GGuf::swap_to_native(&mut data).map_err(OperateError::GGuf)?;
+    for (name, tensor) in GGuf::new(&data).map_err(OperateError::GGuf)?.tensors {
+        let ty = tensor
+            .to_info()
+            .map_err(|e| OperateError::GGuf(GGufError::Reading(e)))?
+            .ty();
+        // 以空数据试探是否支持转换该类型的字节序
+        ty.swap_bytes(&mut [])
+            .map_err(|_| OperateError::Swap(name.into(), ty))?
+    }
+    let mmap = data.make_read_only().map_err(OperateError::Io)?;
+    Ok((InputData::Mapped(mmap), endian))
+}
+
 struct Content<'a> {
     name: GGufFileName<'a>,
+    endian: GGufEndian,
     alignment: usize,
     meta_kvs: IndexMap<Cow<'a, str>, MetaValue<'a>>,
     tensors: IndexMap<Cow<'a, str>, Tensor<'a>>,
@@ -1,37 +1,60 @@
-use super::{Content, DataPromise, Operator};
+use super::{CastRule, Content, DataPromise, Operator, preset::parse_preset};
 use ggus::{
-    DataFuture, GGmlType as Ty, GGufMetaMapExt,
-    ggml_quants::{Q4_0, Q4_1, Q5_0, Q5_1, Q8_0, Q8_1, QuantExt, bf16, f16},
+    DataFuture, GGmlType as Ty, GGufImatrix, GGufImatrixError, GGufMetaMapExt,
+    ggml_quants::{
+        DataBlock, IQ3S, IQ3XXS, IQ4NL, IQ4XS, InterleaveExt, Q2K, Q3K, Q4_0, Q4_0_4_4, Q4_0_4_8,
+        Q4_0_8_8, Q4K, Q5K, Q6K, QuantExt, QuantWeightedExt,
+    },
 };
 use log::debug;
 use memmap2::MmapMut;
 use regex::Regex;
-use std::{alloc::Layout, collections::HashMap, sync::LazyLock};
+use std::{
+    alloc::Layout,
+    collections::HashMap,
+    fmt, fs, io,
+    iter::zip,
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
 
 impl Operator {
     #[inline]
-    pub fn cast(types: &str) -> Self {
+    pub fn cast(types: &str, imatrix: Option<Arc<GGufImatrix>>) -> Result<Self, CastError> {
+        if types.contains('=') {
+            return Ok(Self::CastRules(CastRule::parse_list(types)?, imatrix));
+        }
+        if !types.contains(':') {
+            let ftype = parse_preset(types).ok_or_else(|| CastError::Preset(types.into()))?;
+            return Ok(Self::CastPreset(ftype, imatrix));
+        }
         static REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\w+):(\w+)").unwrap());
-        Self::Cast(
+        Ok(Self::Cast(
             REGEX
                 .captures_iter(types)
                 .map(|captures| {
                     let key = captures[1].to_string();
-                    let val = parse(&captures[2]);
-                    (key, val)
+                    let val = parse_type(&captures[2])?;
+                    Ok((key, val))
                 })
-                .collect(),
-        )
+                .collect::<Result<_, _>>()?,
+            imatrix,
+        ))
     }
 }
 
 impl Content<'_> {
-    pub(super) fn cast(&mut self, types: HashMap<String, Ty>) {
-        match self.general_architecture().unwrap() {
+    pub(super) fn cast(
+        &mut self,
+        types: HashMap<String, Ty>,
+        imatrix: Option<Arc<GGufImatrix>>,
+    ) -> Result<(), CastError> {
+        // 缺少架构的文件同样无法按类别转换
+        match self.general_architecture().unwrap_or("unknown") {
             "llama" | "gpt2" | "qwen2" | "qwen3" => {
                 let [linear, embd, norm, else_] =
                     ["linear", "embd", "norm", "else"].map(|name| types.get(name).copied());
-                self.cast_(linear, |name, shape| {
+                self.cast_(linear, imatrix.as_deref(), |name, shape| {
                     if matches!(name, "token_embd.weight" | "output.weight") {
                         embd
                     } else if name.ends_with("_norm.weight") || name.ends_with("_norm.bias") {
@@ -46,7 +69,7 @@ impl Content<'_> {
             "clip" => {
                 let [linear, embd, norm, else_] =
                     ["linear", "embd", "norm", "else"].map(|name| types.get(name).copied());
-                self.cast_(linear, |name, _| {
+                self.cast_(linear, imatrix.as_deref(), |name, _| {
                     if let Some(name) = name.strip_prefix("v.") {
                         if name.contains("embd") {
                             embd
@@ -66,11 +89,16 @@ impl Content<'_> {
                     }
                 })
             }
-            arch => panic!("Unsupported architecture: {arch}"),
+            arch => Err(CastError::Architecture(arch.into())),
         }
     }
 
-    fn cast_(&mut self, main: Option<Ty>, mut ty: impl FnMut(&str, &[u64]) -> Option<Ty>) {
+    pub(super) fn cast_(
+        &mut self,
+        main: Option<Ty>,
+        imatrix: Option<&GGufImatrix>,
+        mut ty: impl FnMut(&str, &[u64]) -> Option<Ty>,
+    ) -> Result<(), CastError> {
         if let Some(main) = main {
             self.name.encoding = Some(format!("{main:?}").into());
         }
@@ -79,79 +107,238 @@ impl Content<'_> {
             let to = ty(name, &tensor.shape);
 
             if let Some(to) = to.filter(|to| from != *to) {
+                if !castable(from, to) {
+                    return Err(CastError::Unsupported(name.to_string(), from, to));
+                }
+                let row = tensor.shape[0];
+                if row % to.size().block_size as u64 != 0 {
+                    return Err(CastError::Indivisible(name.to_string(), to, row));
+                }
+                let rows = tensor.shape[1..].iter().product::<u64>();
+                if let Some(r) = interleaved_rows(to).filter(|r| rows % r != 0) {
+                    return Err(CastError::Interleave(name.to_string(), to, rows, r));
+                }
+
                 debug!("Casting tensor {name} from {from:?} to {to:?}");
                 tensor.ty = to;
 
                 let data = tensor.data.clone();
-                let row = tensor.shape[0];
-                tensor.data = DataPromise::lazy(move || cast(row as _, data.get(), from, to))
+                let weights = imatrix.and_then(|m| m.get(name));
+                if let Some(w) = weights {
+                    // 混合专家张量的重要性向量依次包含每个专家的重要性
+                    let len = w.len() as u64;
+                    if len == 0 || !len.is_multiple_of(row) || !rows.is_multiple_of(len / row) {
+                        return Err(CastError::ImatrixLength(name.to_string(), w.len(), row));
+                    }
+                }
+                let weights = weights.map(<[f32]>::to_vec);
+                tensor.data = DataPromise::lazy(move || {
+                    cast(row as _, data.get(), from, to, weights.as_deref())
+                })
             }
         }
+        Ok(())
+    }
+}
+
+/// 张量类型转换错误。
+#[derive(Debug)]
+pub(crate) enum CastError {
+    /// 缺少源类型的反量化或目标类型的量化实现。
+    Unsupported(String, Ty, Ty),
+    /// 行长不能被目标类型的块大小整除。
+    Indivisible(String, Ty, u64),
+    /// 行数不能被交错类型的交错行数整除。
+    Interleave(String, Ty, u64, u64),
+    /// 所有张量都取最小的候选类型仍超出预算，记录最小大小和预算的字节数。
+    OverBudget(usize, usize),
+    /// 无法读取重要性矩阵文件。
+    ImatrixIo(PathBuf, io::Error),
+    /// 无法解析重要性矩阵文件。
+    ImatrixFormat(PathBuf, GGufImatrixError),
+    /// 张量的重要性向量长度与行长不匹配。
+    ImatrixLength(String, usize, u64),
+    /// 不支持按类别转换的架构。
+    Architecture(String),
+    /// 不支持的量化预设名。
+    Preset(String),
+    /// 不支持的类型名。
+    Type(String),
+    /// 不支持的字节序名。
+    Endian(String),
+    /// 无法解析的转换规则，记录规则和原因。
+    Rule(String, String),
+    /// 无法解析的 `fit` 步骤参数，记录参数和原因。
+    Fit(String, String),
+}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unsupported(name, from, to) => {
+                write!(f, "cannot cast tensor {name} from {from:?} to {to:?}")
+            }
+            Self::Indivisible(name, ty, row) => write!(
+                f,
+                "cannot cast tensor {name} to {ty:?}: row length {row} is not a multiple of {}",
+                ty.size().block_size
+            ),
+            Self::Interleave(name, ty, rows, r) => write!(
+                f,
+                "cannot cast tensor {name} to {ty:?}: row count {rows} is not a multiple of {r}"
+            ),
+            Self::OverBudget(size, budget) => write!(
+                f,
+                "cannot fit in budget: at least {size} bytes needed but the budget is {budget} bytes"
+            ),
+            Self::ImatrixIo(path, e) => {
+                write!(f, "cannot read imatrix file {}: {e}", path.display())
+            }
+            Self::ImatrixFormat(path, e) => {
+                write!(f, "cannot parse imatrix file {}: {e}", path.display())
+            }
+            Self::ImatrixLength(name, len, row) => write!(
+                f,
+                "imatrix length {len} of tensor {name} is not a multiple of row length {row}"
+            ),
+            Self::Architecture(arch) => write!(
+                f,
+                "cannot cast architecture {arch} by category, use rules like `cast:<regex>=<type>` instead"
+            ),
+            Self::Preset(name) => write!(f, "unsupported preset: {name}"),
+            Self::Type(name) => write!(f, "unsupported type: {name}"),
+            Self::Endian(name) => write!(f, "unsupported endian: {name}"),
+            Self::Rule(rule, reason) => write!(f, "invalid cast rule `{rule}`: {reason}"),
+            Self::Fit(args, reason) => write!(f, "invalid step `fit:{args}`: {reason}"),
+        }
+    }
+}
+
+/// 读取并解析重要性矩阵文件。
+pub(crate) fn load_imatrix(path: &Path) -> Result<GGufImatrix, CastError> {
+    let data = fs::read(path).map_err(|e| CastError::ImatrixIo(path.into(), e))?;
+    GGufImatrix::new(&data).map_err(|e| CastError::ImatrixFormat(path.into(), e))
+}
+
+/// 交错类型每个数据块交错的行数。
+pub(super) fn interleaved_rows(ty: Ty) -> Option<u64> {
+    match ty {
+        Ty::Q4_0_4_4 | Ty::Q4_0_4_8 => Some(4),
+        Ty::Q4_0_8_8 => Some(8),
+        _ => None,
+    }
+}
+
+/// 判断是否支持从 `from` 转换到 `to`。
+pub(super) fn castable(from: Ty, to: Ty) -> bool {
+    // 交错类型与 Q4_0 可以无损转换
+    fn deinterleaved(ty: Ty) -> Ty {
+        match ty {
+            Ty::Q4_0_4_4 | Ty::Q4_0_4_8 | Ty::Q4_0_8_8 => Ty::Q4_0,
+            ty => ty,
+        }
+    }
+    let from = deinterleaved(from);
+    let to = deinterleaved(to);
+    // 以空数据试探是否实现了反量化和量化
+    from == to
+        || (from.dequantize_to_f32(&[], &mut []).is_ok()
+            && to.quantize_from_f32(&[], &mut []).is_ok())
+}
+
+/// 转换张量数据，没有直接转换的类型经由 F32 中转。
+#[rustfmt::skip]
+fn cast(row: usize, data: &[u8], from: Ty, to: Ty, weights: Option<&[f32]>) -> MmapMut {
+    const Q4_0: Ty = Ty::Q4_0;
+    const F32 : Ty = Ty::F32;
+    match (from, to) {
+        (Q4_0        , Ty::Q4_0_4_4) =>   interleave::<Q4_0_4_4, Q4_0, 4>(data, row),
+        (Q4_0        , Ty::Q4_0_4_8) =>   interleave::<Q4_0_4_8, Q4_0, 4>(data, row),
+        (Q4_0        , Ty::Q4_0_8_8) =>   interleave::<Q4_0_8_8, Q4_0, 8>(data, row),
+        (Ty::Q4_0_4_4, Q4_0        ) => deinterleave::<Q4_0_4_4, Q4_0, 4>(data, row),
+        (Ty::Q4_0_4_8, Q4_0        ) => deinterleave::<Q4_0_4_8, Q4_0, 4>(data, row),
+        (Ty::Q4_0_8_8, Q4_0        ) => deinterleave::<Q4_0_8_8, Q4_0, 8>(data, row),
+        (Ty::Q4_0_4_4 | Ty::Q4_0_4_8 | Ty::Q4_0_8_8, _) =>
+            cast(row, &cast(row, data, from, Q4_0, None), Q4_0, to, weights),
+        (_, Ty::Q4_0_4_4 | Ty::Q4_0_4_8 | Ty::Q4_0_8_8) =>
+            cast(row, &cast(row, data, from, Q4_0, weights), Q4_0, to, None),
+        (F32, _) =>   quantize(row, data, to, weights),
+        (_, F32) => dequantize(data, from),
+        (_, _  ) => cast(row, &cast(row, data, from, F32, None), F32, to, weights),
     }
 }
 
 #[rustfmt::skip]
-fn cast(row: usize, data: &[u8], from: Ty, to: Ty) -> MmapMut {
-    match from {
-        Ty::F32 => match to {
-            Ty::F32      => unreachable!(),
-            Ty::F16      => quantize::<f16 , f32,  1>(data, row),
-            Ty::Q4_0     => quantize::<Q4_0, f32, 32>(data, row),
-            Ty::Q4_1     => quantize::<Q4_1, f32, 32>(data, row),
-            Ty::Q5_0     => quantize::<Q5_0, f32, 32>(data, row),
-            Ty::Q5_1     => quantize::<Q5_1, f32, 32>(data, row),
-            Ty::Q8_0     => quantize::<Q8_0, f32, 32>(data, row),
-            Ty::Q8_1     => quantize::<Q8_1, f32, 32>(data, row),
-            Ty::BF16     => quantize::<bf16, f32,  1>(data, row),
-            _ => todo!(),
-        },
-        Ty::F16 => match to {
-            Ty::F32      => dequantize::<f16 , f32,  1>(data),
-            Ty::F16      => unreachable!(),
-            Ty::Q4_0     =>   quantize::<Q4_0, f16, 32>(data, row),
-            Ty::Q4_1     =>   quantize::<Q4_1, f16, 32>(data, row),
-            Ty::Q5_0     =>   quantize::<Q5_0, f16, 32>(data, row),
-            Ty::Q5_1     =>   quantize::<Q5_1, f16, 32>(data, row),
-            Ty::Q8_0     =>   quantize::<Q8_0, f16, 32>(data, row),
-            Ty::Q8_1     =>   quantize::<Q8_1, f16, 32>(data, row),
-            Ty::BF16     =>   quantize::<bf16, f16,  1>(data, row),
-            _ => todo!(),
-        },
-        Ty::BF16 => match to {
-            Ty::F32      => dequantize::<bf16, f32 ,  1>(data),
-            Ty::F16      =>   quantize::<f16 , bf16,  1>(data, row),
-            Ty::Q4_0     =>   quantize::<Q4_0, bf16, 32>(data, row),
-            Ty::Q4_1     =>   quantize::<Q4_1, bf16, 32>(data, row),
-            Ty::Q5_0     =>   quantize::<Q5_0, bf16, 32>(data, row),
-            Ty::Q5_1     =>   quantize::<Q5_1, bf16, 32>(data, row),
-            Ty::Q8_0     =>   quantize::<Q8_0, bf16, 32>(data, row),
-            Ty::Q8_1     =>   quantize::<Q8_1, bf16, 32>(data, row),
-            Ty::BF16     => unreachable!(),
-            _ => todo!(),
-        },
-        Ty::Q8_0 => match to {
-            Ty::F32      => dequantize::<Q8_0, f32, 32>(data),
-            _            => todo!(),
-        },
-        _ => cast(row, &cast(row, data, from, Ty::F32), Ty::F32, to),
+fn quantize(row: usize, data: &[u8], to: Ty, weights: Option<&[f32]>) -> MmapMut {
+    let src = reslice::<f32>(data);
+    if let Some(weights) = weights {
+        match to {
+            Ty::Q2K    => return quantize_weighted::<Q2K   , 256>(src, row, weights),
+            Ty::Q3K    => return quantize_weighted::<Q3K   , 256>(src, row, weights),
+            Ty::Q4K    => return quantize_weighted::<Q4K   , 256>(src, row, weights),
+            Ty::Q5K    => return quantize_weighted::<Q5K   , 256>(src, row, weights),
+            Ty::Q6K    => return quantize_weighted::<Q6K   , 256>(src, row, weights),
+            Ty::IQ3XXS => return quantize_weighted::<IQ3XXS, 256>(src, row, weights),
+            Ty::IQ3S   => return quantize_weighted::<IQ3S  , 256>(src, row, weights),
+            Ty::IQ4NL  => return quantize_weighted::<IQ4NL ,  32>(src, row, weights),
+            Ty::IQ4XS  => return quantize_weighted::<IQ4XS , 256>(src, row, weights),
+            _          => {}
+        }
     }
+    let mut ans = MmapMut::map_anon(to.size().elements_to_bytes(&[src.len() as _])).unwrap();
+    to.quantize_from_f32(src, &mut ans).unwrap();
+    ans
 }
 
-fn quantize<Ext: QuantExt<T, N>, T, const N: usize>(data: &[u8], row: usize) -> MmapMut {
-    let src = reslice::<T>(data);
-    assert_eq!(src.len() % row, 0);
-    assert_eq!(row % N, 0);
+fn quantize_weighted<Ext, const N: usize>(src: &[f32], row: usize, weights: &[f32]) -> MmapMut
+where
+    Ext: QuantExt<f32, N> + QuantWeightedExt<f32, N>,
+{
     let mut ans = malloc::<Ext>(src.len() / N);
     let dst = reslice_mut::<Ext>(&mut ans);
-    Ext::quantize_slice(dst, src).unwrap();
+    // 行长和重要性向量的长度已在规划转换时检查，每个专家的数据按行对应其重要性向量
+    let n_mat = weights.len() / row;
+    for ((dst, src), weights) in zip(
+        zip(
+            dst.chunks_mut((dst.len() / n_mat).max(1)),
+            src.chunks((src.len() / n_mat).max(1)),
+        ),
+        weights.chunks(row),
+    ) {
+        Ext::quantize_slice_weighted(dst, src, weights).unwrap()
+    }
+    ans
+}
+
+fn dequantize(data: &[u8], from: Ty) -> MmapMut {
+    let size = from.size();
+    let len = data.len() / size.type_size as usize * size.block_size as usize;
+    let mut ans = malloc::<f32>(len);
+    from.dequantize_to_f32(data, reslice_mut(&mut ans)).unwrap();
+    ans
+}
+
+fn interleave<Ext: InterleaveExt<B, R>, B: DataBlock, const R: usize>(
+    data: &[u8],
+    row: usize,
+) -> MmapMut {
+    let src = reslice::<B>(data);
+    assert_eq!(row % B::COUNT, 0);
+    let mut ans = malloc::<Ext>(src.len() / R);
+    let dst = reslice_mut::<Ext>(&mut ans);
+    Ext::interleave_slice(dst, src, row / B::COUNT).unwrap();
     ans
 }
 
-fn dequantize<Ext: QuantExt<T, N>, T, const N: usize>(data: &[u8]) -> MmapMut {
+fn deinterleave<Ext: InterleaveExt<B, R>, B: DataBlock, const R: usize>(
+    data: &[u8],
+    row: usize,
+) -> MmapMut {
     let src = reslice::<Ext>(data);
-    let mut ans = malloc::<T>(src.len() * N);
-    let dst = reslice_mut::<T>(&mut ans);
-    Ext::dequantize_slice(dst, src).unwrap();
+    assert_eq!(row % B::COUNT, 0);
+    let mut ans = malloc::<B>(src.len() * R);
+    let dst = reslice_mut::<B>(&mut ans);
+    Ext::deinterleave_slice(dst, src, row / B::COUNT).unwrap();
     ans
 }
 
@@ -176,52 +363,117 @@ fn reslice_mut<T>(data: &mut [u8]) -> &mut [T] {
     data
 }
 
+/// 解析类型名，忽略大小写，也接受 llama.cpp 的命名，如 `Q4K` 或 `q4_K`。
 #[rustfmt::skip]
-fn parse(s: &str) -> Ty {
-    match s.to_ascii_uppercase().as_str() {
-        "F32"      => Ty::F32,
-        "F16"      => Ty::F16,
-        "Q4_0"     => Ty::Q4_0,
-        "Q4_1"     => Ty::Q4_1,
-        "Q5_0"     => Ty::Q5_0,
-        "Q5_1"     => Ty::Q5_1,
-        "Q8_0"     => Ty::Q8_0,
-        "Q8_1"     => Ty::Q8_1,
-        "Q2K"      => Ty::Q2K,
-        "Q3K"      => Ty::Q3K,
-        "Q4K"      => Ty::Q4K,
-        "Q5K"      => Ty::Q5K,
-        "Q6K"      => Ty::Q6K,
-        "Q8K"      => Ty::Q8K,
-        "IQ2XXS"   => Ty::IQ2XXS,
-        "IQ2XS"    => Ty::IQ2XS,
-        "IQ3XXS"   => Ty::IQ3XXS,
-        "IQ1S"     => Ty::IQ1S,
-        "IQ4NL"    => Ty::IQ4NL,
-        "IQ3S"     => Ty::IQ3S,
-        "IQ2S"     => Ty::IQ2S,
-        "IQ4XS"    => Ty::IQ4XS,
-        "I8"       => Ty::I8,
-        "I16"      => Ty::I16,
-        "I32"      => Ty::I32,
-        "I64"      => Ty::I64,
-        "F64"      => Ty::F64,
-        "IQ1M"     => Ty::IQ1M,
-        "BF16"     => Ty::BF16,
-        "Q4_0_4_4" => Ty::Q4_0_4_4,
-        "Q4_0_4_8" => Ty::Q4_0_4_8,
-        "Q4_0_8_8" => Ty::Q4_0_8_8,
-        _          => todo!(),
-    }
+pub(crate) fn parse_type(s: &str) -> Result<Ty, CastError> {
+    Ok(match s.trim().to_ascii_uppercase().as_str() {
+        "F32"                 => Ty::F32,
+        "F16"                 => Ty::F16,
+        "Q4_0"                => Ty::Q4_0,
+        "Q4_1"                => Ty::Q4_1,
+        "Q5_0"                => Ty::Q5_0,
+        "Q5_1"                => Ty::Q5_1,
+        "Q8_0"                => Ty::Q8_0,
+        "Q8_1"                => Ty::Q8_1,
+        "Q2K"    | "Q2_K"     => Ty::Q2K,
+        "Q3K"    | "Q3_K"     => Ty::Q3K,
+        "Q4K"    | "Q4_K"     => Ty::Q4K,
+        "Q5K"    | "Q5_K"     => Ty::Q5K,
+        "Q6K"    | "Q6_K"     => Ty::Q6K,
+        "Q8K"    | "Q8_K"     => Ty::Q8K,
+        "IQ2XXS" | "IQ2_XXS"  => Ty::IQ2XXS,
+        "IQ2XS"  | "IQ2_XS"   => Ty::IQ2XS,
+        "IQ3XXS" | "IQ3_XXS"  => Ty::IQ3XXS,
+        "IQ1S"   | "IQ1_S"    => Ty::IQ1S,
+        "IQ4NL"  | "IQ4_NL"   => Ty::IQ4NL,
+        "IQ3S"   | "IQ3_S"    => Ty::IQ3S,
+        "IQ2S"   | "IQ2_S"    => Ty::IQ2S,
+        "IQ4XS"  | "IQ4_XS"   => Ty::IQ4XS,
+        "I8"                  => Ty::I8,
+        "I16"                 => Ty::I16,
+        "I32"                 => Ty::I32,
+        "I64"                 => Ty::I64,
+        "F64"                 => Ty::F64,
+        "IQ1M"   | "IQ1_M"    => Ty::IQ1M,
+        "BF16"                => Ty::BF16,
+        "Q4_0_4_4"            => Ty::Q4_0_4_4,
+        "Q4_0_4_8"            => Ty::Q4_0_4_8,
+        "Q4_0_8_8"            => Ty::Q4_0_8_8,
+        "TQ1_0"               => Ty::TQ1_0,
+        "TQ2_0"               => Ty::TQ2_0,
+        "MXFP4"               => Ty::MXFP4,
+        _                     => return Err(CastError::Type(s.into())),
+    })
 }
 
 #[test]
 fn test_parse() {
-    let Operator::Cast(types) = Operator::cast("embd:f16 mat:q8_0, norm:f32") else {
+    let Operator::Cast(types, None) = Operator::cast("embd:f16 mat:q8_0, norm:f32", None).unwrap()
+    else {
         unreachable!()
     };
     assert_eq!(types.len(), 3);
     assert_eq!(types.get("embd"), Some(&Ty::F16));
     assert_eq!(types.get("mat"), Some(&Ty::Q8_0));
     assert_eq!(types.get("norm"), Some(&Ty::F32));
+
+    assert!(matches!(
+        Operator::cast("q4_k_m", None),
+        Ok(Operator::CastPreset(ggus::GGufFileType::MostlyQ4KM, None))
+    ));
+    assert!(matches!(
+        Operator::cast("q4_k_x", None),
+        Err(CastError::Preset(name)) if name == "q4_k_x"
+    ));
+    let Operator::CastRules(rules, None) = Operator::cast(r".*_norm.*=f32; *=q4_0", None).unwrap()
+    else {
+        unreachable!()
+    };
+    assert_eq!(rules.len(), 2);
+
+    assert_eq!(parse_type("q4_K").unwrap(), Ty::Q4K);
+    assert_eq!(parse_type("IQ4_NL").unwrap(), Ty::IQ4NL);
+    assert!(matches!(
+        Operator::cast("embd:f16 mat:q9_0", None),
+        Err(CastError::Type(name)) if name == "q9_0"
+    ));
+    assert!(Operator::endian("BE").is_ok());
+    assert!(matches!(
+        Operator::endian("middle"),
+        Err(CastError::Endian(name)) if name == "middle"
+    ));
+}
+
+#[test]
+fn test_cast() {
+    let mut data = malloc::<f32>(2048);
+    for (i, x) in reslice_mut::<f32>(&mut data).iter_mut().enumerate() {
+        *x = (i as f32 / 64.).sin()
+    }
+    let row = 256;
+    let q8_0 = cast(row, &data, Ty::F32, Ty::Q8_0, None);
+    let q4_0_8_8 = cast(row, &q8_0, Ty::Q8_0, Ty::Q4_0_8_8, None);
+    let q4k = cast(row, &q4_0_8_8, Ty::Q4_0_8_8, Ty::Q4K, None);
+    let f16 = cast(row, &q4k, Ty::Q4K, Ty::F16, None);
+    let f32 = cast(row, &f16, Ty::F16, Ty::F32, None);
+    for (a, b) in zip(reslice::<f32>(&data), reslice::<f32>(&f32)) {
+        assert!((a - b).abs() < 0.2, "{a} vs {b}")
+    }
+
+    assert!(castable(Ty::Q4_0_4_4, Ty::Q4_0_8_8));
+    assert!(castable(Ty::IQ1S, Ty::Q8_0));
+    assert!(!castable(Ty::Q8_0, Ty::IQ1S));
+    assert!(!castable(Ty::I32, Ty::F32));
+}
+
+#[test]
+fn test_load_imatrix() {
+    let path = std::env::temp_dir().join("gguf-utils-test-load-imatrix.dat");
+    assert!(matches!(load_imatrix(&path), Err(CastError::ImatrixIo(..))));
+    fs::write(&path, [1, 2, 3]).unwrap();
+    assert!(matches!(
+        load_imatrix(&path),
+        Err(CastError::ImatrixFormat(..))
+    ));
+    fs::remove_file(&path).unwrap()
 }
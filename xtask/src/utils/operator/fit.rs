@@ -0,0 +1,371 @@
+use super::{
+    super::MemSize,
+    CastError, Content, Operator,
+    cast::{castable, interleaved_rows, parse_type},
+};
+use ggus::{DataFuture, GGmlType as Ty, GGufFileSimulator, GGufImatrix};
+use log::{debug, info};
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+
+/// 转换结果的大小预算。
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Budget {
+    /// 输出文件的字节数。
+    Bytes(MemSize),
+    /// 所有张量的平均每个权重的位数。
+    Bpw(f64),
+}
+
+impl FromStr for Budget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_suffix("bpw") {
+            Some(bpw) => bpw
+                .trim()
+                .parse()
+                .map(Self::Bpw)
+                .map_err(|e| format!("invalid bpw budget `{s}`: {e}")),
+            None => s
+                .parse()
+                .map(Self::Bytes)
+                .map_err(|e| format!("invalid bytes budget `{s}`: {e}")),
+        }
+    }
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Bytes(size) => write!(f, "{size}"),
+            Self::Bpw(bpw) => write!(f, "{bpw}bpw"),
+        }
+    }
+}
+
+impl Operator {
+    #[inline]
+    pub fn fit(args: &str, imatrix: Option<Arc<GGufImatrix>>) -> Result<Self, CastError> {
+        let (budget, types, measure) = parse_fit(args)?;
+        Ok(Self::Fit(budget, types, measure, imatrix))
+    }
+}
+
+/// 解析 `<budget>:<types>[:measure]` 形式的参数。
+fn parse_fit(args: &str) -> Result<(Budget, Vec<Ty>, bool), CastError> {
+    let err = |reason: String| CastError::Fit(args.into(), reason);
+    let mut parts = args.split(':');
+    let budget = parts.next().unwrap().parse().map_err(err)?;
+    let types = parts
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| parse_type(s).map_err(|e| err(e.to_string())))
+        .collect::<Result<Vec<_>, _>>()?;
+    if types.is_empty() {
+        return Err(err("missing candidate types".into()));
+    }
+    let measure = match parts.next().map(str::trim) {
+        None => false,
+        Some("measure") => true,
+        Some(option) => return Err(err(format!("unsupported option `{option}`"))),
+    };
+    Ok((budget, types, measure))
+}
+
+/// 一个可选择类型的张量。
+struct Choice {
+    name: String,
+    /// 按大小升序排列的候选类型及其估计误差。
+    options: Vec<(Ty, f64)>,
+    /// 当前选择的候选类型序号。
+    current: usize,
+}
+
+impl Content<'_> {
+    pub(super) fn fit(
+        &mut self,
+        budget: Budget,
+        mut candidates: Vec<Ty>,
+        measure: bool,
+        imatrix: Option<Arc<GGufImatrix>>,
+    ) -> Result<(), CastError> {
+        sort_candidates(&mut candidates);
+
+        let mut choices = Vec::new();
+        for (name, tensor) in &self.tensors {
+            // 与 llama.cpp 一致，只量化二维以上的权重
+            if !name.ends_with("weight") || tensor.shape.len() < 2 {
+                continue;
+            }
+            let row = tensor.shape[0];
+            let rows = tensor.shape[1..].iter().product::<u64>();
+            let options = candidates
+                .iter()
+                .copied()
+                .filter(|&to| {
+                    castable(tensor.ty, to)
+                        && row.is_multiple_of(to.size().block_size as _)
+                        && interleaved_rows(to).is_none_or(|r| rows.is_multiple_of(r))
+                })
+                .collect::<Vec<_>>();
+            if options.is_empty() {
+                debug!("No candidate type fits tensor {name}, keep {:?}", tensor.ty);
+                continue;
+            }
+
+            // 重要性矩阵的均值作为张量的敏感度
+            let sensitivity = imatrix
+                .as_deref()
+                .and_then(|m| m.get(name))
+                .filter(|w| !w.is_empty())
+                .map_or(1., |w| {
+                    w.iter().map(|&x| x as f64).sum::<f64>() / w.len() as f64
+                });
+            let n = tensor.shape.iter().product::<u64>() as f64;
+            let errors = if measure {
+                measure_errors(tensor.ty, tensor.data.get(), n as _, &options)
+            } else {
+                None
+            };
+            let errors = errors.unwrap_or_else(|| {
+                // 量化噪声的能量随位数每增加一位约降为 1/4
+                options
+                    .iter()
+                    .map(|&ty| n * 0.25f64.powf(bpw(ty)))
+                    .collect()
+            });
+            choices.push(Choice {
+                name: name.to_string(),
+                options: options
+                    .into_iter()
+                    .zip(errors)
+                    .map(|(ty, e)| (ty, e * sensitivity))
+                    .collect(),
+                current: 0,
+            })
+        }
+
+        let limit = match budget {
+            Budget::Bytes(size) => size.nbytes(),
+            Budget::Bpw(bpw) => {
+                let n = self
+                    .tensors
+                    .values()
+                    .map(|t| t.shape.iter().product::<u64>())
+                    .sum::<u64>();
+                (bpw * n as f64 / 8.) as usize
+            }
+        };
+        let size = self.predict_size(budget, &choices);
+        if size > limit {
+            return Err(CastError::OverBudget(size, limit));
+        }
+
+        // 贪心地升级单位字节误差下降最多的张量，直到无法在预算内继续升级
+        loop {
+            let mut best = None;
+            for (i, choice) in choices.iter().enumerate() {
+                let (ty, err) = choice.options[choice.current];
+                let bytes = nbytes(ty, &self.tensors[&*choice.name].shape);
+                for (j, &(ty_, err_)) in choice.options.iter().enumerate().skip(choice.current + 1)
+                {
+                    let gain = err - err_;
+                    let cost = nbytes(ty_, &self.tensors[&*choice.name].shape) - bytes;
+                    if gain <= 0. || cost == 0 {
+                        continue;
+                    }
+                    let ratio = gain / cost as f64;
+                    if best.is_none_or(|(_, _, r)| ratio > r) {
+                        best = Some((i, j, ratio))
+                    }
+                }
+            }
+            let Some((i, j, _)) = best else {
+                break;
+            };
+
+            // 超出预算的升级不再考虑，但仍可尝试该张量其他较小的升级
+            let old = std::mem::replace(&mut choices[i].current, j);
+            if self.predict_size(budget, &choices) > limit {
+                choices[i].current = old;
+                choices[i].options.remove(j);
+            }
+        }
+
+        let size = self.predict_size(budget, &choices);
+        info!("fit {budget}: {size} bytes in budget {limit} bytes");
+        let types = choices
+            .into_iter()
+            .map(|c| (c.name, c.options[c.current].0))
+            .collect::<HashMap<_, _>>();
+        self.cast_(None, imatrix.as_deref(), |name, _| types.get(name).copied())
+    }
+
+    /// 预测选择的类型对应的大小，字节预算时为完整的文件大小，位数预算时为张量数据的大小。
+    fn predict_size(&self, budget: Budget, choices: &[Choice]) -> usize {
+        let types = choices
+            .iter()
+            .map(|c| (&*c.name, c.options[c.current].0))
+            .collect::<HashMap<_, _>>();
+        let ty = |name: &str, ty: Ty| types.get(name).copied().unwrap_or(ty);
+        match budget {
+            Budget::Bytes(_) => {
+                let mut simulator = GGufFileSimulator::with_alignment(self.alignment);
+                for (k, v) in &self.meta_kvs {
+                    simulator.write_meta_kv(k, v.ty, &v.value)
+                }
+                let mut simulator = simulator.finish();
+                for (name, tensor) in &self.tensors {
+                    simulator.write_tensor(name, ty(name, tensor.ty), &tensor.shape)
+                }
+                simulator.written_bytes()
+            }
+            Budget::Bpw(_) => self
+                .tensors
+                .iter()
+                .map(|(name, tensor)| nbytes(ty(name, tensor.ty), &tensor.shape))
+                .sum(),
+        }
+    }
+}
+
+/// 类型平均每个权重的位数。
+fn bpw(ty: Ty) -> f64 {
+    let size = ty.size();
+    size.type_size as f64 * 8. / size.block_size as f64
+}
+
+/// 按位宽升序排列候选类型并去重，位宽相同的类型按编号排列以保证重复项相邻。
+fn sort_candidates(candidates: &mut Vec<Ty>) {
+    candidates.sort_unstable_by(|a, b| {
+        bpw(*a)
+            .total_cmp(&bpw(*b))
+            .then_with(|| (*a as u32).cmp(&(*b as u32)))
+    });
+    candidates.dedup();
+}
+
+fn nbytes(ty: Ty, shape: &[u64]) -> usize {
+    ty.size().elements_to_bytes(shape)
+}
+
+/// 实际量化张量以测量每个候选类型的误差能量，源类型无法反量化时返回 `None`。
+fn measure_errors(from: Ty, data: &[u8], n: usize, options: &[Ty]) -> Option<Vec<f64>> {
+    let mut src = vec![0.; n];
+    from.dequantize_to_f32(data, &mut src).ok()?;
+    options
+        .iter()
+        .map(|ty| {
+            ty.quant_error(&src)
+                .ok()
+                .map(|stats| stats.rmse * stats.rmse * n as f64)
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_fit() {
+    let (budget, types, measure) = parse_fit("6G:q4k,q6_K, q8_0").unwrap();
+    assert!(matches!(budget, Budget::Bytes(MemSize(6442450944))));
+    assert_eq!(types, [Ty::Q4K, Ty::Q6K, Ty::Q8_0]);
+    assert!(!measure);
+
+    let (budget, types, measure) = parse_fit("4.5bpw:q4_0,q8_0:measure").unwrap();
+    assert!(matches!(budget, Budget::Bpw(4.5)));
+    assert_eq!(types, [Ty::Q4_0, Ty::Q8_0]);
+    assert!(measure);
+
+    for args in ["6x:q4k", "6G", "6G:q9k", "6G:q4k:fast"] {
+        assert!(matches!(parse_fit(args), Err(CastError::Fit(a, _)) if a == args));
+    }
+}
+
+#[test]
+fn test_fit() {
+    use super::super::Tensor;
+    use ggus::{GGufEndian, GGufFileName};
+
+    // 两个形状相同的权重，`loud` 的幅度是 `quiet` 的 100 倍，量化误差也大得多
+    let wave = |scale: f32| {
+        (0..256 * 16)
+            .flat_map(|i| ((i as f32 * 0.37).sin() * scale).to_ne_bytes())
+            .collect::<Vec<_>>()
+    };
+    let loud = wave(1.);
+    let quiet = wave(0.01);
+    let norm = vec![0; 256 * 4];
+    let content = || Content {
+        name: GGufFileName::default(),
+        endian: GGufEndian::NATIVE,
+        alignment: 32,
+        meta_kvs: Default::default(),
+        tensors: [
+            ("quiet.weight", &[256, 16][..], &quiet),
+            ("loud.weight", &[256, 16], &loud),
+            ("norm", &[256], &norm),
+        ]
+        .into_iter()
+        .map(|(name, shape, data)| {
+            let tensor = Tensor {
+                ty: Ty::F32,
+                shape: shape.to_vec(),
+                data: super::DataPromise::Borrowed(data),
+            };
+            (name.into(), tensor)
+        })
+        .collect(),
+    };
+    let fit = |budget| {
+        let mut content = content();
+        content
+            .fit(budget, vec![Ty::Q8_0, Ty::Q4_0, Ty::Q8_0], true, None)
+            .map(|()| {
+                let size = content.predict_size(budget, &[]);
+                let ty = |name: &str| content.tensors[name].ty;
+                (size, [ty("quiet.weight"), ty("loud.weight"), ty("norm")])
+            })
+    };
+
+    // 两个权重取 Q4_0 共 2 × 2304 字节，每升级一个到 Q8_0 增加 2048 字节，norm 占 1024 字节
+    let Err(CastError::OverBudget(min, 1)) = fit(Budget::Bytes(MemSize(1))) else {
+        unreachable!()
+    };
+    // 预算只够升级一个张量时，优先升级单位字节误差下降更多的 loud
+    let limit = min + 2048;
+    let (size, types) = fit(Budget::Bytes(MemSize(limit))).unwrap();
+    assert!(size <= limit);
+    assert_eq!(types, [Ty::Q4_0, Ty::Q8_0, Ty::F32]);
+    let (size, types) = fit(Budget::Bytes(MemSize(limit - 1))).unwrap();
+    assert_eq!(size, min);
+    assert_eq!(types, [Ty::Q4_0, Ty::Q4_0, Ty::F32]);
+
+    // 位数预算按所有 8448 个权重计算，7.5bpw 即 7920 字节
+    let (size, types) = fit(Budget::Bpw(7.5)).unwrap();
+    assert_eq!(size, 2304 + 4352 + 1024);
+    assert_eq!(types, [Ty::Q4_0, Ty::Q8_0, Ty::F32]);
+    let (size, types) = fit(Budget::Bpw(10.)).unwrap();
+    assert_eq!(size, 2 * 4352 + 1024);
+    assert_eq!(types, [Ty::Q8_0, Ty::Q8_0, Ty::F32]);
+    assert!(matches!(
+        fit(Budget::Bpw(5.)),
+        Err(CastError::OverBudget(5632, 5280))
+    ));
+
+    // 位宽相同的重复类型不相邻时也要去重
+    let mut candidates = vec![Ty::Q4_0, Ty::Q4K, Ty::Q4_0];
+    sort_candidates(&mut candidates);
+    assert_eq!(candidates, [Ty::Q4_0, Ty::Q4K]);
+    let mut content = content();
+    content
+        .fit(
+            Budget::Bpw(32.),
+            vec![Ty::Q4_0, Ty::Q4K, Ty::Q4_0],
+            true,
+            None,
+        )
+        .unwrap();
+}
@@ -1,25 +1,36 @@
 mod cast;
+mod fit;
 mod merge;
 mod permute_qk;
+mod preset;
+mod rules;
 mod set_meta;
 mod sort;
 mod to_llama;
 
-use super::{Content, DataPromise, compile_patterns};
-use ggus::{GGmlType, GGufMetaDataValueType};
+pub(crate) use cast::{CastError, load_imatrix, parse_type};
+pub(crate) use fit::Budget;
+pub(crate) use rules::CastRule;
+
+use super::{Content, DataPromise, OperateError, compile_patterns};
+use ggus::{GGmlType, GGufEndian, GGufFileType, GGufImatrix, GGufMetaDataValueType};
 use regex::Regex;
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, sync::Arc};
 
 #[allow(unused)]
 pub(crate) enum Operator {
     FilterMetaKey(Regex),
     FilterTensorName(Regex),
-    Cast(HashMap<String, GGmlType>),
+    Cast(HashMap<String, GGmlType>, Option<Arc<GGufImatrix>>),
+    CastPreset(GGufFileType, Option<Arc<GGufImatrix>>),
+    CastRules(Vec<CastRule>, Option<Arc<GGufImatrix>>),
+    Fit(Budget, Vec<GGmlType>, bool, Option<Arc<GGufImatrix>>),
     ToLlama(HashMap<String, String>),
     MergeLinear(bool),
     PermuteQK(bool),
     SortTensors,
     SetMeta(HashMap<String, (GGufMetaDataValueType, Vec<u8>)>),
+    Endian(GGufEndian),
 }
 
 impl fmt::Display for Operator {
@@ -27,7 +38,13 @@ impl fmt::Display for Operator {
         match self {
             Self::FilterMetaKey(regex) => write!(f, "filter-meta: {}", regex.as_str()),
             Self::FilterTensorName(regex) => write!(f, "filter-tensor: {}", regex.as_str()),
-            Self::Cast(types) => write!(f, "cast:{types:?}"),
+            Self::Cast(types, _) => write!(f, "cast:{types:?}"),
+            Self::CastPreset(ftype, _) => write!(f, "cast:{ftype:?}"),
+            Self::Fit(budget, types, _, _) => write!(f, "fit:{budget}:{types:?}"),
+            Self::CastRules(rules, _) => {
+                let rules = rules.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "cast:{}", rules.join("; "))
+            }
             Self::ToLlama(extra) => write!(f, "to-llama:{extra:?}"),
             &Self::MergeLinear(val) => {
                 if val {
@@ -40,6 +57,7 @@ impl fmt::Display for Operator {
             Self::PermuteQK(false) => write!(f, "permute-qk-rev"),
             Self::SortTensors => write!(f, "sort-tensors"),
             Self::SetMeta(map) => write!(f, "set-meta: {} items", map.len()),
+            Self::Endian(endian) => write!(f, "endian:{endian:?}"),
         }
     }
 }
@@ -54,20 +72,39 @@ impl Operator {
     pub fn filter_tensor_name(p: impl AsRef<str>) -> Self {
         Self::FilterTensorName(compile_patterns(p.as_ref()))
     }
+
+    #[inline]
+    pub fn endian(endian: &str) -> Result<Self, CastError> {
+        endian
+            .parse()
+            .map(Self::Endian)
+            .map_err(|_| CastError::Endian(endian.into()))
+    }
 }
 
 impl Content<'_> {
-    pub fn apply(&mut self, op: Operator) {
+    pub fn apply(&mut self, op: Operator) -> Result<(), OperateError> {
         use Operator::*;
         match op {
             ToLlama(extra) => self.convert_to_llama(extra),
             FilterMetaKey(r) => self.meta_kvs.retain(|k, _| r.is_match(k)),
             FilterTensorName(r) => self.tensors.retain(|k, _| r.is_match(k)),
-            Cast(types) => self.cast(types),
+            Cast(types, imatrix) => self.cast(types, imatrix).map_err(OperateError::Cast)?,
+            CastPreset(ftype, imatrix) => self
+                .cast_preset(ftype, imatrix)
+                .map_err(OperateError::Cast)?,
+            CastRules(rules, imatrix) => self
+                .cast_rules(rules, imatrix)
+                .map_err(OperateError::Cast)?,
+            Fit(budget, types, measure, imatrix) => self
+                .fit(budget, types, measure, imatrix)
+                .map_err(OperateError::Cast)?,
             MergeLinear(ty) => self.merge_linear(ty),
             PermuteQK(ty) => self.permute_qk(ty),
             SortTensors => self.sort_tensors(),
             SetMeta(map) => self.set_meta(map),
+            Endian(endian) => self.endian = endian,
         }
+        Ok(())
     }
 }
@@ -0,0 +1,481 @@
+use super::{super::MetaValue, CastError, Content};
+use ggus::{
+    GGmlType as Ty, GGufFileType as Ft, GGufImatrix, GGufMetaDataValueType, GGufMetaMapExt,
+};
+use regex::Regex;
+use std::sync::{Arc, LazyLock};
+
+/// llama.cpp 量化预设的名字，与 `llama-quantize` 的命名一致。
+///
+/// 仅包含所需类型都能量化的预设。
+#[rustfmt::skip]
+const PRESETS: [(&str, Ft); 24] = [
+    ("F32"    , Ft::AllF32      ),
+    ("F16"    , Ft::MostlyF16   ),
+    ("BF16"   , Ft::MostlyBF16  ),
+    ("Q4_0"   , Ft::MostlyQ4_0  ),
+    ("Q4_1"   , Ft::MostlyQ4_1  ),
+    ("Q5_0"   , Ft::MostlyQ5_0  ),
+    ("Q5_1"   , Ft::MostlyQ51   ),
+    ("Q8_0"   , Ft::MostlyQ8_0  ),
+    ("Q2_K"   , Ft::MostlyQ2K   ),
+    ("Q2_K_S" , Ft::MostlyQ2KS  ),
+    ("Q3_K_S" , Ft::MostlyQ3KS  ),
+    ("Q3_K_M" , Ft::MostlyQ3KM  ),
+    ("Q3_K_L" , Ft::MostlyQ3KL  ),
+    ("Q4_K_S" , Ft::MostlyQ4KS  ),
+    ("Q4_K_M" , Ft::MostlyQ4KM  ),
+    ("Q5_K_S" , Ft::MostlyQ5KS  ),
+    ("Q5_K_M" , Ft::MostlyQ5KM  ),
+    ("Q6_K"   , Ft::MostlyQ6K   ),
+    ("IQ3_XXS", Ft::MostlyIQ3XXS),
+    ("IQ3_XS" , Ft::MostlyIQ3XS ),
+    ("IQ3_S"  , Ft::MostlyIQ3S  ),
+    ("IQ3_M"  , Ft::MostlyIQ3M  ),
+    ("IQ4_NL" , Ft::MostlyIQ4NL ),
+    ("IQ4_XS" , Ft::MostlyIQ4XS ),
+];
+
+/// 解析预设名，忽略大小写和下划线，如 `q4_k_m` 或 `Q4KM`。
+pub(super) fn parse_preset(s: &str) -> Option<Ft> {
+    let normalize = |s: &str| s.replace('_', "").to_ascii_uppercase();
+    let s = normalize(s.trim());
+    PRESETS
+        .iter()
+        .find(|(name, _)| normalize(name) == s)
+        .map(|&(_, ftype)| ftype)
+}
+
+fn preset_name(ftype: Ft) -> &'static str {
+    PRESETS.iter().find(|(_, ft)| *ft == ftype).unwrap().0
+}
+
+impl Content<'_> {
+    pub(super) fn cast_preset(
+        &mut self,
+        ftype: Ft,
+        imatrix: Option<Arc<GGufImatrix>>,
+    ) -> Result<(), CastError> {
+        let n_head = self.llm_attention_head_count().unwrap_or(1);
+        let n_head_kv = self.llm_attention_head_count_kv().unwrap_or(n_head);
+        let policy = Policy {
+            ftype,
+            n_layer: self.llm_block_count().unwrap_or(0),
+            n_gqa: n_head / n_head_kv.max(1),
+            n_expert: self.llm_expert_count().unwrap_or(0),
+            has_output: self.tensors.contains_key("output.weight"),
+            has_imatrix: imatrix.is_some(),
+            falcon: matches!(self.general_architecture(), Ok("falcon")),
+        };
+        self.cast_(None, imatrix.as_deref(), |name, shape| {
+            policy.tensor_type(name, shape)
+        })?;
+
+        self.name.encoding = Some(preset_name(ftype).into());
+        self.meta_kvs.insert(
+            "general.file_type".into(),
+            MetaValue {
+                ty: GGufMetaDataValueType::U32,
+                value: (ftype as u32).to_ne_bytes().to_vec().into(),
+            },
+        );
+        Ok(())
+    }
+}
+
+/// 按 llama.cpp 的 `llama_tensor_get_type` 为每个张量选择类型。
+struct Policy {
+    ftype: Ft,
+    n_layer: usize,
+    n_gqa: usize,
+    n_expert: usize,
+    has_output: bool,
+    has_imatrix: bool,
+    /// llama.cpp 为 Falcon 架构单独调整了部分规则。
+    falcon: bool,
+}
+
+impl Policy {
+    /// 预设的主要类型。
+    fn base(&self) -> Ty {
+        match self.ftype {
+            Ft::AllF32 => Ty::F32,
+            Ft::MostlyF16 => Ty::F16,
+            Ft::MostlyBF16 => Ty::BF16,
+            Ft::MostlyQ4_0 => Ty::Q4_0,
+            Ft::MostlyQ4_1 => Ty::Q4_1,
+            Ft::MostlyQ5_0 => Ty::Q5_0,
+            Ft::MostlyQ51 => Ty::Q5_1,
+            Ft::MostlyQ8_0 => Ty::Q8_0,
+            Ft::MostlyQ2K | Ft::MostlyQ2KS => Ty::Q2K,
+            Ft::MostlyQ3KS | Ft::MostlyQ3KM | Ft::MostlyQ3KL => Ty::Q3K,
+            Ft::MostlyQ4KS | Ft::MostlyQ4KM => Ty::Q4K,
+            Ft::MostlyQ5KS | Ft::MostlyQ5KM => Ty::Q5K,
+            Ft::MostlyQ6K => Ty::Q6K,
+            Ft::MostlyIQ3XXS => Ty::IQ3XXS,
+            Ft::MostlyIQ3XS | Ft::MostlyIQ3S | Ft::MostlyIQ3M => Ty::IQ3S,
+            Ft::MostlyIQ4NL => Ty::IQ4NL,
+            Ft::MostlyIQ4XS => Ty::IQ4XS,
+            ftype => unreachable!("unsupported preset {ftype:?}"),
+        }
+    }
+
+    fn tensor_type(&self, name: &str, shape: &[u64]) -> Option<Ty> {
+        // 与 llama.cpp 一致，只量化二维以上的权重，且跳过专家路由和位置编码
+        if !name.ends_with("weight")
+            || shape.len() < 2
+            || name.contains("ffn_gate_inp")
+            || name.contains("pos_embd")
+            || name.contains("token_types")
+        {
+            return None;
+        }
+
+        let base = self.base();
+        let ty = if matches!(base, Ty::F32 | Ty::F16 | Ty::BF16) {
+            base
+        } else {
+            self.quantized_type(name, shape[0], base)
+        };
+        Some(fallback(ty, shape[0]))
+    }
+
+    fn quantized_type(&self, name: &str, row: u64, base: Ty) -> Ty {
+        static LAYER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^blk\.(\d+)\.").unwrap());
+        let i = LAYER
+            .captures(name)
+            .map_or(0, |captures| captures[1].parse().unwrap());
+        let n = self.n_layer;
+        let more_bits = use_more_bits(i, n);
+        let gqa4 = self.n_gqa >= 4;
+
+        if name == "output.weight" || (!self.has_output && name == "token_embd.weight") {
+            return if self.falcon || !row.is_multiple_of(256) {
+                Ty::Q8_0
+            } else if base != Ty::Q8_0 {
+                Ty::Q6K
+            } else {
+                base
+            };
+        }
+        if name == "token_embd.weight" {
+            return match self.ftype {
+                Ft::MostlyIQ3XXS => Ty::IQ3S,
+                _ => base,
+            };
+        }
+
+        if name.contains("attn_v") {
+            let ty = match self.ftype {
+                Ft::MostlyQ2K => {
+                    if gqa4 {
+                        Ty::Q4K
+                    } else {
+                        Ty::Q3K
+                    }
+                }
+                Ft::MostlyQ2KS if gqa4 => Ty::Q4K,
+                Ft::MostlyIQ3XXS if gqa4 => Ty::Q4K,
+                Ft::MostlyIQ3XXS if !self.has_imatrix => Ty::IQ3S,
+                Ft::MostlyIQ3XS | Ft::MostlyIQ3S if gqa4 => Ty::Q4K,
+                Ft::MostlyIQ3M => Ty::Q4K,
+                Ft::MostlyQ3KM => {
+                    if i < 2 {
+                        Ty::Q5K
+                    } else {
+                        Ty::Q4K
+                    }
+                }
+                Ft::MostlyQ3KL => Ty::Q5K,
+                Ft::MostlyIQ4NL | Ft::MostlyIQ4XS if gqa4 => Ty::Q5K,
+                Ft::MostlyQ4KM | Ft::MostlyQ5KM if more_bits => Ty::Q6K,
+                Ft::MostlyQ4KS if i < 4 => Ty::Q5K,
+                _ => base,
+            };
+            if self.n_expert == 8 { Ty::Q8_0 } else { ty }
+        } else if name.contains("attn_k") {
+            match self.ftype {
+                _ if self.n_expert == 8 => Ty::Q8_0,
+                Ft::MostlyIQ3XS => Ty::IQ3XXS,
+                _ => base,
+            }
+        } else if name.contains("attn_q") && !name.contains("attn_qkv") {
+            match self.ftype {
+                Ft::MostlyIQ3XS => Ty::IQ3XXS,
+                _ => base,
+            }
+        } else if name.contains("ffn_down") {
+            match self.ftype {
+                Ft::MostlyQ2K => Ty::Q3K,
+                Ft::MostlyQ2KS if i < n / 8 => Ty::Q4K,
+                Ft::MostlyIQ3XXS if !self.has_imatrix => {
+                    if i < n / 8 {
+                        Ty::Q4K
+                    } else {
+                        Ty::Q3K
+                    }
+                }
+                Ft::MostlyQ3KM => {
+                    if i < n / 16 {
+                        Ty::Q5K
+                    } else if !self.falcon || more_bits {
+                        Ty::Q4K
+                    } else {
+                        Ty::Q3K
+                    }
+                }
+                Ft::MostlyIQ3M if i < n / 8 || (self.n_expert == 8 && more_bits) => Ty::Q4K,
+                Ft::MostlyQ3KL => {
+                    if self.falcon {
+                        Ty::Q4K
+                    } else {
+                        Ty::Q5K
+                    }
+                }
+                Ft::MostlyQ4KM if self.falcon => {
+                    if i < n / 16 {
+                        Ty::Q6K
+                    } else if more_bits {
+                        Ty::Q5K
+                    } else {
+                        Ty::Q4K
+                    }
+                }
+                Ft::MostlyQ4KM | Ft::MostlyQ5KM if more_bits => Ty::Q6K,
+                Ft::MostlyIQ4NL | Ft::MostlyIQ4XS if !self.has_imatrix && i < n / 8 => Ty::Q5K,
+                Ft::MostlyQ4KS if !self.falcon && i < n / 8 => Ty::Q5K,
+                // 即使有重要性矩阵，Q4_0 和 Q5_0 在前几层的 ffn_down 上误差仍可能很大
+                Ft::MostlyQ4_0 if self.has_imatrix && i < n / 8 => Ty::Q4_1,
+                Ft::MostlyQ5_0 if self.has_imatrix && i < n / 8 => Ty::Q5_1,
+                _ => base,
+            }
+        } else if name.contains("attn_output") {
+            if self.falcon {
+                match self.ftype {
+                    Ft::MostlyQ3KL => Ty::Q4K,
+                    _ => base,
+                }
+            } else if self.n_expert == 8 {
+                match self.ftype {
+                    Ft::MostlyQ2K
+                    | Ft::MostlyIQ3XS
+                    | Ft::MostlyIQ3XXS
+                    | Ft::MostlyQ3KS
+                    | Ft::MostlyQ3KM
+                    | Ft::MostlyIQ4NL
+                    | Ft::MostlyQ4KS
+                    | Ft::MostlyQ4KM
+                    | Ft::MostlyIQ3S
+                    | Ft::MostlyIQ3M
+                    | Ft::MostlyIQ4XS => Ty::Q5K,
+                    _ => base,
+                }
+            } else {
+                match self.ftype {
+                    Ft::MostlyQ2K => Ty::Q3K,
+                    Ft::MostlyIQ3XXS => Ty::IQ3S,
+                    Ft::MostlyQ3KM | Ft::MostlyIQ3M => Ty::Q4K,
+                    Ft::MostlyQ3KL => Ty::Q5K,
+                    _ => base,
+                }
+            }
+        } else if name.contains("attn_qkv") {
+            match self.ftype {
+                Ft::MostlyQ3KM | Ft::MostlyQ3KL | Ft::MostlyIQ3M => Ty::Q4K,
+                Ft::MostlyQ4KM => Ty::Q5K,
+                Ft::MostlyQ5KM => Ty::Q6K,
+                _ => base,
+            }
+        } else if name.contains("ffn_gate") || name.contains("ffn_up") {
+            match self.ftype {
+                Ft::MostlyIQ3XS if i >= n / 8 && i < 7 * n / 8 => Ty::IQ3XXS,
+                _ => base,
+            }
+        } else {
+            base
+        }
+    }
+}
+
+/// 与 llama.cpp 相同，在首尾各 1/8 的层和中间每 3 层中的 1 层使用更多位。
+fn use_more_bits(i: usize, n: usize) -> bool {
+    i < n / 8 || i >= 7 * n / 8 || (i - n / 8) % 3 == 2
+}
+
+/// 行长不能被块大小整除时，按 llama.cpp 的规则换用块更小的类型。
+fn fallback(ty: Ty, row: u64) -> Ty {
+    if row.is_multiple_of(ty.size().block_size as _) {
+        return ty;
+    }
+    let ty = match ty {
+        Ty::Q2K | Ty::Q3K | Ty::IQ3XXS | Ty::IQ3S | Ty::IQ4XS => Ty::IQ4NL,
+        Ty::Q4K => Ty::Q5_0,
+        Ty::Q5K => Ty::Q5_1,
+        Ty::Q6K => Ty::Q8_0,
+        _ => Ty::F16,
+    };
+    if row.is_multiple_of(ty.size().block_size as _) {
+        ty
+    } else {
+        Ty::F16
+    }
+}
+
+#[test]
+fn test_preset() {
+    assert_eq!(parse_preset("q4_k_m"), Some(Ft::MostlyQ4KM));
+    assert_eq!(parse_preset("Q3KL"), Some(Ft::MostlyQ3KL));
+    assert_eq!(parse_preset("IQ2_XXS"), None);
+    assert_eq!(preset_name(Ft::MostlyQ5KS), "Q5_K_S");
+
+    let policy = Policy {
+        ftype: Ft::MostlyQ4KM,
+        n_layer: 32,
+        n_gqa: 4,
+        n_expert: 0,
+        has_output: true,
+        has_imatrix: false,
+        falcon: false,
+    };
+    let ty = |name: &str, shape: &[u64]| policy.tensor_type(name, shape);
+    assert_eq!(ty("output.weight", &[4096, 32000]), Some(Ty::Q6K));
+    assert_eq!(ty("token_embd.weight", &[4096, 32000]), Some(Ty::Q4K));
+    assert_eq!(ty("output_norm.weight", &[4096]), None);
+    assert_eq!(ty("blk.0.attn_v.weight", &[4096, 1024]), Some(Ty::Q6K));
+    assert_eq!(ty("blk.4.attn_v.weight", &[4096, 1024]), Some(Ty::Q4K));
+    assert_eq!(ty("blk.6.ffn_down.weight", &[11008, 4096]), Some(Ty::Q6K));
+    assert_eq!(ty("blk.7.ffn_down.weight", &[11008, 4096]), Some(Ty::Q4K));
+    assert_eq!(ty("blk.31.ffn_down.weight", &[11008, 4096]), Some(Ty::Q6K));
+    assert_eq!(ty("blk.7.attn_q.weight", &[4096, 4096]), Some(Ty::Q4K));
+    // 行长不能被 256 整除时换用 Q5_0
+    assert_eq!(ty("blk.7.attn_q.weight", &[4000, 4096]), Some(Ty::Q5_0));
+
+    // 一半的 attn_v 和 ffn_down 使用 Q6_K
+    let q6k = (0..32)
+        .filter(|i| ty(&format!("blk.{i}.ffn_down.weight"), &[11008, 4096]) == Some(Ty::Q6K))
+        .count();
+    assert_eq!(q6k, 16);
+}
+
+#[test]
+fn test_policy() {
+    use Ty::*;
+    use std::iter::zip;
+
+    let policy = |ftype| Policy {
+        ftype,
+        n_layer: 32,
+        n_gqa: 1,
+        n_expert: 0,
+        has_output: true,
+        has_imatrix: false,
+        falcon: false,
+    };
+    let ty = |policy: &Policy, name: &str| policy.tensor_type(name, &[4096, 4096]).unwrap();
+
+    // 32 层中第 0 层在前 1/8，第 16 层不使用更多位，第 18 层使用更多位
+    const NAMES: [&str; 11] = [
+        "token_embd.weight",
+        "output.weight",
+        "blk.16.attn_q.weight",
+        "blk.16.attn_k.weight",
+        "blk.0.attn_v.weight",
+        "blk.16.attn_v.weight",
+        "blk.16.attn_output.weight",
+        "blk.16.ffn_gate.weight",
+        "blk.0.ffn_down.weight",
+        "blk.16.ffn_down.weight",
+        "blk.18.ffn_down.weight",
+    ];
+    // 与 llama.cpp 的 llama_tensor_get_type 逐条对照得出
+    #[rustfmt::skip]
+    let table = [
+        (Ft::AllF32      , [F32   , F32, F32   , F32   , F32 , F32  , F32  , F32   , F32 , F32  , F32  ]),
+        (Ft::MostlyF16   , [F16   , F16, F16   , F16   , F16 , F16  , F16  , F16   , F16 , F16  , F16  ]),
+        (Ft::MostlyBF16  , [BF16  , BF16, BF16 , BF16  , BF16, BF16 , BF16 , BF16  , BF16, BF16 , BF16 ]),
+        (Ft::MostlyQ4_0  , [Q4_0  , Q6K, Q4_0  , Q4_0  , Q4_0, Q4_0 , Q4_0 , Q4_0  , Q4_0, Q4_0 , Q4_0 ]),
+        (Ft::MostlyQ4_1  , [Q4_1  , Q6K, Q4_1  , Q4_1  , Q4_1, Q4_1 , Q4_1 , Q4_1  , Q4_1, Q4_1 , Q4_1 ]),
+        (Ft::MostlyQ5_0  , [Q5_0  , Q6K, Q5_0  , Q5_0  , Q5_0, Q5_0 , Q5_0 , Q5_0  , Q5_0, Q5_0 , Q5_0 ]),
+        (Ft::MostlyQ51   , [Q5_1  , Q6K, Q5_1  , Q5_1  , Q5_1, Q5_1 , Q5_1 , Q5_1  , Q5_1, Q5_1 , Q5_1 ]),
+        (Ft::MostlyQ8_0  , [Q8_0  , Q8_0, Q8_0 , Q8_0  , Q8_0, Q8_0 , Q8_0 , Q8_0  , Q8_0, Q8_0 , Q8_0 ]),
+        (Ft::MostlyQ2K   , [Q2K   , Q6K, Q2K   , Q2K   , Q3K , Q3K  , Q3K  , Q2K   , Q3K , Q3K  , Q3K  ]),
+        (Ft::MostlyQ2KS  , [Q2K   , Q6K, Q2K   , Q2K   , Q2K , Q2K  , Q2K  , Q2K   , Q4K , Q2K  , Q2K  ]),
+        (Ft::MostlyQ3KS  , [Q3K   , Q6K, Q3K   , Q3K   , Q3K , Q3K  , Q3K  , Q3K   , Q3K , Q3K  , Q3K  ]),
+        (Ft::MostlyQ3KM  , [Q3K   , Q6K, Q3K   , Q3K   , Q5K , Q4K  , Q4K  , Q3K   , Q5K , Q4K  , Q4K  ]),
+        (Ft::MostlyQ3KL  , [Q3K   , Q6K, Q3K   , Q3K   , Q5K , Q5K  , Q5K  , Q3K   , Q5K , Q5K  , Q5K  ]),
+        (Ft::MostlyQ4KS  , [Q4K   , Q6K, Q4K   , Q4K   , Q5K , Q4K  , Q4K  , Q4K   , Q5K , Q4K  , Q4K  ]),
+        (Ft::MostlyQ4KM  , [Q4K   , Q6K, Q4K   , Q4K   , Q6K , Q4K  , Q4K  , Q4K   , Q6K , Q4K  , Q6K  ]),
+        (Ft::MostlyQ5KS  , [Q5K   , Q6K, Q5K   , Q5K   , Q5K , Q5K  , Q5K  , Q5K   , Q5K , Q5K  , Q5K  ]),
+        (Ft::MostlyQ5KM  , [Q5K   , Q6K, Q5K   , Q5K   , Q6K , Q5K  , Q5K  , Q5K   , Q6K , Q5K  , Q6K  ]),
+        (Ft::MostlyQ6K   , [Q6K   , Q6K, Q6K   , Q6K   , Q6K , Q6K  , Q6K  , Q6K   , Q6K , Q6K  , Q6K  ]),
+        (Ft::MostlyIQ3XXS, [IQ3S  , Q6K, IQ3XXS, IQ3XXS, IQ3S, IQ3S , IQ3S , IQ3XXS, Q4K , Q3K  , Q3K  ]),
+        (Ft::MostlyIQ3XS , [IQ3S  , Q6K, IQ3XXS, IQ3XXS, IQ3S, IQ3S , IQ3S , IQ3XXS, IQ3S, IQ3S , IQ3S ]),
+        (Ft::MostlyIQ3S  , [IQ3S  , Q6K, IQ3S  , IQ3S  , IQ3S, IQ3S , IQ3S , IQ3S  , IQ3S, IQ3S , IQ3S ]),
+        (Ft::MostlyIQ3M  , [IQ3S  , Q6K, IQ3S  , IQ3S  , Q4K , Q4K  , Q4K  , IQ3S  , Q4K , IQ3S , IQ3S ]),
+        (Ft::MostlyIQ4NL , [IQ4NL , Q6K, IQ4NL , IQ4NL , IQ4NL, IQ4NL, IQ4NL, IQ4NL , Q5K , IQ4NL, IQ4NL]),
+        (Ft::MostlyIQ4XS , [IQ4XS , Q6K, IQ4XS , IQ4XS , IQ4XS, IQ4XS, IQ4XS, IQ4XS , Q5K , IQ4XS, IQ4XS]),
+    ];
+    assert_eq!(table.len(), PRESETS.len());
+    for (ftype, expected) in table {
+        let policy = policy(ftype);
+        for (name, expected) in zip(NAMES, expected) {
+            assert_eq!(ty(&policy, name), expected, "{ftype:?} {name}");
+        }
+    }
+
+    // 分组查询注意力、重要性矩阵、混合专家和 Falcon 架构的规则
+    let gqa = |ftype| Policy {
+        n_gqa: 4,
+        ..policy(ftype)
+    };
+    let imatrix = |ftype| Policy {
+        has_imatrix: true,
+        ..policy(ftype)
+    };
+    let expert = |ftype| Policy {
+        n_expert: 8,
+        ..policy(ftype)
+    };
+    let falcon = |ftype| Policy {
+        falcon: true,
+        ..policy(ftype)
+    };
+    #[rustfmt::skip]
+    let table = [
+        (gqa(Ft::MostlyQ2K)        , NAMES[5] , Q4K   ),
+        (gqa(Ft::MostlyQ2KS)       , NAMES[5] , Q4K   ),
+        (gqa(Ft::MostlyIQ3XXS)     , NAMES[5] , Q4K   ),
+        (gqa(Ft::MostlyIQ3XS)      , NAMES[5] , Q4K   ),
+        (gqa(Ft::MostlyIQ3S)       , NAMES[5] , Q4K   ),
+        (gqa(Ft::MostlyIQ4NL)      , NAMES[5] , Q5K   ),
+        (gqa(Ft::MostlyIQ4XS)      , NAMES[5] , Q5K   ),
+        (imatrix(Ft::MostlyIQ3XXS) , NAMES[5] , IQ3XXS),
+        (imatrix(Ft::MostlyIQ3XXS) , NAMES[8] , IQ3XXS),
+        (imatrix(Ft::MostlyIQ3XXS) , NAMES[9] , IQ3XXS),
+        (imatrix(Ft::MostlyIQ4NL)  , NAMES[8] , IQ4NL ),
+        (imatrix(Ft::MostlyIQ4XS)  , NAMES[8] , IQ4XS ),
+        (imatrix(Ft::MostlyQ4_0)   , NAMES[8] , Q4_1  ),
+        (imatrix(Ft::MostlyQ4_0)   , NAMES[9] , Q4_0  ),
+        (imatrix(Ft::MostlyQ5_0)   , NAMES[8] , Q5_1  ),
+        (imatrix(Ft::MostlyQ5_0)   , NAMES[9] , Q5_0  ),
+        (expert(Ft::MostlyQ4KM)    , NAMES[3] , Q8_0  ),
+        (expert(Ft::MostlyQ4KM)    , NAMES[5] , Q8_0  ),
+        (expert(Ft::MostlyQ4KM)    , NAMES[6] , Q5K   ),
+        (expert(Ft::MostlyQ5KM)    , NAMES[6] , Q5K   ),
+        (expert(Ft::MostlyIQ3M)    , NAMES[9] , IQ3S  ),
+        (expert(Ft::MostlyIQ3M)    , NAMES[10], Q4K   ),
+        (falcon(Ft::MostlyQ4KM)    , NAMES[1] , Q8_0  ),
+        (falcon(Ft::MostlyQ3KM)    , NAMES[6] , Q3K   ),
+        (falcon(Ft::MostlyQ3KM)    , NAMES[9] , Q3K   ),
+        (falcon(Ft::MostlyQ3KM)    , NAMES[10], Q4K   ),
+        (falcon(Ft::MostlyQ3KL)    , NAMES[6] , Q4K   ),
+        (falcon(Ft::MostlyQ3KL)    , NAMES[9] , Q4K   ),
+        (falcon(Ft::MostlyQ4KM)    , NAMES[8] , Q6K   ),
+        (falcon(Ft::MostlyQ4KM)    , NAMES[9] , Q4K   ),
+        (falcon(Ft::MostlyQ4KM)    , NAMES[10], Q5K   ),
+        (falcon(Ft::MostlyQ4KS)    , NAMES[8] , Q4K   ),
+    ];
+    for (policy, name, expected) in table {
+        assert_eq!(ty(&policy, name), expected, "{:?} {name}", policy.ftype);
+    }
+}
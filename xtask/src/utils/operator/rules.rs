@@ -0,0 +1,135 @@
+use super::{CastError, Content, cast::parse_type};
+use ggus::{GGmlType as Ty, GGufImatrix};
+use regex::Regex;
+use std::{fmt, sync::Arc};
+
+/// 按张量名和形状选择类型的转换规则。
+///
+/// 规则写作 `<regex>[@<shape>]=<type>`，多条规则以 `;` 分隔，按顺序匹配，第一条匹配的规则生效。
+/// 正则表达式须匹配完整的张量名，单独的 `*` 匹配任何张量。
+/// 形状写作以 `x` 分隔的各维长度，`*` 匹配任意长度，如 `*x4096` 匹配第二维为 4096 的二维张量。
+pub(crate) struct CastRule {
+    pattern: String,
+    name: Option<Regex>,
+    shape: Option<Vec<Option<u64>>>,
+    ty: Ty,
+}
+
+impl CastRule {
+    /// 解析以 `;` 分隔的规则列表。
+    pub fn parse_list(rules: &str) -> Result<Vec<Self>, CastError> {
+        rules
+            .split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    fn parse(rule: &str) -> Result<Self, CastError> {
+        let err = |reason: String| CastError::Rule(rule.into(), reason);
+        let Some((pattern, ty)) = rule.rsplit_once('=') else {
+            return Err(err("should be `<regex>[@<shape>]=<type>`".into()));
+        };
+        let (name, shape) = match pattern.trim().rsplit_once('@') {
+            Some((name, shape)) => (name, Some(shape)),
+            None => (pattern.trim(), None),
+        };
+        Ok(Self {
+            pattern: pattern.trim().into(),
+            name: match name {
+                "*" => None,
+                name => Some(Regex::new(&format!("^(?:{name})$")).map_err(|e| err(e.to_string()))?),
+            },
+            shape: shape
+                .map(|shape| {
+                    shape
+                        .split('x')
+                        .map(|d| match d.trim() {
+                            "*" => Ok(None),
+                            d => d
+                                .parse()
+                                .map(Some)
+                                .map_err(|_| err(format!("invalid dimension `{d}`"))),
+                        })
+                        .collect()
+                })
+                .transpose()?,
+            ty: parse_type(ty).map_err(|e| err(e.to_string()))?,
+        })
+    }
+
+    fn matches(&self, name: &str, shape: &[u64]) -> bool {
+        self.name.as_ref().is_none_or(|r| r.is_match(name))
+            && self.shape.as_ref().is_none_or(|pattern| {
+                pattern.len() == shape.len()
+                    && pattern
+                        .iter()
+                        .zip(shape)
+                        .all(|(p, d)| p.is_none_or(|p| p == *d))
+            })
+    }
+}
+
+impl fmt::Display for CastRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}={:?}", self.pattern, self.ty)
+    }
+}
+
+impl Content<'_> {
+    pub(super) fn cast_rules(
+        &mut self,
+        rules: Vec<CastRule>,
+        imatrix: Option<Arc<GGufImatrix>>,
+    ) -> Result<(), CastError> {
+        // 匹配所有张量的规则决定文件名中的编码
+        let main = rules
+            .iter()
+            .find(|r| r.name.is_none() && r.shape.is_none())
+            .map(|r| r.ty);
+        self.cast_(main, imatrix.as_deref(), |name, shape| {
+            rules.iter().find(|r| r.matches(name, shape)).map(|r| r.ty)
+        })
+    }
+}
+
+#[test]
+fn test_rules() {
+    let rules = CastRule::parse_list(
+        r"blk\.[0-3]\..*ffn_down.*=q8_0; .*_norm.*=f32; .*@*x4096=f16; *=q4_0;",
+    )
+    .unwrap();
+    assert_eq!(rules.len(), 4);
+    let ty =
+        |name: &str, shape: &[u64]| rules.iter().find(|r| r.matches(name, shape)).map(|r| r.ty);
+    assert_eq!(ty("blk.2.ffn_down.weight", &[11008, 4096]), Some(Ty::Q8_0));
+    assert_eq!(ty("blk.12.ffn_down.weight", &[11008, 4096]), Some(Ty::F16));
+    assert_eq!(ty("blk.12.ffn_down.weight", &[11008, 2048]), Some(Ty::Q4_0));
+    assert_eq!(ty("blk.0.attn_norm.weight", &[4096]), Some(Ty::F32));
+    // 正则表达式须匹配完整的名字
+    assert_eq!(ty("xblk.0.ffn_down.weight", &[11008, 2048]), Some(Ty::Q4_0));
+
+    let text = rules.iter().map(ToString::to_string).collect::<Vec<_>>();
+    assert_eq!(
+        text,
+        [
+            r"blk\.[0-3]\..*ffn_down.*=Q8_0",
+            ".*_norm.*=F32",
+            ".*@*x4096=F16",
+            "*=Q4_0"
+        ]
+    );
+}
+
+#[test]
+fn test_invalid_rules() {
+    let err = |rules: &str| match CastRule::parse_list(rules) {
+        Err(CastError::Rule(rule, _)) => rule,
+        _ => unreachable!(),
+    };
+    assert_eq!(err("*=f32; blk.*"), "blk.*");
+    assert_eq!(err("blk.(=q4_0"), "blk.(=q4_0");
+    assert_eq!(err("*@4096xn=f16"), "*@4096xn=f16");
+    assert_eq!(err("*=q9_0"), "*=q9_0");
+}
@@ -1,6 +1,6 @@
 use std::{
     fmt,
-    num::ParseIntError,
+    num::{NonZeroUsize, ParseIntError},
     path::PathBuf,
     str::{FromStr, from_utf8},
 };
@@ -22,6 +22,9 @@ pub(crate) struct OutputArgs {
     /// If set, tensor data will not be written to output files
     #[clap(long)]
     no_data: bool,
+    /// Max number of threads used to cast tensors and write shards
+    #[clap(long, short = 'j')]
+    threads: Option<NonZeroUsize>,
 }
 
 pub(crate) struct OutputConfig {
@@ -30,6 +33,7 @@ pub(crate) struct OutputConfig {
     pub shard_max_file_size: MemSize,
     pub shard_no_tensor_first: bool,
     pub write_data: bool,
+    pub threads: Option<usize>,
 }
 
 impl From<OutputArgs> for OutputConfig {
@@ -40,6 +44,7 @@ impl From<OutputArgs> for OutputConfig {
             max_bytes,
             no_tensor_first,
             no_data,
+            threads,
         } = args;
         Self {
             dir: output_dir,
@@ -49,6 +54,7 @@ impl From<OutputArgs> for OutputConfig {
             }),
             shard_no_tensor_first: no_tensor_first,
             write_data: !no_data,
+            threads: threads.map(NonZeroUsize::get),
         }
     }
 }
@@ -1,14 +1,16 @@
 use super::{Content, DataPromise, MetaValue, Tensor};
-use ggus::{GENERAL_ALIGNMENT, GGuf, GGufError, GGufFileName};
+use ggus::{GENERAL_ALIGNMENT, GGuf, GGufEndian, GGufError, GGufFileName};
+use memmap2::MmapMut;
 
 impl<'a> Content<'a> {
     pub fn new(
         name: GGufFileName<'a>,
-        files: impl IntoIterator<Item = &'a [u8]> + 'a,
+        files: impl IntoIterator<Item = (&'a [u8], GGufEndian)> + 'a,
     ) -> Result<Self, GGufError> {
         std::thread::scope(|s| {
             let mut ans = Self {
                 name,
+                endian: GGufEndian::NATIVE,
                 alignment: 0,
                 meta_kvs: Default::default(),
                 tensors: Default::default(),
@@ -16,21 +18,24 @@ impl<'a> Content<'a> {
 
             for thread in files
                 .into_iter()
-                .map(|data| s.spawn(|| GGuf::new(data)))
+                .map(|(data, endian)| s.spawn(move || GGuf::new(data).map(|gguf| (gguf, endian))))
                 .collect::<Vec<_>>()
                 .into_iter()
             {
                 thread
                     .join()
                     .unwrap()
-                    .and_then(|gguf| ans.merge_file(gguf))?;
+                    .and_then(|(gguf, endian)| ans.merge_file(gguf, endian))?;
             }
 
             Ok(ans)
         })
     }
 
-    fn merge_file(&mut self, others: GGuf<'a>) -> Result<(), GGufError> {
+    /// 合并一个文件的元数据和张量，`endian` 为文件原本的字节序。
+    ///
+    /// 非本机字节序文件的元数据已转换为本机字节序，张量数据在使用时才复制并转换。
+    fn merge_file(&mut self, others: GGuf<'a>, endian: GGufEndian) -> Result<(), GGufError> {
         self.alignment = self.alignment.max(others.alignment);
 
         for (k, kv) in others.meta_kvs {
@@ -47,11 +52,22 @@ impl<'a> Content<'a> {
         }
 
         for (name, tensor) in others.tensors {
-            let tensor = tensor.to_info();
+            let tensor = tensor.to_info().map_err(GGufError::Reading)?;
+            let ty = tensor.ty();
+            let data = &others.data[tensor.offset()..][..tensor.nbytes()];
             let tensor = Tensor {
-                ty: tensor.ty(),
+                ty,
                 shape: tensor.shape().to_vec(),
-                data: DataPromise::Borrowed(&others.data[tensor.offset()..][..tensor.nbytes()]),
+                data: if endian == GGufEndian::NATIVE {
+                    DataPromise::Borrowed(data)
+                } else {
+                    DataPromise::lazy(move || {
+                        let mut ans = MmapMut::map_anon(data.len()).unwrap();
+                        ans.copy_from_slice(data);
+                        ty.swap_bytes(&mut ans).unwrap();
+                        ans
+                    })
+                },
             };
             if self.tensors.insert(name.into(), tensor).is_some() {
                 return Err(GGufError::DuplicateTensorName(name.into()));
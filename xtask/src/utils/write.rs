@@ -1,11 +1,13 @@
-﻿use super::{Content, FileInfo, OutputConfig};
-use ggus::{GGufFileHeader, GGufFileSimulator, GGufFileWriter};
-use std::{fs::File, io, iter::zip, path::PathBuf, thread};
+﻿use super::{Content, FileInfo, MetaValue, OutputConfig, Tensor};
+use ggus::{GGufEndian, GGufFileHeader, GGufFileSimulator, GGufFileWriter};
+use indexmap::IndexMap;
+use std::{borrow::Cow, fs::File, io, iter::zip, path::PathBuf, sync::Mutex, thread};
 
 impl Content<'_> {
     pub fn write_files(self, out: OutputConfig) -> Result<Vec<FileInfo>, io::Error> {
         let Self {
             name,
+            endian,
             alignment,
             meta_kvs,
             tensors,
@@ -16,6 +18,7 @@ impl Content<'_> {
             shard_max_file_size,
             shard_no_tensor_first,
             write_data,
+            threads,
         } = out;
 
         // 规划分片方案
@@ -58,46 +61,69 @@ impl Content<'_> {
             .split_n(shards.len())
             .map(|name| dir.join(name.to_string()));
 
-        // 并行写入文件
+        // 并行写入文件，同时写入的分片数不超过线程数
 
         std::fs::create_dir_all(&dir)?;
+        let n_shards = shards.len();
+        let n_workers = threads.map_or(n_shards, |n| n.min(n_shards));
+        let tasks = Mutex::new(zip(shards, path).enumerate());
+        let results = Mutex::new((0..n_shards).map(|_| None).collect::<Vec<_>>());
         thread::scope(|s| {
-            zip(shards, path)
-                .enumerate()
-                .map(|(i, (tensors, path))| {
-                    s.spawn(move || -> Result<FileInfo, io::Error> {
-                        let path = find_path(path);
-
-                        let n_meta_kvs = if i == 0 { meta_kvs.len() + 1 } else { 1 };
-                        let n_tensors = tensors.len();
-                        let header = GGufFileHeader::new(3, n_tensors as _, n_meta_kvs as _);
-
-                        let mut writer = GGufFileWriter::new(File::create(&path)?, header)?;
-                        writer.write_alignment(alignment)?;
-                        if i == 0 {
-                            for (k, v) in meta_kvs {
-                                writer.write_meta_kv(k, v.ty, &v.value)?;
-                            }
-                        }
-
-                        let mut writer = writer.finish(write_data);
-                        for (name, tensor) in tensors {
-                            writer.write_tensor(&name, tensor.ty, &tensor.shape, tensor.data)?;
-                        }
-                        writer.finish().map(|n_bytes| FileInfo {
-                            path,
-                            n_tensors,
-                            n_meta_kvs,
-                            n_bytes,
-                        })
-                    })
-                })
-                .collect::<Vec<_>>()
-                .into_iter()
-                .map(|j| j.join().unwrap())
-                .collect::<Result<Vec<_>, _>>()
-        })
+            for _ in 0..n_workers {
+                s.spawn(|| {
+                    loop {
+                        let Some((i, (tensors, path))) = tasks.lock().unwrap().next() else {
+                            break;
+                        };
+                        let ans =
+                            write_shard(i, tensors, path, endian, alignment, meta_kvs, write_data);
+                        results.lock().unwrap()[i] = Some(ans)
+                    }
+                });
+            }
+        });
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(Option::unwrap)
+            .collect()
+    }
+}
+
+fn write_shard(
+    i: usize,
+    tensors: Vec<(Cow<str>, Tensor)>,
+    path: PathBuf,
+    endian: GGufEndian,
+    alignment: usize,
+    meta_kvs: &IndexMap<Cow<str>, MetaValue>,
+    write_data: bool,
+) -> Result<FileInfo, io::Error> {
+    let path = find_path(path);
+
+    let n_meta_kvs = if i == 0 { meta_kvs.len() + 1 } else { 1 };
+    let n_tensors = tensors.len();
+    let header = GGufFileHeader::new(3, n_tensors as _, n_meta_kvs as _);
+
+    let mut writer = GGufFileWriter::with_endian(File::create(&path)?, header, endian)?;
+    writer.write_alignment(alignment)?;
+    if i == 0 {
+        for (k, v) in meta_kvs {
+            writer.write_meta_kv(k, v.ty, &v.value)?;
+        }
+    }
+
+    let mut writer = writer.finish(write_data);
+    for (name, tensor) in tensors {
+        writer.write_tensor(&name, tensor.ty, &tensor.shape, tensor.data)?;
     }
+    writer.finish().map(|n_bytes| FileInfo {
+        path,
+        n_tensors,
+        n_meta_kvs,
+        n_bytes,
+    })
 }
 
 /// 找到一个未被占用的文件名